@@ -1,5 +1,8 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use gameshell::{predicates as pred, types::Type, Evaluate, Evaluator, GameShell, IncConsumer};
+use gameshell::{
+    evaluator::ParseScratch, predicates as pred, types::Type, Evaluate, Evaluator, GameShell,
+    IncConsumer,
+};
 
 // ---
 
@@ -8,8 +11,10 @@ criterion_group!(
     benches,
     initialize,
     interpret,
+    interpret_single_token,
     interpret_complex,
-    interpret_nested
+    interpret_nested,
+    interpret_nested_with_scratch
 );
 
 // ---
@@ -57,6 +62,24 @@ fn interpret(c: &mut Criterion) {
     });
 }
 
+fn interpret_single_token(c: &mut Criterion) {
+    let mut eval = Evaluator::new(0u8);
+
+    fn handler(_context: &mut u8, _args: &[Type]) -> Result<String, String> {
+        Ok("".into())
+    }
+
+    eval.register((&[("status", None)], handler)).unwrap();
+
+    c.bench_function("single token call", move |b| {
+        b.iter(|| {
+            eval.interpret_single(black_box("status"))
+                .unwrap()
+                .unwrap();
+        })
+    });
+}
+
 fn interpret_complex(c: &mut Criterion) {
     let mut eval = Evaluator::new(0u8);
 
@@ -95,3 +118,24 @@ fn interpret_nested(c: &mut Criterion) {
         })
     });
 }
+
+fn interpret_nested_with_scratch(c: &mut Criterion) {
+    let mut eval = Evaluator::new(0u8);
+
+    fn handler(_context: &mut u8, _args: &[Type]) -> Result<String, String> {
+        Ok("123".into())
+    }
+
+    eval.register((&[("call", pred::ANY_I32)], handler))
+        .unwrap();
+    eval.register((&[("call-2", None)], handler)).unwrap();
+
+    let mut scratch = ParseScratch::default();
+    c.bench_function("nested call with scratch", move |b| {
+        b.iter(|| {
+            eval.interpret_single_with(black_box("call (call-2)"), &mut scratch)
+                .unwrap()
+                .unwrap();
+        })
+    });
+}
@@ -1,4 +1,4 @@
-use crate::{Evaluator, Feedback, PartialParse, PartialParseOp};
+use crate::{batch_response, ok_response, Evaluator, Feedback, PartialParse, PartialParseOp};
 use metac::Evaluate;
 use slog::{error, info, warn, Logger};
 use std::str::from_utf8;
@@ -24,24 +24,27 @@ pub async fn tokio_apply<C>(mut evaluator: Evaluator<'_, C>, mut stream: TcpStre
             let _ = stream
                 .write_all(b"DecodeError(\"Internal buffer is full, disconnecting\")")
                 .await;
+            let _ = stream.flush().await;
             return;
         }
 
         let amount = match stream.read(&mut buf[begin..]).await {
             Ok(n) if n == 0 => {
                 info!(log, "Remote gameshell has disconnected");
+                let _ = stream.flush().await;
                 return;
             }
             Ok(n) => n,
             Err(err) => {
                 error!(log, "An error occurred while reading from the stream"; "error" => ?err);
+                let _ = stream.flush().await;
                 return;
             }
         };
 
         for ch in buf[begin..(begin + amount)].iter() {
             begin += 1;
-            match parser.parse_increment(*ch) {
+            match evaluator.parse_increment(&mut parser, *ch) {
                 PartialParseOp::Discard => {
                     shift = begin;
                 }
@@ -49,13 +52,32 @@ pub async fn tokio_apply<C>(mut evaluator: Evaluator<'_, C>, mut stream: TcpStre
                     let string = from_utf8(&buf[shift..begin]);
                     if let Ok(string) = string {
                         info!(log, "Got input"; "string" => string);
+                        if evaluator.batching() {
+                            let response =
+                                batch_response(&evaluator.interpret_batch_presented(string));
+                            if stream.write_all(response.as_bytes()).await.is_err()
+                                || stream.flush().await.is_err()
+                            {
+                                return;
+                            }
+                            shift = begin;
+                            continue;
+                        }
                         let result = evaluator.interpret_single(string);
                         match result {
                             Ok(result) => {
+                                let warnings = evaluator
+                                    .warnings()
+                                    .iter()
+                                    .map(|warning| format!("Warning({:?})", warning))
+                                    .collect::<String>();
+                                if stream.write_all(warnings.as_bytes()).await.is_err() {
+                                    return;
+                                }
                                 match result {
                                     Feedback::Ok(res) => {
-                                        let result = format!("Ok({:?})", res);
-                                        if stream.write_all(result.as_bytes()).await.is_err() {
+                                        let result = ok_response(&res, evaluator.presentation());
+                                        if stream.write_all(&result).await.is_err() {
                                             return;
                                         }
                                     }
@@ -122,7 +144,7 @@ mod tests {
     };
     use slog::{o, Discard, Logger};
     use std::{
-        net::{Ipv4Addr, SocketAddrV4},
+        net::{Ipv4Addr, Shutdown, SocketAddrV4},
         str::from_utf8,
     };
     use tokio::{
@@ -145,6 +167,38 @@ mod tests {
         }
     }
 
+    // Run `evaluator` on a loopback connection, send it all of `script`, then close the sending
+    // side and collect everything written back until the evaluator disconnects
+    fn run_session<C>(evaluator: Evaluator<'_, C>, script: &[u8]) -> String {
+        let mut sched = Builder::new()
+            .basic_scheduler()
+            .enable_io()
+            .build()
+            .unwrap();
+
+        let script = script.to_vec();
+        sched.block_on(async move {
+            let loopback = Ipv4Addr::new(127, 0, 0, 1);
+            let socket = SocketAddrV4::new(loopback, 0);
+
+            let mut listen = TcpListener::bind(socket).await.unwrap();
+            let address = listen.local_addr().unwrap();
+
+            let client = tokio::spawn(async move {
+                let mut stream = TcpStream::connect(address).await.unwrap();
+                stream.write_all(&script).await.unwrap();
+                stream.shutdown(Shutdown::Write).unwrap();
+                let mut response = vec![];
+                stream.read_to_end(&mut response).await.unwrap();
+                response
+            });
+
+            let (stream, _) = listen.accept().await.unwrap();
+            tokio_apply(evaluator, stream, Logger::root(Discard, o!())).await;
+            String::from_utf8(client.await.unwrap()).unwrap()
+        })
+    }
+
     #[test]
     fn basic_io() {
         let mut sched = Builder::new()
@@ -258,4 +312,59 @@ mod tests {
             .await;
         });
     }
+
+    #[test]
+    fn whole_session() {
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok(context.to_string())
+        }
+
+        let mut evaluator = Evaluator::new(0u32);
+        evaluator.register((&[("count", None)], handler)).unwrap();
+
+        assert_eq!(
+            "Ok(\"1\")Ok(\"\")Ok(\"2\")Err(\"Unrecognized mapping: nope\")Ok(\"3\")",
+            run_session(evaluator, b"count\nset x 1\ncount\nnope\ncount\n")
+        );
+    }
+
+    #[test]
+    fn warnings_precede_result() {
+        fn mode(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+            if input.first() == Some(&"1") {
+                crate::warn("mode 1 is deprecated");
+            }
+            out.push(Type::String(input[0].into()));
+            Decision::Accept(1)
+        }
+
+        fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        const MODE: Decider<Type, String> = Decider {
+            description: "<mode>",
+            decider: mode,
+        };
+
+        let mut evaluator = Evaluator::new(());
+        evaluator
+            .register((&[("mode", Some(&MODE))], handler))
+            .unwrap();
+        assert_eq!(
+            "Warning(\"mode 1 is deprecated\")Ok(\"\")Ok(\"\")",
+            run_session(evaluator, b"mode 1\nmode 2\n")
+        );
+
+        let mut evaluator = Evaluator::new(());
+        evaluator
+            .register((&[("mode", Some(&MODE))], handler))
+            .unwrap();
+        evaluator.set_batching(true);
+        assert_eq!(
+            "[Warning(\"mode 1 is deprecated\"), Ok(\"\"), Ok(\"\")]",
+            run_session(evaluator, b"mode 1; mode 2\n")
+        );
+    }
 }
@@ -0,0 +1,235 @@
+//! Interactive console on top of an [Evaluator], reading statements line by line
+use crate::{Evaluator, Feedback, Presentation};
+use metac::{Evaluate, PartialParse, PartialParseOp};
+use std::io::{self, BufRead, Write};
+
+/// A line-based console that prompts for statements and writes their results
+///
+/// Statements with unclosed parentheses continue on the next line, which is prompted for with
+/// `... `. Successful results are written as they are, errors are prefixed with `Error: `.
+/// Warnings raised by deciders precede the result, prefixed with `Warning: `. Reading stops at the
+/// end of the input.
+pub struct Console<R: BufRead, W: Write> {
+    reader: R,
+    writer: W,
+    prompt: String,
+    echo: bool,
+}
+
+impl<R: BufRead, W: Write> Console<R, W> {
+    /// Create a console reading from `reader` and writing to `writer`.
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader,
+            writer,
+            prompt: "> ".into(),
+            echo: false,
+        }
+    }
+
+    /// Set the prompt written before reading a statement. Defaults to `"> "`.
+    pub fn set_prompt(&mut self, prompt: &str) {
+        self.prompt = prompt.into();
+    }
+
+    /// Set whether each line read is written back after the prompt, which is useful when the
+    /// input is not a terminal. Defaults to `false`.
+    pub fn set_echo(&mut self, echo: bool) {
+        self.echo = echo;
+    }
+
+    /// Read and interpret statements until the input ends.
+    pub fn run<C>(&mut self, evaluator: &mut Evaluator<'_, C>) -> io::Result<()> {
+        let mut parser = PartialParse::default();
+        let mut statement = Vec::new();
+        let mut discarded = false;
+        let mut line = String::new();
+        loop {
+            if statement.is_empty() {
+                self.writer.write_all(self.prompt.as_bytes())?;
+            } else {
+                self.writer.write_all(b"... ")?;
+            }
+            self.writer.flush()?;
+
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            if !line.ends_with('\n') {
+                line.push('\n');
+            }
+            if self.echo {
+                self.writer.write_all(line.as_bytes())?;
+            }
+
+            for byte in line.bytes() {
+                match evaluator.parse_increment(&mut parser, byte) {
+                    PartialParseOp::Ready => {
+                        if discarded {
+                            writeln![
+                                self.writer,
+                                "Error: Right parenthesis encountered with no matching left parenthesis"
+                            ]?;
+                        } else {
+                            // Only whole lines are split, so this is valid UTF-8
+                            let string = String::from_utf8_lossy(&statement);
+                            if !string.trim().is_empty() {
+                                let result = evaluator.interpret_single(&string);
+                                if result.is_ok() {
+                                    for warning in evaluator.warnings() {
+                                        writeln![self.writer, "Warning: {}", warning]?;
+                                    }
+                                }
+                                self.write_result(result, evaluator.presentation())?;
+                            }
+                        }
+                        statement.clear();
+                        discarded = false;
+                    }
+                    PartialParseOp::Unready => statement.push(byte),
+                    PartialParseOp::Discard => {
+                        statement.clear();
+                        discarded = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_result(
+        &mut self,
+        result: Result<Feedback, metac::ParseError>,
+        presentation: Option<&Presentation>,
+    ) -> io::Result<()> {
+        match result {
+            Ok(Feedback::Ok(res)) => match presentation {
+                Some(Presentation::Raw) => writeln![
+                    self.writer,
+                    "<{} bytes of binary data>",
+                    base64::decode(&res).map_or(0, |bytes| bytes.len())
+                ],
+                _ if res.is_empty() => Ok(()),
+                _ => writeln![self.writer, "{}", res],
+            },
+            Ok(Feedback::Err(res)) => writeln![self.writer, "Error: {}", res],
+            Err(parse_error) => writeln![
+                self.writer,
+                "Error: Unable to parse input: {:?}",
+                parse_error
+            ],
+        }
+    }
+}
+
+/// Run a console on standard input and output until standard input ends, see [Console].
+pub fn run_stdio<C>(evaluator: &mut Evaluator<'_, C>) -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    Console::new(stdin.lock(), stdout.lock()).run(evaluator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{predicates::*, types::Type};
+    use std::{io::Cursor, str::from_utf8};
+
+    #[test]
+    fn console_session() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn add(context: &mut u32, args: &[Type]) -> Result<String, String> {
+            *context += 1;
+            let mut sum = 0;
+            for arg in args {
+                if let Type::I32(value) = arg {
+                    sum += value;
+                }
+            }
+            Ok(sum.to_string())
+        }
+
+        fn quiet(_: &mut u32, _: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        eval.register((&[("add", MANY_I32)], add)).unwrap();
+        eval.register((&[("quiet", None)], quiet)).unwrap();
+
+        let input = Cursor::new("add 1 2\n\nadd (add 1\n2) 3\nquiet\nfäil\n)\nadd 4");
+        let mut output = vec![];
+        let mut console = Console::new(input, &mut output);
+        console.set_prompt("$ ");
+        console.set_echo(true);
+        console.run(&mut eval).unwrap();
+
+        assert_eq!(
+            "$ add 1 2\n\
+             3\n\
+             $ \n\
+             $ add (add 1\n\
+             ... 2) 3\n\
+             6\n\
+             $ quiet\n\
+             $ fäil\n\
+             Error: Unrecognized mapping: fäil\n\
+             $ )\n\
+             Error: Right parenthesis encountered with no matching left parenthesis\n\
+             $ add 4\n\
+             4\n\
+             $ ",
+            from_utf8(&output).unwrap()
+        );
+        assert_eq!(4, *eval.context());
+    }
+
+    #[test]
+    fn without_echo() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(_: &mut u32, _: &[Type]) -> Result<String, String> {
+            Ok("pong".into())
+        }
+
+        eval.register((&[("ping", None)], handler)).unwrap();
+
+        let mut output = vec![];
+        Console::new(Cursor::new("ping\nping\n"), &mut output)
+            .run(&mut eval)
+            .unwrap();
+        assert_eq!("> pong\n> pong\n> ", from_utf8(&output).unwrap());
+    }
+
+    #[test]
+    fn warnings_precede_result() {
+        let mut eval = Evaluator::new(());
+
+        fn mode(input: &[&str], out: &mut cmdmat::SVec<Type>) -> cmdmat::Decision<String> {
+            if input.first() == Some(&"1") {
+                crate::warn("mode 1 is deprecated");
+            }
+            out.push(Type::String(input[0].into()));
+            cmdmat::Decision::Accept(1)
+        }
+
+        fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("set".into())
+        }
+
+        const MODE: cmdmat::Decider<Type, String> = cmdmat::Decider {
+            description: "<mode>",
+            decider: mode,
+        };
+        eval.register((&[("mode", Some(&MODE))], handler)).unwrap();
+
+        let mut output = vec![];
+        Console::new(Cursor::new("mode 1\nmode 2\n"), &mut output)
+            .run(&mut eval)
+            .unwrap();
+        assert_eq!(
+            "> Warning: mode 1 is deprecated\nset\n> set\n> ",
+            from_utf8(&output).unwrap()
+        );
+    }
+}
@@ -1,8 +1,289 @@
 //! Core virtual machine.
-use crate::{types::Type, Feedback};
-use cmdmat::{self, Either, LookError, Mapping, RegError, Spec};
-use metac::{Data, Evaluate, ParseError};
+#[cfg(feature = "with-futures")]
+use crate::jobs::{JobId, JobRegistry};
+use crate::{
+    predicates::{RuntimeDecider, SomeDec, EXAMPLES},
+    schema::Schema,
+    tree::{parse_tree, Tree},
+    types::{Type, TypeKind},
+    Feedback, Presentation,
+};
+use cmdmat::{
+    self, Decider, Decision, Either, FinWithArgs, Finalizer, LookError, Mapping, RegError, SVec,
+    Spec,
+};
+#[cfg(feature = "with-futures")]
+use futures::{stream::LocalBoxStream, StreamExt};
+use metac::{Data, Evaluate, ParseError, PartialParse, PartialParseOp};
 use regex::Regex;
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    io::{self, BufRead, Write},
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    static DRY_RUN: Cell<bool> = const { Cell::new(false) };
+    static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static EVENTS: RefCell<Vec<Box<dyn Any + Send>>> = const { RefCell::new(Vec::new()) };
+    static CONFIRMED: Cell<bool> = const { Cell::new(false) };
+    static NEEDS_CONFIRMATION: Cell<bool> = const { Cell::new(false) };
+    static PRESENTATION: RefCell<Option<(Presentation, String)>> = const { RefCell::new(None) };
+}
+
+/// Check whether the handler currently running was invoked in dry-run mode, see
+/// [Evaluator::set_dry_run].
+///
+/// Handlers should compute their result as usual but avoid any side effects when this returns
+/// `true`. Outside of a handler this always returns `false`.
+pub fn is_dry_run() -> bool {
+    DRY_RUN.with(Cell::get)
+}
+
+/// Attach a warning to the command being looked up, for use inside a decider that accepts its
+/// input but wants to tell the user something about it, such as a deprecated value.
+///
+/// Warnings from deciders of the command that ends up running are available from
+/// [Evaluator::warnings] after the statement has been interpreted. Warnings from deciders of
+/// lookups that fail are dropped.
+///
+/// ```
+/// use gameshell::{cmdmat::{Decider, Decision, SVec}, types::Type, warn, Evaluate, Evaluator};
+///
+/// fn mode(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+///     match input.first() {
+///         Some(&"fast") => {}
+///         Some(&"quick") => warn("'quick' is deprecated, use 'fast'"),
+///         _ => return Decision::Deny("expected fast".into()),
+///     }
+///     out.push(Type::String("fast".into()));
+///     Decision::Accept(1)
+/// }
+///
+/// fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+///     Ok("".into())
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// eval.register((&[("mode", Some(&Decider { description: "<mode>", decider: mode }))], handler))
+///     .unwrap();
+/// eval.interpret_single("mode quick").unwrap().unwrap();
+/// assert_eq!(&["'quick' is deprecated, use 'fast'".to_string()], eval.warnings());
+/// ```
+pub fn warn(message: impl Into<String>) {
+    WARNINGS.with(|warnings| warnings.borrow_mut().push(message.into()));
+}
+
+/// Emit an event from inside a handler, for use when a command should trigger something in the
+/// host beyond its string result.
+///
+/// Events are passed to the [EventEmitter] set with [Evaluator::set_event_emitter] once the
+/// handler returns, whether it succeeded or not. Events are dropped when no emitter is set, when
+/// the handler runs in dry-run mode, or when called outside of a handler.
+///
+/// ```
+/// use gameshell::{emit, predicates::*, types::Type, Evaluate, Evaluator};
+/// use std::sync::mpsc::channel;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Explosion(f32);
+///
+/// fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+///     if let Type::F32(radius) = args[0] {
+///         emit(Explosion(radius));
+///     }
+///     Ok("".into())
+/// }
+///
+/// let (tx, rx) = channel();
+/// let mut eval = Evaluator::new(());
+/// eval.set_event_emitter(Some(Box::new(tx)));
+/// eval.register((&[("explode", ANY_F32)], handler)).unwrap();
+/// eval.interpret_single("explode 2.5").unwrap().unwrap();
+/// assert_eq!(Explosion(2.5), rx.try_recv().unwrap());
+/// ```
+pub fn emit<E: Any + Send>(event: E) {
+    EVENTS.with(|events| events.borrow_mut().push(Box::new(event)));
+}
+
+// Label the result a handler is about to return, the label is dropped if the handler returns
+// anything else
+pub(crate) fn present(presentation: Presentation, result: &str) {
+    PRESENTATION.with(|current| *current.borrow_mut() = Some((presentation, result.to_string())));
+}
+
+/// Ask the user to confirm the command before its handler takes effect, for use inside a handler
+/// of a destructive command such as `delete-all`.
+///
+/// Returns the result the handler should reply with, `"type 'confirm' to proceed"`. The evaluator
+/// keeps the handler and its arguments, and runs the handler again if the next top-level
+/// statement is `confirm`, this time with [is_confirmed] returning `true`. Any other statement
+/// cancels the command and fails with `"cancelled"` instead of being interpreted. The permission
+/// level is checked again when confirming. Confirmation is not requested in dry-run mode or by
+/// nested commands, whose result the user does not see.
+///
+/// ```
+/// use gameshell::{is_confirmed, needs_confirmation, types::Type, Evaluate, Evaluator};
+///
+/// fn delete_all(items: &mut Vec<u32>, _: &[Type]) -> Result<String, String> {
+///     if !is_confirmed() {
+///         return needs_confirmation();
+///     }
+///     items.clear();
+///     Ok("deleted".into())
+/// }
+///
+/// let mut eval = Evaluator::new(vec![1, 2, 3]);
+/// eval.register((&[("delete-all", None)], delete_all)).unwrap();
+/// assert_eq!(
+///     Ok("type 'confirm' to proceed".into()),
+///     eval.interpret_single("delete-all").unwrap()
+/// );
+/// assert_eq!(3, eval.context().len());
+/// assert_eq!(Ok("deleted".into()), eval.interpret_single("confirm").unwrap());
+/// assert!(eval.context().is_empty());
+/// ```
+pub fn needs_confirmation() -> Feedback {
+    NEEDS_CONFIRMATION.with(|flag| flag.set(true));
+    Ok("type 'confirm' to proceed".into())
+}
+
+/// Check whether the handler currently running was confirmed by the user, see
+/// [needs_confirmation]. Outside of a handler this always returns `false`.
+pub fn is_confirmed() -> bool {
+    CONFIRMED.with(Cell::get)
+}
+
+/// Receiver of the events emitted by handlers, see [emit]
+///
+/// This is implemented for [mpsc::Sender], which forwards events of its item type and ignores
+/// the rest.
+pub trait EventEmitter {
+    /// Handle a single event emitted by a handler.
+    fn emit(&mut self, event: Box<dyn Any + Send>);
+}
+
+/// Register a table of command specifications on an [Evaluator] or [crate::GameShell], failing
+/// to compile if two specifications share a literal path
+///
+/// Expands to a call to `register_many`, so duplicates that would otherwise make it return
+/// [RegError::FinalizerAlreadyExists] are caught when building. The paths are checked in a
+/// constant, see [has_duplicate_path], so each must be written out as a constant expression.
+///
+/// ```
+/// use gameshell::{predicates::*, register_table, types::Type, Evaluate, Evaluator};
+///
+/// fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+///     Ok("".into())
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// register_table!(eval, [
+///     (&[("log", None), ("level", ANY_U8)], handler),
+///     (&[("log", None), ("file", ANY_STRING)], handler),
+/// ])
+/// .unwrap();
+/// assert_eq!(Ok("".into()), eval.interpret_single("log level 3").unwrap());
+/// ```
+///
+/// Registering `log level` twice fails to compile, even with different deciders:
+///
+/// ```compile_fail
+/// use gameshell::{predicates::*, register_table, types::Type, Evaluator};
+///
+/// fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+///     Ok("".into())
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// register_table!(eval, [
+///     (&[("log", None), ("level", ANY_U8)], handler),
+///     (&[("log", None), ("level", ANY_STRING)], handler),
+/// ])
+/// .unwrap();
+/// ```
+#[macro_export]
+macro_rules! register_table {
+    ($target:expr, [$(($path:expr, $handler:expr)),* $(,)?]) => {{
+        const _: () = assert!(
+            !$crate::evaluator::has_duplicate_path(&[$($path),*]),
+            "Duplicate command path in register_table"
+        );
+        $target.register_many(&[$(($path, $handler)),*])
+    }};
+}
+
+/// Check whether two of `paths` consist of the same literals, ignoring their deciders
+///
+/// This is a `const fn` so that [crate::register_table] can reject duplicates at compile time.
+pub const fn has_duplicate_path(paths: &[&[(&str, SomeDec)]]) -> bool {
+    let mut first = 0;
+    while first < paths.len() {
+        let mut second = first + 1;
+        while second < paths.len() {
+            if same_literals(paths[first], paths[second]) {
+                return true;
+            }
+            second += 1;
+        }
+        first += 1;
+    }
+    false
+}
+
+const fn same_literals(left: &[(&str, SomeDec)], right: &[(&str, SomeDec)]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    let mut index = 0;
+    while index < left.len() {
+        let (left, right) = (left[index].0.as_bytes(), right[index].0.as_bytes());
+        if left.len() != right.len() {
+            return false;
+        }
+        let mut byte = 0;
+        while byte < left.len() {
+            if left[byte] != right[byte] {
+                return false;
+            }
+            byte += 1;
+        }
+        index += 1;
+    }
+    true
+}
+
+/// Splits a statement into the tokens that are looked up in the command tree, see
+/// [Evaluator::set_tokenizer]
+pub trait Tokenizer {
+    /// Split `statement` into tokens. Each token is matched like an atom of the default syntax.
+    fn tokenize(&self, statement: &str) -> Result<Vec<String>, ParseError>;
+}
+
+impl<E: Any + Send> EventEmitter for mpsc::Sender<E> {
+    fn emit(&mut self, event: Box<dyn Any + Send>) {
+        if let Ok(event) = event.downcast::<E>() {
+            let _ = self.send(*event);
+        }
+    }
+}
+
+type TimedRunner<C> = fn(Finalizer<Type, C>, &mut C, SVec<Type>, Duration) -> Feedback;
+
+// The result of a statement in a batch along with its presentation and the warnings raised by
+// its deciders
+pub(crate) type Presented = (
+    Result<Feedback, ParseError>,
+    Option<Presentation>,
+    Vec<String>,
+);
 
 /// The virtual machine that runs commands
 ///
@@ -10,24 +291,176 @@ use regex::Regex;
 /// according to the specified mapping table, which can be manipulated via [Evaluator::register] and
 /// [Evaluator::register_many].
 ///
-/// Builting commands are `autocomplete`, which tries to look ahead by 1 query, and `?` which lists
-/// all possible queries.
+/// Builting commands are `autocomplete`, which tries to look ahead by 1 query, `?` which lists
+/// all possible queries, `help` which shows the usage and examples of a command, see
+/// [Evaluator::register_with_examples], `use` which sets a prefix for subsequent queries, `set`
+/// and `get` which manipulate variables, `test-decider` which shows how each decider handles a
+/// query without running the handler, `explain` which also shows the tokens, the command path,
+/// and nested commands without running any of them, stopping at deciders that depend on the
+/// result of a nested command, and `debug-args` which toggles
+/// [Evaluator::set_debug_args]. With [Evaluator::set_ansi] enabled, `clear` returns the escape
+/// sequence that clears a terminal. With a job registry set, `jobs` lists spawned jobs and
+/// `kill <id>` cancels one, see `Evaluator::set_jobs`. While a command waits for confirmation,
+/// `confirm` runs it, see [needs_confirmation].
 pub struct Evaluator<'a, C> {
     mapping: Mapping<'a, Type, String, C>,
+    specs: Vec<(Vec<&'static str>, Finalizer<Type, C>)>,
     context: C,
     current_depth: usize,
     max_depth: usize,
+    operations: usize,
+    operation_budget: Option<usize>,
+    lookup_time_budget: Option<Duration>,
+    lookup_deadline: Option<Instant>,
+    substitutions: usize,
+    max_substitutions: usize,
+    substitution_enabled: bool,
+    hidden: Vec<Vec<&'static str>>,
+    show_hidden: bool,
+    case_insensitive: bool,
+    permissions: Vec<(Vec<&'static str>, u32)>,
+    permission_level: u32,
+    cooldowns: Vec<Cooldown>,
+    clock: Box<dyn Fn() -> Instant + Send + 'a>,
+    handler_timeout: Option<(Duration, TimedRunner<C>)>,
+    max_output: Option<(usize, OutputOverflow)>,
+    prefix: Vec<String>,
+    allowed_types: Option<Vec<TypeKind>>,
+    batching: bool,
+    examples: HashMap<&'static str, &'static str>,
+    default_handler: Option<Finalizer<Type, C>>,
+    dry_run: bool,
+    debug_args: bool,
+    partial_results: bool,
+    ansi: bool,
+    replay_sink: Option<Box<dyn Write + Send + 'a>>,
+    event_emitter: Option<Box<dyn EventEmitter + Send + 'a>>,
+    tokenizer: Option<Box<dyn Tokenizer + Send + 'a>>,
+    stop_on_handler_error: bool,
+    newline_closes_in_paren: bool,
+    variables: HashMap<String, String>,
+    global_variables: Option<GlobalVariables>,
+    decider_usage: HashMap<&'static str, Vec<Vec<String>>>,
+    metadata: Vec<(Vec<&'static str>, HashMap<&'static str, &'static str>)>,
+    command_examples: Vec<(Vec<&'static str>, Vec<&'static str>)>,
+    scratch: Option<ParseScratch>,
+    warnings: Vec<String>,
+    presentation: Option<Presentation>,
+    pending_confirmation: Option<(Vec<String>, FinWithArgs<'a, Type, C>)>,
+    trace: Option<Vec<String>>,
+    #[cfg(feature = "with-futures")]
+    jobs: Option<(JobRegistry, u32)>,
+    runtime_deciders: Vec<RuntimeDecider>,
 }
 
+struct Cooldown {
+    path: Vec<&'static str>,
+    duration: Duration,
+    last_invocation: Option<Instant>,
+}
+
+/// Buffers for the arguments of a statement, reused across calls to
+/// [Evaluator::interpret_single_with]
+///
+/// Each nesting level of a statement gets its own buffers, so a scratch that has seen a
+/// statement nested `n` levels deep holds `n + 1` sets of buffers.
+#[derive(Default)]
+pub struct ParseScratch {
+    frames: Vec<ScratchFrame>,
+}
+
+#[derive(Default)]
+struct ScratchFrame {
+    content: Vec<String>,
+    spare: Vec<String>,
+}
+
+impl ScratchFrame {
+    // Push a copy of `string`, reusing the allocation of a string from an earlier statement
+    fn push(&mut self, string: &str) {
+        let mut reused = self.spare.pop().unwrap_or_default();
+        reused.clear();
+        reused.push_str(string);
+        self.content.push(reused);
+    }
+}
+
+/// What to do with a handler result longer than the limit, see [Evaluator::set_max_output_len]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputOverflow {
+    /// Cut the result at the limit and append `"...(truncated)"`
+    Truncate,
+    /// Replace the result with an error
+    Error,
+}
+
+/// A top-level statement as given and the command it resolved to, see
+/// [Evaluator::interpret_traced]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace {
+    /// The statement as given
+    pub input: String,
+    /// The literals and arguments of the command that ran, after nested commands are substituted
+    /// and the prefix set by `use` is prepended. Empty if no registered command ran.
+    pub resolved: Vec<String>,
+}
+
+/// Variables shared between evaluators, see [Evaluator::set_global_variables]
+pub type GlobalVariables = Arc<RwLock<HashMap<String, String>>>;
+
 impl<'a, C> Evaluator<'a, C> {
     /// Create a new VM which owns a `context`. The context is used in handler functions and can be
     /// mutated.
     pub fn new(context: C) -> Self {
         Self {
             mapping: Mapping::default(),
+            specs: Vec::new(),
             context,
             current_depth: 0,
             max_depth: 100,
+            operations: 0,
+            operation_budget: None,
+            lookup_time_budget: None,
+            lookup_deadline: None,
+            substitutions: 0,
+            max_substitutions: usize::MAX,
+            substitution_enabled: true,
+            hidden: Vec::new(),
+            show_hidden: false,
+            case_insensitive: false,
+            permissions: Vec::new(),
+            permission_level: 0,
+            cooldowns: Vec::new(),
+            clock: Box::new(Instant::now),
+            handler_timeout: None,
+            max_output: None,
+            prefix: Vec::new(),
+            allowed_types: None,
+            batching: false,
+            examples: EXAMPLES.iter().cloned().collect(),
+            default_handler: None,
+            dry_run: false,
+            debug_args: false,
+            partial_results: false,
+            ansi: false,
+            replay_sink: None,
+            event_emitter: None,
+            tokenizer: None,
+            stop_on_handler_error: false,
+            newline_closes_in_paren: false,
+            variables: HashMap::new(),
+            global_variables: None,
+            decider_usage: HashMap::new(),
+            metadata: Vec::new(),
+            command_examples: Vec::new(),
+            scratch: None,
+            warnings: Vec::new(),
+            presentation: None,
+            pending_confirmation: None,
+            trace: None,
+            #[cfg(feature = "with-futures")]
+            jobs: None,
+            runtime_deciders: Vec::new(),
         }
     }
 
@@ -36,6 +469,436 @@ impl<'a, C> Evaluator<'a, C> {
         self.max_depth = limit;
     }
 
+    /// Get the recursion limit of nested calls.
+    pub fn recursion_limit(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Get the current nesting depth, this is 0 unless a nested command is being evaluated.
+    pub fn depth(&self) -> usize {
+        self.current_depth
+    }
+
+    /// Check whether nesting `levels` more calls would hit the recursion limit. Useful for code
+    /// that drives an evaluator and wants to avoid the generic recursion error.
+    pub fn would_exceed_limit(&self, levels: usize) -> bool {
+        self.current_depth + levels > self.max_depth
+    }
+
+    /// Restrict the types that deciders may produce. Commands whose parsed arguments contain any
+    /// other type are rejected before their handler runs.
+    pub fn set_allowed_types(&mut self, kinds: &[TypeKind]) {
+        self.allowed_types = Some(kinds.to_vec());
+    }
+
+    /// Remove any restriction set by [Evaluator::set_allowed_types].
+    pub fn allow_all_types(&mut self) {
+        self.allowed_types = None;
+    }
+
+    /// Set whether [crate::GameShell] and `tokio_apply` interpret each incoming frame using
+    /// [Evaluator::interpret_batch]. Defaults to `false`.
+    pub fn set_batching(&mut self, batching: bool) {
+        self.batching = batching;
+    }
+
+    /// Check whether incoming frames are interpreted as batches, see [Evaluator::set_batching].
+    pub fn batching(&self) -> bool {
+        self.batching
+    }
+
+    /// Set the maximum number of substitutions, such as `(get x)`, that a single top-level
+    /// statement may perform. Nested substitutions count towards the same limit.
+    ///
+    /// Unlike the recursion limit, this also catches statements with many sibling
+    /// substitutions. Unlimited by default.
+    pub fn set_max_substitutions_per_statement(&mut self, limit: usize) {
+        self.max_substitutions = limit;
+    }
+
+    /// Set whether nested commands such as `(get x)` are evaluated. Defaults to `true`.
+    ///
+    /// When disabled, the body of every parenthesized argument is passed to the decider as
+    /// literal text, so `(get x)` becomes the argument `get x`. Literals such as `(#text)` still
+    /// have their leading `#` removed, so both forms produce the same argument.
+    pub fn set_substitution_enabled(&mut self, enabled: bool) {
+        self.substitution_enabled = enabled;
+    }
+
+    /// Check whether nested commands are evaluated, see [Evaluator::set_substitution_enabled].
+    pub fn substitution_enabled(&self) -> bool {
+        self.substitution_enabled
+    }
+
+    /// Get the maximum number of substitutions per top-level statement.
+    pub fn max_substitutions_per_statement(&self) -> usize {
+        self.max_substitutions
+    }
+
+    /// Set the maximum number of commands, including nested ones, that a single top-level command
+    /// may evaluate. `None` removes the limit, which is the default.
+    ///
+    /// When the budget is exhausted the command fails with `"Operation budget exhausted: <n>"`.
+    pub fn set_operation_budget(&mut self, budget: Option<usize>) {
+        self.operation_budget = budget;
+    }
+
+    /// Set the time that looking up the commands of a single top-level statement, including
+    /// nested ones, may take. `None` removes the limit, which is the default.
+    ///
+    /// The time is checked before each decider runs, so a single slow decider is not
+    /// interrupted. Once the budget is exceeded the statement fails with
+    /// `"command parsing timed out"`. This guards shells taking untrusted input against deciders
+    /// that are slow on some inputs.
+    pub fn set_lookup_time_budget(&mut self, budget: Option<Duration>) {
+        self.lookup_time_budget = budget;
+    }
+
+    /// Get the number of commands evaluated so far by the current or last top-level command.
+    pub fn operations(&self) -> usize {
+        self.operations
+    }
+
+    /// Clear the state that only concerns a single top-level command, such as the number of
+    /// operations counted against [Evaluator::set_operation_budget] and of substitutions
+    /// counted against [Evaluator::set_max_substitutions_per_statement].
+    ///
+    /// This is called automatically at the start of every top-level command. Persistent state
+    /// such as registered commands, variables, and the prefix is left as is.
+    pub fn reset_transient(&mut self) {
+        self.current_depth = 0;
+        self.operations = 0;
+        self.substitutions = 0;
+        self.warnings.clear();
+        self.presentation = None;
+        // Deciders also run outside of `lookup`, such as when checking permissions, whose
+        // warnings are never collected
+        WARNINGS.with(|warnings| warnings.borrow_mut().clear());
+        self.lookup_deadline = self
+            .lookup_time_budget
+            .map(|budget| Instant::now() + budget);
+    }
+
+    /// Get the warnings raised by deciders during the current or last top-level statement, see
+    /// [warn].
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Get how the result of the current or last top-level statement is to be presented, `None`
+    /// for an ordinary string, see [crate::raw_feedback].
+    ///
+    /// Only the handler of the top-level command can set this, results of nested commands are
+    /// always passed on as strings.
+    pub fn presentation(&self) -> Option<&Presentation> {
+        self.presentation.as_ref()
+    }
+
+    /// Limit the length in bytes of successful handler results, `None` removes the limit, which is
+    /// the default.
+    ///
+    /// Longer results are handled according to `overflow`. Truncation happens at a character
+    /// boundary, so the result may be slightly shorter than `max` before the marker is appended.
+    /// Errors returned by handlers are never limited.
+    pub fn set_max_output_len(&mut self, max: Option<usize>, overflow: OutputOverflow) {
+        self.max_output = max.map(|max| (max, overflow));
+    }
+
+    /// Set whether [Evaluator::interpret_all] and `interpret_multiple` stop at the first statement
+    /// that results in a [Feedback::Err]. Defaults to `false`.
+    ///
+    /// This only concerns handler errors: statements that fail to parse always stop
+    /// interpretation with a [ParseError], regardless of this setting.
+    pub fn set_stop_on_handler_error(&mut self, stop: bool) {
+        self.stop_on_handler_error = stop;
+    }
+
+    /// Set whether every newline ends a statement, even inside parentheses. Defaults to `false`,
+    /// where a statement continues across lines until its parentheses are closed.
+    ///
+    /// When enabled, a statement with parentheses left open at the end of a line fails with
+    /// [ParseError::DanglingLeftParenthesis]. This applies to [Evaluator::interpret_all],
+    /// `interpret_multiple`, [crate::GameShell], [crate::console::Console], and `tokio_apply`.
+    pub fn set_newline_closes_in_paren(&mut self, closes: bool) {
+        self.newline_closes_in_paren = closes;
+    }
+
+    // Feed a byte of a statement stream to `parser`, which ends the statement at every newline if
+    // `newline_closes_in_paren` is set
+    pub(crate) fn parse_increment(&self, parser: &mut PartialParse, byte: u8) -> PartialParseOp {
+        if self.newline_closes_in_paren && byte == b'\n' {
+            *parser = PartialParse::default();
+            return PartialParseOp::Ready;
+        }
+        parser.parse_increment(byte)
+    }
+
+    /// Interpret newline-separated statements, returning the feedback of each statement.
+    ///
+    /// Newlines inside parentheses do not separate statements unless
+    /// [Evaluator::set_newline_closes_in_paren] is enabled. Interpretation stops with an
+    /// error if a statement fails to parse, and also stops after a statement results in a
+    /// [Feedback::Err] if [Evaluator::set_stop_on_handler_error] is enabled.
+    pub fn interpret_all(&mut self, code: &str) -> Result<Vec<Feedback>, ParseError> {
+        let mut results = vec![];
+        let mut start = 0;
+        let mut depth = 0usize;
+        let mut seen_non_ws = false;
+        for (idx, ch) in code.char_indices() {
+            let boundary = if depth == 0 {
+                seen_non_ws
+            } else {
+                self.newline_closes_in_paren
+            };
+            if ch == '\n' && boundary {
+                seen_non_ws = false;
+                depth = 0;
+                let res = self.interpret_single(&code[start..idx])?;
+                let stop = self.stop_on_handler_error && res.is_err();
+                results.push(res);
+                if stop {
+                    return Ok(results);
+                }
+                start = idx + 1;
+            } else if ch == '(' {
+                depth += 1;
+            } else if ch == ')' {
+                if depth == 0 {
+                    return Err(ParseError::PrematureRightParenthesis);
+                }
+                depth -= 1;
+            } else if !ch.is_whitespace() {
+                seen_non_ws = true;
+            }
+        }
+        if seen_non_ws {
+            results.push(self.interpret_single(&code[start..])?);
+        }
+        if results.is_empty() {
+            Err(ParseError::NothingToParse)
+        } else {
+            Ok(results)
+        }
+    }
+
+    /// Interpret a single statement like `interpret_single`, also returning a [Trace] of the
+    /// statement as given and the command it resolved to, for audit logs that need both.
+    pub fn interpret_traced(&mut self, code: &str) -> (Result<Feedback, ParseError>, Trace) {
+        self.trace = Some(Vec::new());
+        let res = self.interpret_single(code);
+        let trace = Trace {
+            input: code.into(),
+            resolved: self.trace.take().unwrap_or_default(),
+        };
+        (res, trace)
+    }
+
+    /// Interpret a single statement like `interpret_single`, keeping the argument buffers in
+    /// `scratch` so that later calls can reuse them.
+    ///
+    /// Reusing the same scratch avoids allocating the argument strings of each statement,
+    /// including those of nested statements, once the scratch has seen statements of similar
+    /// shape. Parsing the statement itself and the results of handlers still allocate.
+    pub fn interpret_single_with(
+        &mut self,
+        code: &str,
+        scratch: &mut ParseScratch,
+    ) -> Result<Feedback, ParseError> {
+        self.scratch = Some(std::mem::take(scratch));
+        let res = self.interpret_single(code);
+        *scratch = self.scratch.take().unwrap_or_default();
+        res
+    }
+
+    /// Interpret multiple `;`-separated statements, returning the result of each statement.
+    ///
+    /// Separators inside parentheses are part of the nested command, and empty statements are
+    /// skipped. Unlike `interpret_multiple`, every statement is evaluated even if a previous one
+    /// failed.
+    pub fn interpret_batch(&mut self, frame: &str) -> Vec<Result<Feedback, ParseError>> {
+        self.interpret_batch_presented(frame)
+            .into_iter()
+            .map(|(result, _, _)| result)
+            .collect()
+    }
+
+    // Interpret a batch like `interpret_batch`, keeping the presentation and the warnings of each
+    // result
+    pub(crate) fn interpret_batch_presented(&mut self, frame: &str) -> Vec<Presented> {
+        let mut results = vec![];
+        let mut depth = 0usize;
+        let mut start = 0;
+        for (idx, ch) in frame.char_indices() {
+            match ch {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                ';' if depth == 0 => {
+                    if !frame[start..idx].trim().is_empty() {
+                        results.push(self.interpret_presented(&frame[start..idx]));
+                    }
+                    start = idx + 1;
+                }
+                _ => {}
+            }
+        }
+        if !frame[start..].trim().is_empty() {
+            results.push(self.interpret_presented(&frame[start..]));
+        }
+        results
+    }
+
+    // Interpret a statement of a batch, the next statement resets the warnings of this one. A
+    // statement that fails to parse is never evaluated, so it has no warnings of its own
+    fn interpret_presented(&mut self, statement: &str) -> Presented {
+        let result = self.interpret_single(statement);
+        let warnings = if result.is_ok() {
+            self.warnings.clone()
+        } else {
+            vec![]
+        };
+        (result, self.presentation.take(), warnings)
+    }
+
+    /// Set a handler for statements whose first token is not a registered command, `None` removes
+    /// the handler. The handler receives every token of the statement as a [Type::String].
+    ///
+    /// Builtin commands take precedence over the default handler.
+    pub fn set_default_handler(&mut self, handler: Option<Finalizer<Type, C>>) {
+        self.default_handler = handler;
+    }
+
+    /// Set whether handlers run in dry-run mode. Handlers query this flag using [is_dry_run].
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Set whether commands show their parsed arguments instead of running their handlers, one
+    /// argument per line as its [TypeKind] followed by its value. Also toggled by the builtin
+    /// `debug-args on|off`. Nested commands are not run either, so the outer command receives
+    /// their rendered arguments. Defaults to `false`.
+    pub fn set_debug_args(&mut self, debug_args: bool) {
+        self.debug_args = debug_args;
+    }
+
+    /// Check whether a command is waiting for the user to type `confirm`, see
+    /// [needs_confirmation].
+    pub fn pending_confirmation(&self) -> bool {
+        self.pending_confirmation.is_some()
+    }
+
+    /// Drop the command waiting for the user to type `confirm`, if any, so that the next
+    /// statement is interpreted as usual.
+    pub fn cancel_confirmation(&mut self) {
+        self.pending_confirmation = None;
+    }
+
+    /// Set whether output is shown on a terminal that understands ANSI escape sequences, which
+    /// enables the builtin `clear`. Defaults to `false`.
+    ///
+    /// Only enable this for local consoles. Clients of [crate::GameShell] and `tokio_apply` are
+    /// programs that would receive the escape sequence as part of the result.
+    pub fn set_ansi(&mut self, ansi: bool) {
+        self.ansi = ansi;
+    }
+
+    /// Check whether ANSI output is enabled, see [Evaluator::set_ansi].
+    pub fn ansi(&self) -> bool {
+        self.ansi
+    }
+
+    /// Set the receiver of events emitted by handlers using [emit], `None` drops all events, which
+    /// is the default.
+    pub fn set_event_emitter(&mut self, emitter: Option<Box<dyn EventEmitter + Send + 'a>>) {
+        self.event_emitter = emitter;
+    }
+
+    /// Set the registry listed by the builtin `jobs` and cancelled from by the builtin
+    /// `kill <id>`, both of which require a permission level of at least `level`, see
+    /// [Evaluator::set_permission_level]. `None` disables both builtins, which is the default.
+    ///
+    /// Handlers spawn jobs through a clone of the registry kept in their context.
+    #[cfg(feature = "with-futures")]
+    pub fn set_jobs(&mut self, jobs: Option<JobRegistry>, level: u32) {
+        self.jobs = jobs.map(|jobs| (jobs, level));
+    }
+
+    /// Set the tokenizer used by `interpret_single` in place of the default syntax, where
+    /// parentheses nest commands. `None` restores the default syntax, which is the default.
+    ///
+    /// Tokens are dispatched through the same command tree, so a `key=value` or shell-quoted
+    /// syntax can reuse registered commands. Nested commands are only available in the default
+    /// syntax. A tokenizer producing no tokens fails with [ParseError::NothingToParse].
+    pub fn set_tokenizer(&mut self, tokenizer: Option<Box<dyn Tokenizer + Send + 'a>>) {
+        self.tokenizer = tokenizer;
+    }
+
+    /// Set whether an error in a nested command reports the results of the nested commands of the
+    /// same statement that completed before it, as in `"error (partial: [a, b])"`. Defaults to
+    /// `false`.
+    pub fn set_partial_results(&mut self, partial_results: bool) {
+        self.partial_results = partial_results;
+    }
+
+    /// Record every top-level statement and its feedback to `sink`, `None` stops recording.
+    ///
+    /// Each statement produces two lines: `> ` followed by the statement, and `< ` followed by
+    /// the feedback, as in `< Ok("result")`. Both are escaped like Rust string literals such that
+    /// they never contain a newline or other control characters, and are read back by
+    /// [read_replay]. Nested statements are not recorded separately.
+    pub fn set_replay_sink(&mut self, sink: Option<Box<dyn Write + Send + 'a>>) {
+        self.replay_sink = sink;
+    }
+
+    /// Interpret every statement of a log written to a replay sink, see
+    /// [Evaluator::set_replay_sink], returning each statement whose feedback differs from the
+    /// recorded feedback as `(statement, recorded, actual)`.
+    ///
+    /// A statement that fails to parse has the feedback `Err("Unable to parse input: ...")`.
+    pub fn replay(&mut self, log: impl BufRead) -> io::Result<Vec<(String, Feedback, Feedback)>> {
+        let mut differences = vec![];
+        for (statement, recorded) in read_replay(log)? {
+            let actual = match self.interpret_single(&statement) {
+                Ok(feedback) => feedback,
+                Err(parse_error) => {
+                    Feedback::Err(format!["Unable to parse input: {:?}", parse_error])
+                }
+            };
+            if actual != recorded {
+                differences.push((statement, recorded, actual));
+            }
+        }
+        Ok(differences)
+    }
+
+    /// Set a variable local to this evaluator, as the builtin `set` command does.
+    pub fn set_variable(&mut self, name: &str, value: &str) {
+        self.variables.insert(name.into(), value.into());
+    }
+
+    /// Get a variable, looking first at the variables local to this evaluator and then at the
+    /// global variables.
+    pub fn variable(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.variables.get(name) {
+            return Some(value.clone());
+        }
+        self.global_variables
+            .as_ref()
+            .and_then(|global| global.read().ok()?.get(name).cloned())
+    }
+
+    /// Set the global variables this evaluator reads from. The builtin `set` command never writes
+    /// to the global variables, only the owner of the [GlobalVariables] can change them.
+    pub fn set_global_variables(&mut self, global: Option<GlobalVariables>) {
+        self.global_variables = global;
+    }
+
+    /// Set an example value for deciders with the given description. The example is shown when
+    /// such a decider denies its input. The builtin predicates come with examples, see
+    /// [crate::predicates::EXAMPLES].
+    pub fn set_example(&mut self, description: &'static str, example: &'static str) {
+        self.examples.insert(description, example);
+    }
+
     /// Get a reference to this machine's context.
     pub fn context(&self) -> &C {
         &self.context
@@ -48,7 +911,21 @@ impl<'a, C> Evaluator<'a, C> {
 
     /// Register a handler function for a command.
     pub fn register(&mut self, spec: Spec<'_, 'a, Type, String, C>) -> Result<(), RegError> {
-        self.mapping.register(spec)
+        let path = spec.0;
+        self.mapping.register(spec)?;
+        self.specs
+            .push((path.iter().map(|(literal, _)| *literal).collect(), spec.1));
+        let literals = path
+            .iter()
+            .map(|(literal, _)| (*literal).to_string())
+            .collect::<Vec<_>>();
+        for decider in path.iter().filter_map(|(_, decider)| *decider) {
+            let users = self.decider_usage.entry(decider.description).or_default();
+            if !users.contains(&literals) {
+                users.push(literals.clone());
+            }
+        }
+        Ok(())
     }
 
     /// Register an array of handler functions for a command, see [Evaluator::register].
@@ -56,91 +933,886 @@ impl<'a, C> Evaluator<'a, C> {
         &mut self,
         spec: &[Spec<'_, 'a, Type, String, C>],
     ) -> Result<(), RegError> {
-        self.mapping.register_many(spec)
+        for subspec in spec {
+            self.register(*subspec)?;
+        }
+        Ok(())
     }
 
-    // Parse subcommands recursively into a vector of strings, fail with feedback otherwise
-    fn parse_subcommands(&mut self, cmds: &[Data]) -> Result<Vec<String>, String> {
-        let mut content: Vec<String> = Vec::new();
-        for cmd in cmds {
-            match cmd {
-                Data::Atom(string) => {
-                    content.push((*string).into());
-                }
-                Data::Command(string) => {
-                    if let Some('#') = string.chars().next() {
-                        content.push((string[1..]).into());
+    /// Make the commands at and below `to` also available at `from`, so that `ls` can stand for
+    /// `list`.
+    ///
+    /// The commands are copied rather than shared: cmdmat nodes own their children, so `from`
+    /// cannot point into the subtree of `to`. Commands registered below `to` afterwards are
+    /// therefore not aliased, and the alias can be removed with [Evaluator::unregister] without
+    /// affecting `to`. The last literal of `from` takes the decider of `to`. Permission levels,
+    /// hidden flags, and cooldowns are copied along with the commands, where the alias keeps its
+    /// own cooldown. If no command lies at or below `to`, nothing is registered. If any of the
+    /// copies conflicts with an existing command, nothing is registered either.
+    pub fn alias(&mut self, from: &[&'static str], to: &[&str]) -> Result<(), RegError> {
+        let mut created = HashSet::new();
+        let mut copies = vec![];
+        for (literals, finalizer) in &self.specs {
+            if !literals.starts_with(to) {
+                continue;
+            }
+            let path = from
+                .iter()
+                .chain(&literals[to.len()..])
+                .cloned()
+                .collect::<Vec<_>>();
+            let spec = (1..=path.len())
+                .map(|length| {
+                    // Only the spec creating a node gives its decider, as when registering
+                    let decider = if length >= from.len() && created.insert(path[..length].to_vec())
+                    {
+                        self.resolve_path(&literals[..length - from.len() + to.len()])
+                            .and_then(|node| *node.decider())
                     } else {
-                        if self.current_depth == self.max_depth {
-                            return Err(format!["Recursion limit reached: {}", self.max_depth]);
-                        }
-                        self.current_depth += 1;
-                        let res = self.interpret_single(string);
-                        self.current_depth -= 1;
-                        match res {
-                            Ok(Feedback::Ok(string)) => {
-                                content.push(string);
-                            }
-                            Ok(Feedback::Err(res)) => {
-                                return Err(res);
-                            }
-                            Err(ParseError::DanglingLeftParenthesis) => {
-                                return Err("Dangling left parenthesis".into());
-                            }
-                            Err(ParseError::PrematureRightParenthesis) => {
-                                return Err("Right parenthesis encountered with no matching left parenthesis".into());
-                            }
-                            Err(ParseError::NothingToParse) => {
-                                return Err("No input to parse".into());
-                            }
-                        }
-                    }
-                }
+                        None
+                    };
+                    (path[length - 1], decider)
+                })
+                .collect::<Vec<_>>();
+            copies.push((spec, *finalizer));
+        }
+        if let Some(err) = copies.iter().find_map(|(spec, _)| self.conflict(spec)) {
+            return Err(err);
+        }
+        for (spec, finalizer) in &copies {
+            self.register((spec, *finalizer))?;
+        }
+
+        let rename = |path: &Vec<&'static str>| {
+            if path.starts_with(to) {
+                Some(
+                    from.iter()
+                        .chain(&path[to.len()..])
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                )
+            } else {
+                None
             }
+        };
+        let hidden = self.hidden.iter().filter_map(rename).collect::<Vec<_>>();
+        self.hidden.extend(hidden);
+        let permissions = self
+            .permissions
+            .iter()
+            .filter_map(|(path, level)| Some((rename(path)?, *level)))
+            .collect::<Vec<_>>();
+        self.permissions.extend(permissions);
+        let cooldowns = self
+            .cooldowns
+            .iter()
+            .filter_map(|cooldown| {
+                Some(Cooldown {
+                    path: rename(&cooldown.path)?,
+                    duration: cooldown.duration,
+                    last_invocation: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        self.cooldowns.extend(cooldowns);
+        Ok(())
+    }
+
+    // The error that registering the literals and deciders of `spec` would fail with, found
+    // without changing the mapping
+    fn conflict(&self, spec: &[(&str, Option<&Decider<Type, String>>)]) -> Option<RegError> {
+        let mut node = &self.mapping;
+        for (literal, decider) in spec {
+            node = match node.iter().find(|(key, _)| **key == *literal) {
+                Some(_) if decider.is_some() => return Some(RegError::DeciderAlreadyExists),
+                Some((_, entry)) => entry,
+                None => return None,
+            };
         }
-        Ok(content)
+        node.finalizer().map(|_| RegError::FinalizerAlreadyExists)
     }
 
-    fn handle_any_builtin_commands(&mut self, content: &[&str]) -> Option<Feedback> {
-        fn mapping_to_list<C>(mapping: &'_ Mapping<'_, Type, String, C>) -> Vec<String> {
-            let mut builder = vec![];
-            for (key, entry) in mapping.iter() {
-                let (parameter, spacer) = if let Some(decider) = entry.decider() {
-                    (decider.description, " ")
-                } else {
-                    (" ", "")
-                };
+    /// Remove the command at `path`, given as its literals, so that it can no longer be run.
+    ///
+    /// Commands below the path remain. Since a cmdmat mapping cannot remove commands, the mapping
+    /// is rebuilt from the remaining commands, which drops the nodes that only led to the removed
+    /// command. Whatever was registered along with the command, such as its permission level,
+    /// cooldown, metadata, or examples, is removed as well, and so is a confirmation it requested,
+    /// see [needs_confirmation]. The path is matched like in lookups, so case-insensitively with
+    /// [Evaluator::set_case_insensitive] enabled. Fails with
+    /// [LookError::UnknownMapping] if the path does not exist, and with
+    /// [LookError::FinalizerDoesNotExist] if there is no command at the path.
+    pub fn unregister(&mut self, path: &[&str]) -> Result<(), LookError<String>> {
+        let mut node = &self.mapping;
+        let mut literals = vec![];
+        for literal in path {
+            let (key, entry) = self
+                .child(node, literal)
+                .ok_or_else(|| LookError::UnknownMapping((*literal).into()))?;
+            literals.push(key);
+            node = entry;
+        }
+        if node.finalizer().is_none() {
+            return Err(LookError::FinalizerDoesNotExist);
+        }
+        let path = &literals[..];
 
-                if entry.finalizer().is_some() {
-                    builder.push(
-                        String::from(*key)
-                            + if entry.decider().is_some() { " " } else { "" }
-                            + parameter,
-                    );
-                }
-                for command in mapping_to_list(entry) {
-                    builder.push(String::from(*key) + spacer + parameter + spacer + &command);
-                }
-            }
-            builder
+        // A confirmation requested by the command can no longer be answered
+        let pending = self.pending_confirmation.as_ref().map(|(input, _)| {
+            let input = input.iter().map(|token| &token[..]).collect::<Vec<_>>();
+            self.lookup_path(&input)
+                .last()
+                .is_some_and(|command| std::ptr::eq(*command, node))
+        });
+        if pending == Some(true) {
+            self.pending_confirmation = None;
         }
-        if let Some(front) = content.first() {
-            if *front == "autocomplete" {
-                match self.mapping.partial_lookup(&content[1..]) {
-                    Ok(Either::Left(mapping)) => {
-                        let mut col = mapping
-                            .get_direct_keys()
-                            .map(|k| {
-                                let mut s = String::new() + k.literal;
-                                if k.decider.is_some() {
-                                    s += " ";
-                                }
-                                s += if k.decider.is_some() {
-                                    k.decider.unwrap().description
-                                } else {
-                                    ""
-                                };
-                                s += if k.finalizer.is_some() {
+
+        self.specs.retain(|(literals, _)| literals[..] != *path);
+        let mut mapping = Mapping::default();
+        let mut created = HashSet::new();
+        for (literals, finalizer) in &self.specs {
+            // Only the spec creating a node gives its decider, as when registering
+            let spec = (1..=literals.len())
+                .map(|length| {
+                    let prefix = &literals[..length];
+                    let decider = if created.insert(prefix) {
+                        self.resolve_path(prefix).and_then(|node| *node.decider())
+                    } else {
+                        None
+                    };
+                    (prefix[length - 1], decider)
+                })
+                .collect::<Vec<_>>();
+            mapping
+                .register((&spec, *finalizer))
+                .expect("Commands of a valid mapping conflict");
+        }
+        self.mapping = mapping;
+        self.drop_unused_deciders();
+
+        self.hidden.retain(|other| other[..] != *path);
+        self.permissions.retain(|(other, _)| other[..] != *path);
+        self.cooldowns.retain(|cooldown| cooldown.path[..] != *path);
+        self.metadata.retain(|(other, _)| other[..] != *path);
+        self.command_examples
+            .retain(|(other, _)| other[..] != *path);
+        for users in self.decider_usage.values_mut() {
+            users.retain(|other| {
+                other
+                    .iter()
+                    .map(|literal| &literal[..])
+                    .ne(path.iter().cloned())
+            });
+        }
+        self.decider_usage.retain(|_, users| !users.is_empty());
+        Ok(())
+    }
+
+    /// Register a handler function for a command whose last literal uses a decider built at
+    /// runtime, see [crate::predicates::RUNTIME_DECIDER_LIMIT].
+    ///
+    /// The evaluator keeps the decider for as long as a command uses it, so it is dropped once
+    /// every command using it is unregistered, see [Evaluator::unregister].
+    pub fn register_with_decider(
+        &mut self,
+        literals: &[&'static str],
+        decider: impl Into<RuntimeDecider>,
+        handler: Finalizer<Type, C>,
+    ) -> Result<(), RegError> {
+        let runtime = decider.into();
+        let decider = runtime.leak();
+        self.runtime_deciders.push(runtime);
+        let spec = literals
+            .iter()
+            .enumerate()
+            .map(|(idx, literal)| {
+                if idx + 1 == literals.len() {
+                    (*literal, Some(decider))
+                } else {
+                    (*literal, None)
+                }
+            })
+            .collect::<Vec<_>>();
+        let res = self.register((&spec, handler));
+        self.drop_unused_deciders();
+        res
+    }
+
+    // Drop the deciders built at runtime that no node of the mapping uses anymore, freeing their
+    // slots
+    fn drop_unused_deciders(&mut self) {
+        fn uses<C>(node: &Mapping<'_, Type, String, C>, decider: &Decider<Type, String>) -> bool {
+            node.iter().any(|(_, entry)| {
+                entry
+                    .decider()
+                    .is_some_and(|other| std::ptr::eq(other, decider))
+                    || uses(entry, decider)
+            })
+        }
+        let mapping = &self.mapping;
+        self.runtime_deciders
+            .retain(|decider| uses(mapping, decider.leak()));
+    }
+
+    /// Register a handler function for a command that is hidden from `?`, `autocomplete`, and
+    /// [Evaluator::export_schema]. The command can still be run as usual, see
+    /// [Evaluator::register].
+    pub fn register_hidden(&mut self, spec: Spec<'_, 'a, Type, String, C>) -> Result<(), RegError> {
+        let path = spec.0.iter().map(|(literal, _)| *literal).collect();
+        self.register(spec)?;
+        self.hidden.push(path);
+        Ok(())
+    }
+
+    /// Set whether hidden commands are listed by `?`, `autocomplete`, and
+    /// [Evaluator::export_schema]. Defaults to `false`.
+    pub fn set_show_hidden(&mut self, show: bool) {
+        self.show_hidden = show;
+    }
+
+    /// Set whether the literals of commands match regardless of case, so that `Quit`, `quit`,
+    /// and `QUIT` all run a command registered as `quit`. A literal matching exactly takes
+    /// precedence over one differing in case. Defaults to `false`.
+    pub fn set_case_insensitive(&mut self, enabled: bool) {
+        self.case_insensitive = enabled;
+    }
+
+    /// Register a handler function for a command that requires a permission level of at least
+    /// `level`, see [Evaluator::set_permission_level].
+    ///
+    /// While the current level is below `level`, the command does not show up in `?`,
+    /// `autocomplete`, and [Evaluator::export_schema], and running it fails with
+    /// `"insufficient permission"`. Commands registered otherwise require level 0.
+    pub fn register_with_perm(
+        &mut self,
+        spec: Spec<'_, 'a, Type, String, C>,
+        level: u32,
+    ) -> Result<(), RegError> {
+        let path = spec.0.iter().map(|(literal, _)| *literal).collect();
+        self.register(spec)?;
+        self.permissions.push((path, level));
+        Ok(())
+    }
+
+    /// Set the permission level of this session, see [Evaluator::register_with_perm]. Defaults
+    /// to 0.
+    pub fn set_permission_level(&mut self, level: u32) {
+        self.permission_level = level;
+    }
+
+    /// Get the permission level of this session.
+    pub fn permission_level(&self) -> u32 {
+        self.permission_level
+    }
+
+    /// Register a handler function for a command that may only run once per `cooldown`.
+    ///
+    /// Running the command again before the cooldown has passed since it last ran fails with
+    /// `"on cooldown, <n> s remaining"`, the remaining time being rounded up to whole seconds.
+    /// Only successful runs count, not runs in dry-run mode, failed runs, or runs that ask for
+    /// confirmation.
+    pub fn register_with_cooldown(
+        &mut self,
+        spec: Spec<'_, 'a, Type, String, C>,
+        cooldown: Duration,
+    ) -> Result<(), RegError> {
+        let path = spec.0.iter().map(|(literal, _)| *literal).collect();
+        self.register(spec)?;
+        self.cooldowns.push(Cooldown {
+            path,
+            duration: cooldown,
+            last_invocation: None,
+        });
+        Ok(())
+    }
+
+    /// Register a handler function for a command along with key/value metadata for tooling, such
+    /// as a `category` to group commands by in a command palette.
+    ///
+    /// The metadata is available from [Evaluator::meta] and in [Evaluator::export_schema].
+    /// Registering metadata for a path that already has some adds to it, replacing values of
+    /// existing keys.
+    pub fn register_with_meta(
+        &mut self,
+        spec: Spec<'_, 'a, Type, String, C>,
+        meta: HashMap<&'static str, &'static str>,
+    ) -> Result<(), RegError> {
+        let path = spec
+            .0
+            .iter()
+            .map(|(literal, _)| *literal)
+            .collect::<Vec<_>>();
+        self.register(spec)?;
+        match self.metadata.iter_mut().find(|(other, _)| *other == path) {
+            Some((_, existing)) => existing.extend(meta),
+            None => self.metadata.push((path, meta)),
+        }
+        Ok(())
+    }
+
+    /// Get the metadata of the command at `path`, given as its literals, see
+    /// [Evaluator::register_with_meta].
+    pub fn meta(&self, path: &[&str]) -> Option<&HashMap<&'static str, &'static str>> {
+        self.metadata
+            .iter()
+            .find(|(other, _)| other[..] == *path)
+            .map(|(_, meta)| meta)
+    }
+
+    /// Register a handler function for a command along with example invocations, which the
+    /// builtin `help` lists below the usage of the command.
+    ///
+    /// Registering examples for a path that already has some adds to them.
+    pub fn register_with_examples(
+        &mut self,
+        spec: Spec<'_, 'a, Type, String, C>,
+        examples: &[&'static str],
+    ) -> Result<(), RegError> {
+        let path = spec
+            .0
+            .iter()
+            .map(|(literal, _)| *literal)
+            .collect::<Vec<_>>();
+        self.register(spec)?;
+        match self
+            .command_examples
+            .iter_mut()
+            .find(|(other, _)| *other == path)
+        {
+            Some((_, existing)) => existing.extend_from_slice(examples),
+            None => self.command_examples.push((path, examples.to_vec())),
+        }
+        Ok(())
+    }
+
+    /// Get the example invocations of the command at `path`, given as its literals, see
+    /// [Evaluator::register_with_examples].
+    pub fn command_examples(&self, path: &[&str]) -> &[&'static str] {
+        self.command_examples
+            .iter()
+            .find(|(other, _)| other[..] == *path)
+            .map_or(&[], |(_, examples)| &examples[..])
+    }
+
+    /// Set the clock used for cooldowns, see [Evaluator::register_with_cooldown]. Defaults to
+    /// [Instant::now].
+    pub fn set_clock(&mut self, clock: impl Fn() -> Instant + Send + 'a) {
+        self.clock = Box::new(clock);
+    }
+
+    /// Get the prefix set by the builtin `use` command.
+    pub fn prefix(&self) -> &[String] {
+        &self.prefix
+    }
+
+    /// The number of registered commands, see [Evaluator::command_paths].
+    pub fn command_count(&self) -> usize {
+        self.command_paths().count()
+    }
+
+    /// The literals of every registered command in lexicographic order, including hidden commands
+    /// and those requiring a higher permission level.
+    pub fn command_paths(&self) -> impl Iterator<Item = Vec<&'a str>> {
+        fn collect<'a, C>(
+            mapping: &Mapping<'a, Type, String, C>,
+            path: &mut Vec<&'a str>,
+            paths: &mut Vec<Vec<&'a str>>,
+        ) {
+            for (key, entry) in mapping.iter() {
+                path.push(*key);
+                if entry.finalizer().is_some() {
+                    paths.push(path.clone());
+                }
+                collect(entry, path, paths);
+                path.pop();
+            }
+        }
+
+        let mut paths = vec![];
+        collect(&self.mapping, &mut vec![], &mut paths);
+        paths.sort_unstable();
+        paths.into_iter()
+    }
+
+    /// Check the invariants between the mapping and the state this evaluator keeps about it,
+    /// returning a description of every violation found.
+    ///
+    /// The invariants are:
+    /// * Every node except the root has a handler or at least one child, otherwise the commands
+    ///   through it can never be completed.
+    /// * No decider that may accept nothing accepts one of the literals following it, otherwise
+    ///   the literal can be taken as an argument, as with `volume up` for a `volume` taking
+    ///   any number of strings and a command `volume up`.
+    /// * With [Evaluator::set_case_insensitive] enabled, no two literals following the same node
+    ///   differ only in case, otherwise either may match.
+    /// * Every command registered as hidden, with a permission level, with metadata, or with
+    ///   examples exists in the mapping.
+    /// * Every command listed by [Evaluator::commands_using] exists in the mapping.
+    ///
+    /// Registering through the evaluator upholds these, so a violation means the mapping was
+    /// replaced or the evaluator has a bug. Useful in tests.
+    pub fn check_consistency(&self) -> Result<(), Vec<String>> {
+        fn check_nodes<C>(
+            mapping: &Mapping<'_, Type, String, C>,
+            case_insensitive: bool,
+            path: &mut Vec<String>,
+            errors: &mut Vec<String>,
+        ) {
+            let mut keys = mapping.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+            keys.sort_unstable();
+            if let Some(decider) = mapping.decider() {
+                if let Decision::Accept(0) = (decider.decider)(&[], &mut SVec::new()) {
+                    for key in &keys {
+                        if let Decision::Accept(1..) = (decider.decider)(&[key], &mut SVec::new()) {
+                            errors.push(format![
+                                "Decider {} of {} accepts the literal following it: {}",
+                                decider.description,
+                                path.join(" "),
+                                key
+                            ]);
+                        }
+                    }
+                }
+            }
+            if case_insensitive {
+                let parent = path.iter().map(|s| s.clone() + " ").collect::<String>();
+                for (index, key) in keys.iter().enumerate() {
+                    for other in &keys[index + 1..] {
+                        if fold_literal(key) == fold_literal(other) {
+                            errors.push(format![
+                                "Literals differ only in case: {}{}, {}{}",
+                                parent, key, parent, other
+                            ]);
+                        }
+                    }
+                }
+            }
+            for (key, entry) in mapping.iter() {
+                path.push((*key).into());
+                if entry.finalizer().is_none() && entry.iter().next().is_none() {
+                    errors.push(format!["No handler or children: {}", path.join(" ")]);
+                }
+                check_nodes(entry, case_insensitive, path, errors);
+                path.pop();
+            }
+        }
+
+        let mut errors = vec![];
+        check_nodes(
+            &self.mapping,
+            self.case_insensitive,
+            &mut vec![],
+            &mut errors,
+        );
+        let resolves = |path: &[&str]| self.resolve_path(path).is_some();
+        for path in &self.hidden {
+            if !resolves(path) {
+                errors.push(format!["Hidden command does not exist: {}", path.join(" ")]);
+            }
+        }
+        for (path, level) in &self.permissions {
+            if !resolves(path) {
+                errors.push(format![
+                    "Command with permission level {} does not exist: {}",
+                    level,
+                    path.join(" ")
+                ]);
+            }
+        }
+        for (path, _) in &self.metadata {
+            if !resolves(path) {
+                errors.push(format![
+                    "Command with metadata does not exist: {}",
+                    path.join(" ")
+                ]);
+            }
+        }
+        for (path, _) in &self.command_examples {
+            if !resolves(path) {
+                errors.push(format![
+                    "Command with examples does not exist: {}",
+                    path.join(" ")
+                ]);
+            }
+        }
+        let mut usage = self.decider_usage.iter().collect::<Vec<_>>();
+        usage.sort();
+        for (description, paths) in usage {
+            for path in paths {
+                let path = path.iter().map(|s| &s[..]).collect::<Vec<_>>();
+                if !resolves(&path) {
+                    errors.push(format![
+                        "Command using {} does not exist: {}",
+                        description,
+                        path.join(" ")
+                    ]);
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Get the literal paths of all commands registered with a decider described by
+    /// `description`, such as `"<f32>"`, in order of registration.
+    ///
+    /// Every command whose specification contains such a decider is listed, including hidden
+    /// ones. The path contains all literals of the specification.
+    pub fn commands_using(&self, description: &str) -> Vec<Vec<String>> {
+        self.decider_usage
+            .get(description)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Suggest text to display after the cursor while the line `partial` is being typed.
+    ///
+    /// If the line ends inside a literal, the hint is the rest of that literal, or of the prefix
+    /// shared by all literals that could complete it. Otherwise the hint is the description of
+    /// the decider awaiting arguments, or the literals that may follow, separated by `|`.
+    /// Hidden commands are never suggested.
+    pub fn ghost_hint(&self, partial: &str) -> Option<String> {
+        if partial.trim().is_empty() {
+            return None;
+        }
+        let mut tokens = partial.split_whitespace().collect::<Vec<_>>();
+        let incomplete = if partial.ends_with(char::is_whitespace) {
+            None
+        } else {
+            tokens.pop()
+        };
+        let hidden = self.hidden_nodes();
+        let mut node = &self.mapping;
+        let mut input = &tokens[..];
+        let mut out = SVec::new();
+        while let Some(token) = input.first() {
+            let (_, entry) = self
+                .child(node, token)
+                .filter(|(_, entry)| !is_hidden(entry, &hidden))?;
+            let mut consumed = 0;
+            if let Some(decider) = entry.decider() {
+                match (decider.decider)(&input[1..], &mut out) {
+                    Decision::Accept(count) if count < input.len() => consumed = count,
+                    Decision::Accept(_) => return None,
+                    Decision::Deny(_) => {
+                        return incomplete.map_or(Some(decider.description.into()), |_| None);
+                    }
+                }
+            }
+            input = &input[1 + consumed..];
+            node = entry;
+        }
+        let mut literals = node
+            .iter()
+            .filter(|(_, entry)| !is_hidden(entry, &hidden))
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+        match incomplete {
+            Some(incomplete) => {
+                literals.retain(|literal| literal.starts_with(incomplete));
+                let first = literals.first()?;
+                let common = literals.iter().fold(&first[..], |common, literal| {
+                    let len = common
+                        .chars()
+                        .zip(literal.chars())
+                        .take_while(|(a, b)| a == b)
+                        .map(|(a, _)| a.len_utf8())
+                        .sum();
+                    &common[..len]
+                });
+                Some(common[incomplete.len()..].to_string()).filter(|rest| !rest.is_empty())
+            }
+            None if literals.is_empty() => None,
+            None => {
+                literals.sort();
+                Some(literals.join("|"))
+            }
+        }
+    }
+
+    /// Export a [Schema] describing every registered command path.
+    pub fn export_schema(&self) -> Schema {
+        let mut schema = Schema::from_mapping(&self.mapping, &self.hidden_nodes());
+        for command in &mut schema.commands {
+            let path = command
+                .path
+                .iter()
+                .map(|node| &node.literal[..])
+                .collect::<Vec<_>>();
+            if let Some(meta) = self.meta(&path) {
+                command.meta = meta
+                    .iter()
+                    .map(|(key, value)| ((*key).into(), (*value).into()))
+                    .collect();
+            }
+        }
+        schema
+    }
+
+    // Resolve the nodes that are not to be listed, these are the nodes registered via
+    // `register_hidden` unless hidden nodes are to be shown, and the nodes requiring a higher
+    // permission level than the current one
+    fn hidden_nodes(&self) -> Vec<&Mapping<'a, Type, String, C>> {
+        let hidden = self.hidden.iter().filter(|_| !self.show_hidden);
+        self.resolve_paths(hidden.chain(self.restricted_paths()))
+    }
+
+    // Check whether `first` is a literal directly below the prefix set by `use`
+    fn below_prefix(&self, first: Option<&str>) -> bool {
+        let prefix = self.prefix.iter().map(|s| &s[..]).collect::<Vec<_>>();
+        match (self.resolve_path(&prefix), first) {
+            (Some(node), Some(first)) => self.child(node, first).is_some(),
+            _ => false,
+        }
+    }
+
+    // Check whether `path` does not resolve or passes through one of the `hidden` nodes
+    fn path_is_hidden(&self, path: &[&str], hidden: &[&Mapping<'a, Type, String, C>]) -> bool {
+        let mut node = &self.mapping;
+        for literal in path {
+            match self.child(node, literal) {
+                Some((_, entry)) if !is_hidden(entry, hidden) => node = entry,
+                _ => return true,
+            }
+        }
+        false
+    }
+
+    // Resolve the nodes requiring a higher permission level than the current one
+    fn restricted_nodes(&self) -> Vec<&Mapping<'a, Type, String, C>> {
+        self.resolve_paths(self.restricted_paths())
+    }
+
+    fn restricted_paths(&self) -> impl Iterator<Item = &Vec<&'static str>> {
+        let level = self.permission_level;
+        self.permissions
+            .iter()
+            .filter(move |(_, required)| *required > level)
+            .map(|(path, _)| path)
+    }
+
+    fn resolve_paths<'b>(
+        &self,
+        paths: impl Iterator<Item = &'b Vec<&'static str>>,
+    ) -> Vec<&Mapping<'a, Type, String, C>> {
+        paths.filter_map(|path| self.resolve_path(path)).collect()
+    }
+
+    fn resolve_path(&self, path: &[&str]) -> Option<&Mapping<'a, Type, String, C>> {
+        path.iter().try_fold(&self.mapping, |node, literal| {
+            self.child(node, literal).map(|(_, entry)| entry)
+        })
+    }
+
+    // Check whether looking up `input` passes through a node requiring a higher permission level
+    fn insufficient_permission(&self, input: &[&str]) -> bool {
+        let restricted = self.restricted_nodes();
+        !restricted.is_empty()
+            && self
+                .lookup_path(input)
+                .iter()
+                .any(|node| is_hidden(node, &restricted))
+    }
+
+    // Nodes visited when looking up `input`, stops at the first token that does not match
+    fn lookup_path(&self, input: &[&str]) -> Vec<&Mapping<'a, Type, String, C>> {
+        self.walk(input)
+            .into_iter()
+            .map(|(_, _, node)| node)
+            .collect()
+    }
+
+    // Follow `input` through the mapping as far as it matches, yielding the index of each literal
+    // along with its key and node
+    fn walk(&self, input: &[&str]) -> Vec<(usize, &'a str, &Mapping<'a, Type, String, C>)> {
+        let mut node = &self.mapping;
+        let mut path = vec![];
+        let mut out = SVec::new();
+        let mut index = 0;
+        while let Some(token) = input.get(index) {
+            let (key, entry) = match self.child(node, token) {
+                Some(child) => child,
+                None => break,
+            };
+            let mut consumed = 0;
+            if let Some(decider) = entry.decider() {
+                match (decider.decider)(&input[index + 1..], &mut out) {
+                    Decision::Accept(count) if count < input.len() - index => consumed = count,
+                    _ => break,
+                }
+            }
+            path.push((index, key, entry));
+            index += 1 + consumed;
+            node = entry;
+        }
+        path
+    }
+
+    // Find the child of `node` that `token` names, see [Evaluator::set_case_insensitive]
+    fn child<'m>(
+        &self,
+        node: &'m Mapping<'a, Type, String, C>,
+        token: &str,
+    ) -> Option<(&'a str, &'m Mapping<'a, Type, String, C>)> {
+        let exact = node.iter().find(|(key, _)| **key == token);
+        let child = match exact {
+            None if self.case_insensitive => {
+                let token = fold_literal(token);
+                node.iter().find(|(key, _)| fold_literal(key) == token)
+            }
+            child => child,
+        };
+        child.map(|(key, entry)| (*key, entry))
+    }
+
+    // Replace the literals in `input` by the keys they match regardless of case
+    fn fold_case(&self, input: &[&str]) -> Vec<String> {
+        let mut folded = input
+            .iter()
+            .map(|token| (*token).to_string())
+            .collect::<Vec<_>>();
+        for (index, key, _) in self.walk(input) {
+            folded[index] = key.to_string();
+        }
+        folded
+    }
+
+    // Take the buffers for the statement at the current depth, see `ParseScratch`
+    fn take_frame(&mut self) -> ScratchFrame {
+        let depth = self.current_depth;
+        match self.scratch {
+            Some(ref mut scratch) => {
+                if scratch.frames.len() <= depth {
+                    scratch.frames.resize_with(depth + 1, ScratchFrame::default);
+                }
+                let mut frame = std::mem::take(&mut scratch.frames[depth]);
+                frame.spare.append(&mut frame.content);
+                frame
+            }
+            None => ScratchFrame::default(),
+        }
+    }
+
+    fn put_frame(&mut self, frame: ScratchFrame) {
+        let depth = self.current_depth;
+        if let Some(ref mut scratch) = self.scratch {
+            if let Some(slot) = scratch.frames.get_mut(depth) {
+                *slot = frame;
+            }
+        }
+    }
+
+    // Parse subcommands recursively into a vector of strings, fail with feedback otherwise
+    fn parse_subcommands(&mut self, cmds: &[Data], frame: &mut ScratchFrame) -> Result<(), String> {
+        let mut completed = vec![];
+        for cmd in cmds {
+            match cmd {
+                Data::Atom(string) => {
+                    frame.push(string);
+                }
+                Data::Command(string) => {
+                    if let Some('#') = string.chars().next() {
+                        frame.push(&string[1..]);
+                    } else if !self.substitution_enabled {
+                        frame.push(string);
+                    } else {
+                        if self.current_depth == self.max_depth {
+                            return Err(format!["Recursion limit reached: {}", self.max_depth]);
+                        }
+                        if self.substitutions == self.max_substitutions {
+                            return Err(format![
+                                "Substitution limit reached: {}",
+                                self.max_substitutions
+                            ]);
+                        }
+                        self.substitutions += 1;
+                        self.current_depth += 1;
+                        let res = self.interpret_single(string);
+                        self.current_depth -= 1;
+                        match res {
+                            Ok(Feedback::Ok(string)) => {
+                                if self.partial_results {
+                                    completed.push(string.clone());
+                                }
+                                frame.content.push(string);
+                            }
+                            Ok(Feedback::Err(res)) => {
+                                if self.partial_results {
+                                    return Err(format![
+                                        "{} (partial: [{}])",
+                                        res,
+                                        completed.join(", ")
+                                    ]);
+                                }
+                                return Err(res);
+                            }
+                            Err(ParseError::DanglingLeftParenthesis) => {
+                                return Err("Dangling left parenthesis".into());
+                            }
+                            Err(ParseError::PrematureRightParenthesis) => {
+                                return Err("Right parenthesis encountered with no matching left parenthesis".into());
+                            }
+                            Err(ParseError::NothingToParse) => {
+                                return Err("No input to parse".into());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_any_builtin_commands(&mut self, content: &[&str]) -> Option<Feedback> {
+        fn mapping_to_list<C>(
+            mapping: &'_ Mapping<'_, Type, String, C>,
+            hidden: &[&Mapping<'_, Type, String, C>],
+        ) -> Vec<String> {
+            let mut builder = vec![];
+            for (key, entry) in mapping.iter() {
+                if is_hidden(entry, hidden) {
+                    continue;
+                }
+                let (parameter, spacer) = if let Some(decider) = entry.decider() {
+                    (decider.description, " ")
+                } else {
+                    (" ", "")
+                };
+
+                if entry.finalizer().is_some() {
+                    builder.push(
+                        String::from(*key)
+                            + if entry.decider().is_some() { " " } else { "" }
+                            + parameter,
+                    );
+                }
+                for command in mapping_to_list(entry, hidden) {
+                    builder.push(String::from(*key) + spacer + parameter + spacer + &command);
+                }
+            }
+            builder
+        }
+        if let Some(front) = content.first() {
+            if *front == "autocomplete" {
+                let input = self.fold_case(&content[1..]);
+                let input = input.iter().map(|token| &token[..]).collect::<Vec<_>>();
+                match self.mapping.partial_lookup(&input) {
+                    Ok(Either::Left(mapping)) => {
+                        let hidden = self.hidden_nodes();
+                        let mut col = mapping
+                            .iter()
+                            .filter(|(_, entry)| !is_hidden(entry, &hidden))
+                            .map(|(key, entry)| {
+                                let mut s = String::new() + key;
+                                if let Some(decider) = entry.decider() {
+                                    s += " ";
+                                    s += decider.description;
+                                }
+                                s += if entry.finalizer().is_some() {
                                     " (final)"
                                 } else {
                                     ""
@@ -159,13 +1831,13 @@ impl<'a, C> Evaluator<'a, C> {
                         return Some(Feedback::Ok(name.into()));
                     }
                     Err(err) => {
-                        return Some(lookerr_to_evalres(err));
+                        return Some(self.lookerr_to_evalres(&content[1..], err));
                     }
                 }
             }
 
             if *front == "?" {
-                let mut list = mapping_to_list(&self.mapping);
+                let mut list = mapping_to_list(&self.mapping, &self.hidden_nodes());
                 if let Some(regex) = content.get(1) {
                     if content.len() >= 3 {
                         return Some(Feedback::Err("Too many arguments to: ?".to_string()));
@@ -189,268 +1861,3421 @@ impl<'a, C> Evaluator<'a, C> {
                 list.sort();
                 return Some(Feedback::Ok(list.join("\n")));
             }
-        }
-        None
-    }
 
-    #[cfg(test)]
-    fn get_current_depth(&self) -> usize {
-        self.current_depth
-    }
-}
+            if *front == "help" {
+                if content.len() == 1 {
+                    return Some(Feedback::Err("Usage: help <command>".into()));
+                }
+                let path = &content[1..];
+                let hidden = self.hidden_nodes();
+                let mut node = &self.mapping;
+                let mut signature = vec![];
+                let mut literals = vec![];
+                for literal in path {
+                    let (key, entry) = match self.child(node, literal) {
+                        Some((key, entry)) if !is_hidden(entry, &hidden) => (key, entry),
+                        _ => {
+                            return Some(Feedback::Err(format![
+                                "Unrecognized mapping: {}",
+                                path.join(" ")
+                            ]));
+                        }
+                    };
+                    node = entry;
+                    literals.push(key);
+                    signature.push(key);
+                    if let Some(decider) = node.decider() {
+                        signature.push(decider.description);
+                    }
+                }
+                let signature = signature.join(" ");
+                let mut lines = mapping_to_list(node, &hidden)
+                    .into_iter()
+                    .map(|command| signature.clone() + " " + &command)
+                    .collect::<Vec<_>>();
+                if node.finalizer().is_some() {
+                    lines.push(signature);
+                }
+                lines.sort();
+                let examples = self
+                    .command_examples
+                    .iter()
+                    .filter(|(other, _)| other.get(..literals.len()) == Some(&literals[..]))
+                    .filter(|(other, _)| !self.path_is_hidden(other, &hidden))
+                    .flat_map(|(_, examples)| examples)
+                    .collect::<Vec<_>>();
+                if !examples.is_empty() {
+                    lines.push("Examples:".into());
+                    lines.extend(examples.iter().map(|example| format!["    {}", example]));
+                }
+                return Some(Feedback::Ok(lines.join("\n")));
+            }
 
-impl<'a, C> Evaluate<Feedback> for Evaluator<'a, C> {
-    fn evaluate(&mut self, commands: &[Data]) -> Feedback {
-        let content = match self.parse_subcommands(commands) {
-            Ok(content) => content,
-            Err(err) => return Err(err),
-        };
-        let content_ref = content.iter().map(|s| &s[..]).collect::<Vec<_>>();
+            if *front == "use" {
+                if content.len() == 1 {
+                    self.prefix.clear();
+                    return Some(Feedback::Ok("".into()));
+                }
+                let input = self.fold_case(&content[1..]);
+                let folded = input.iter().map(|token| &token[..]).collect::<Vec<_>>();
+                match self.mapping.partial_lookup(&folded) {
+                    Ok(Either::Left(_)) => {
+                        self.prefix = input;
+                        return Some(Feedback::Ok("".into()));
+                    }
+                    Ok(Either::Right(name)) => {
+                        return Some(Feedback::Err(format!["Expected {}", name]));
+                    }
+                    Err(err) => {
+                        return Some(self.lookerr_to_evalres(&content[1..], err));
+                    }
+                }
+            }
 
-        let res = self.mapping.lookup(&content_ref[..]);
-        match res {
-            Ok(fin) => {
-                let res = fin.0(&mut self.context, &fin.1);
-                match res {
-                    Ok(res) => Feedback::Ok(res),
-                    Err(res) => Feedback::Err(res),
+            if *front == "test-decider" {
+                let (_, lines) = self.trace_lookup(&content[1..], &vec![false; content.len() - 1]);
+                return Some(Feedback::Ok(lines.join("\n")));
+            }
+
+            if *front == "clear" && content.len() == 1 && self.ansi {
+                return Some(Feedback::Ok("\x1b[2J\x1b[H".into()));
+            }
+
+            if *front == "debug-args" {
+                match content.get(1..) {
+                    Some(["on"]) => self.debug_args = true,
+                    Some(["off"]) => self.debug_args = false,
+                    _ => return Some(Feedback::Err("Usage: debug-args on|off".into())),
                 }
+                return Some(Feedback::Ok("".into()));
             }
-            Err(err) => {
-                if let Some(result) = self.handle_any_builtin_commands(&content_ref[..]) {
-                    return result;
+
+            if *front == "set" {
+                if content.len() != 3 {
+                    return Some(Feedback::Err("Usage: set <name> <value>".into()));
+                }
+                self.set_variable(content[1], content[2]);
+                return Some(Feedback::Ok("".into()));
+            }
+
+            if *front == "get" {
+                if content.len() != 2 {
+                    return Some(Feedback::Err("Usage: get <name>".into()));
+                }
+                return match self.variable(content[1]) {
+                    Some(value) => Some(Feedback::Ok(value)),
+                    None => Some(Feedback::Err(format!["Unknown variable: {}", content[1]])),
+                };
+            }
+
+            #[cfg(feature = "with-futures")]
+            {
+                if let Some(result) = self.handle_job_commands(content) {
+                    return Some(result);
+                }
+            }
+        }
+        None
+    }
+
+    // The builtins `jobs` and `kill`, available once a job registry is set
+    #[cfg(feature = "with-futures")]
+    fn handle_job_commands(&self, content: &[&str]) -> Option<Feedback> {
+        let (jobs, level) = self.jobs.as_ref()?;
+        if content[0] != "jobs" && content[0] != "kill" {
+            return None;
+        }
+        if self.permission_level < *level {
+            return Some(Feedback::Err("insufficient permission".into()));
+        }
+        if content[0] == "jobs" {
+            if content.len() != 1 {
+                return Some(Feedback::Err("Too many arguments to: jobs".into()));
+            }
+            let list = jobs
+                .list()
+                .into_iter()
+                .map(|(id, description, status)| format!["{} {}: {}", id, description, status])
+                .collect::<Vec<_>>();
+            if list.is_empty() {
+                return Some(Feedback::Ok("No jobs".into()));
+            }
+            return Some(Feedback::Ok(list.join("\n")));
+        }
+        match content.get(1..) {
+            Some([id]) => match id.parse() {
+                Ok(id) => Some(jobs.kill(JobId(id)).map(|()| "".into())),
+                Err(_) => Some(Feedback::Err(format!["Unknown job: {}", id])),
+            },
+            _ => Some(Feedback::Err("Usage: kill <id>".into())),
+        }
+    }
+
+    // Walk the mapping like a lookup would, returning the literals of the path taken along with a
+    // description of what each decider consumed and produced. Hidden and restricted commands are
+    // treated as unknown like in `?`. Tokens flagged in `nested` are results of nested commands
+    // that are not known yet, so deciders only see the tokens before them
+    fn trace_lookup(&self, mut input: &[&str], mut nested: &[bool]) -> (Vec<&'a str>, Vec<String>) {
+        let hidden = self.hidden_nodes();
+        let mut node = &self.mapping;
+        let mut path = vec![];
+        let mut lines = vec![];
+        let mut out = SVec::new();
+        while let Some(token) = input.first() {
+            if nested[0] {
+                lines.push(format!["{}: depends on nested command", token]);
+                return (path, lines);
+            }
+            let (key, entry) = match self.child(node, token) {
+                Some((key, entry)) if !is_hidden(entry, &hidden) => (key, entry),
+                _ => {
+                    lines.push(format!["{}: unknown literal", token]);
+                    return (path, lines);
+                }
+            };
+            let mut consumed = 0;
+            if let Some(decider) = entry.decider() {
+                let produced = out.len();
+                let known = nested[1..]
+                    .iter()
+                    .position(|nested| *nested)
+                    .unwrap_or(input.len() - 1);
+                match (decider.decider)(&input[1..=known], &mut out) {
+                    Decision::Accept(count) if count <= known => {
+                        lines.push(format![
+                            "{} {}: consumed {} {:?}",
+                            token,
+                            decider.description,
+                            count,
+                            &out[produced..]
+                        ]);
+                        consumed = count;
+                    }
+                    _ if known < input.len() - 1 => {
+                        lines.push(format![
+                            "{} {}: depends on nested command {}",
+                            token,
+                            decider.description,
+                            input[known + 1]
+                        ]);
+                        return (path, lines);
+                    }
+                    Decision::Accept(count) => {
+                        lines.push(format![
+                            "{} {}: advanced too far, consumed {} of {}",
+                            token,
+                            decider.description,
+                            count,
+                            input.len() - 1
+                        ]);
+                        return (path, lines);
+                    }
+                    Decision::Deny(reason) => {
+                        lines.push(format![
+                            "{} {}: denied: {}",
+                            token, decider.description, reason
+                        ]);
+                        return (path, lines);
+                    }
+                }
+            } else {
+                lines.push(format!["{}: no decider", token]);
+            }
+            path.push(key);
+            input = &input[1 + consumed..];
+            nested = &nested[1 + consumed..];
+            node = entry;
+        }
+        if node.finalizer().is_some() {
+            lines.push("finalizer: present".into());
+        } else {
+            lines.push("finalizer: absent".into());
+        }
+        (path, lines)
+    }
+
+    // Describe how `statement` would be tokenized, looked up, and checked by each decider,
+    // explaining nested commands the same way, without running anything
+    fn explain(&self, statement: &[Data]) -> Result<Vec<String>, String> {
+        if statement.is_empty() {
+            return Err("Usage: explain <command>".into());
+        }
+        let trees = statement
+            .iter()
+            .map(|data| match data {
+                Data::Atom(atom) => Ok(Tree::Atom((*atom).into())),
+                Data::Command(string) => match string.strip_prefix('#') {
+                    Some(string) => Ok(Tree::String(string.into())),
+                    // Without substitution a nested command is passed on as a string
+                    None if !self.substitution_enabled => Ok(Tree::String((*string).into())),
+                    None => parse_tree(string)
+                        .map_err(|err| format!["Unable to parse ({}): {:?}", string, err]),
+                },
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut lines = vec![];
+        self.explain_trees(&trees, "", &mut lines);
+        Ok(lines)
+    }
+
+    fn explain_trees(&self, trees: &[Tree], indent: &str, lines: &mut Vec<String>) {
+        let tokens = trees.iter().map(tree_text).collect::<Vec<_>>();
+        lines.push(format!["{}tokens: {:?}", indent, tokens]);
+        for (tree, token) in trees.iter().zip(&tokens) {
+            if let Tree::Command(inner) = tree {
+                lines.push(format!["{}nested {}:", indent, token]);
+                self.explain_trees(inner, &format!["{}    ", indent], lines);
+            }
+        }
+
+        let arguments = trees
+            .iter()
+            .zip(&tokens)
+            .map(|(tree, token)| match tree {
+                Tree::String(string) => &string[..],
+                _ => &token[..],
+            })
+            .collect::<Vec<_>>();
+        let nested = trees
+            .iter()
+            .map(|tree| matches!(tree, Tree::Command(_)))
+            .collect::<Vec<_>>();
+        let below_prefix = !self.prefix.is_empty()
+            && match (arguments.first(), nested.first()) {
+                (Some(_), Some(true)) => true,
+                (first, _) => self.below_prefix(first.copied()),
+            };
+        let (input, nested) = if below_prefix {
+            let input = self
+                .prefix
+                .iter()
+                .map(|literal| &literal[..])
+                .chain(arguments.iter().cloned())
+                .collect::<Vec<_>>();
+            let nested = self
+                .prefix
+                .iter()
+                .map(|_| false)
+                .chain(nested)
+                .collect::<Vec<_>>();
+            (input, nested)
+        } else {
+            (arguments, nested)
+        };
+
+        let (path, trace) = self.trace_lookup(&input, &nested);
+        lines.push(format!["{}path: {}", indent, path.join(" ")]);
+        for line in trace {
+            lines.push(format!["{}{}", indent, line]);
+        }
+    }
+
+    fn lookerr_to_evalres(&self, input: &[&str], err: LookError<String>) -> Feedback {
+        match err {
+            LookError::DeciderAdvancedTooFar => Feedback::Err("Decider advanced too far".into()),
+            LookError::DeciderDenied(desc, decider) => match self.examples.get(&desc[..]) {
+                Some(example) => Feedback::Err(format![
+                    "Expected {}, e.g. {}. Decider: {}",
+                    desc, example, decider
+                ]),
+                None => Feedback::Err(format!["Expected {}. Decider: {}", desc, decider]),
+            },
+            LookError::FinalizerDoesNotExist => Feedback::Err("Finalizer does not exist".into()),
+            LookError::UnknownMapping(token) => match self.suggest(input, &token) {
+                Some(literal) => Feedback::Err(format![
+                    "Unrecognized mapping: {}; did you mean '{}'?",
+                    token, literal
+                ]),
+                None => Feedback::Err(format!["Unrecognized mapping: {}", token]),
+            },
+        }
+    }
+
+    // Find the visible literal closest to the unrecognized `token` following the part of `input`
+    // that matches
+    fn suggest(&self, input: &[&str], token: &str) -> Option<&'a str> {
+        let hidden = self.hidden_nodes();
+        let node = self
+            .walk(input)
+            .last()
+            .map_or(&self.mapping, |(_, _, node)| *node);
+        let length = token.chars().count();
+        node.iter()
+            .filter(|(_, entry)| !is_hidden(entry, &hidden))
+            .map(|(key, _)| (edit_distance(key, token), *key))
+            .filter(|(distance, _)| *distance <= 2 && *distance < length)
+            .min()
+            .map(|(_, key)| key)
+    }
+
+    // Look up a command, keeping the warnings raised by its deciders if it is found
+    fn lookup(&mut self, input: &[&str]) -> Result<FinWithArgs<'_, Type, C>, LookError<String>> {
+        let folded = if self.case_insensitive {
+            self.fold_case(input)
+        } else {
+            Vec::new()
+        };
+        let folded = folded.iter().map(|token| &token[..]).collect::<SVec<_>>();
+        let input = if self.case_insensitive {
+            &folded[..]
+        } else {
+            input
+        };
+        let previous = WARNINGS.with(|warnings| warnings.replace(Vec::new()));
+        let res = match self.lookup_deadline {
+            Some(deadline) => self.lookup_until(input, deadline),
+            None => self.mapping.lookup(input),
+        };
+        let mut warnings = WARNINGS.with(|warnings| warnings.replace(previous));
+        if res.is_ok() {
+            self.warnings.append(&mut warnings);
+        }
+        res
+    }
+
+    // Look up a command like `Mapping::lookup` does, giving up before running a decider once
+    // `deadline` has passed
+    fn lookup_until(
+        &self,
+        mut input: &[&str],
+        deadline: Instant,
+    ) -> Result<FinWithArgs<'_, Type, C>, LookError<String>> {
+        let mut node = &self.mapping;
+        let mut out = SVec::new();
+        while let Some(token) = input.first() {
+            let entry = match self.child(node, token) {
+                Some((_, entry)) => entry,
+                None => return Err(LookError::UnknownMapping(token.to_string())),
+            };
+            let mut consumed = 0;
+            if let Some(decider) = entry.decider() {
+                if Instant::now() > deadline {
+                    return Err(LookError::DeciderDenied(
+                        decider.description.into(),
+                        "command parsing timed out".into(),
+                    ));
+                }
+                match (decider.decider)(&input[1..], &mut out) {
+                    Decision::Accept(count) => consumed = count,
+                    Decision::Deny(res) => {
+                        return Err(LookError::DeciderDenied(decider.description.into(), res));
+                    }
+                }
+            }
+            if consumed >= input.len() {
+                return Err(LookError::DeciderAdvancedTooFar);
+            }
+            input = &input[1 + consumed..];
+            node = entry;
+        }
+        match node.finalizer() {
+            Some(finalizer) => Ok((*finalizer, out)),
+            None => Err(LookError::FinalizerDoesNotExist),
+        }
+    }
+
+    fn lookup_timed_out(&self) -> bool {
+        self.lookup_deadline
+            .is_some_and(|deadline| Instant::now() > deadline)
+    }
+
+    fn run_permitted(&mut self, input: &[&str], fin: FinWithArgs<Type, C>) -> Feedback {
+        if self.debug_args && !self.insufficient_permission(input) {
+            let args: Vec<_> = fin
+                .1
+                .iter()
+                .map(|arg| format!["{:?} {}", arg.kind(), arg])
+                .collect();
+            return Feedback::Ok(args.join("\n"));
+        }
+        let cooldown = self.check_access(input)?;
+        if self.current_depth == 0 {
+            if let Some(ref mut trace) = self.trace {
+                *trace = input.iter().map(|arg| (*arg).to_string()).collect();
+            }
+        }
+        let res = self.run_finalizer(input, fin);
+        self.start_cooldown(cooldown, &res);
+        res
+    }
+
+    // Check the permission level and cooldown of the command looked up by `input`, returning the
+    // index of its cooldown and the current time if it has one, see `start_cooldown`
+    fn check_access(&mut self, input: &[&str]) -> Result<Option<(usize, Instant)>, String> {
+        if self.insufficient_permission(input) {
+            return Err("insufficient permission".into());
+        }
+        if self.cooldowns.is_empty() {
+            return Ok(None);
+        }
+        let command: *const Mapping<'a, Type, String, C> = match self.lookup_path(input).last() {
+            Some(command) => *command,
+            None => return Ok(None),
+        };
+        let index = self.cooldowns.iter().position(|cooldown| {
+            self.resolve_path(&cooldown.path)
+                .is_some_and(|node| std::ptr::eq(node, command))
+        });
+        if let Some(index) = index {
+            let now = (self.clock)();
+            let cooldown = &self.cooldowns[index];
+            if let Some(last) = cooldown.last_invocation {
+                let elapsed = now.saturating_duration_since(last);
+                if elapsed < cooldown.duration {
+                    let remaining = cooldown.duration - elapsed;
+                    let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+                    return Err(format!["on cooldown, {} s remaining", seconds]);
+                }
+            }
+            return Ok(Some((index, now)));
+        }
+        Ok(None)
+    }
+
+    // Start the cooldown found by `check_access` once the handler has run, unless it ran in
+    // dry-run mode, failed, or only asked for confirmation
+    fn start_cooldown(&mut self, cooldown: Option<(usize, Instant)>, res: &Feedback) {
+        if let Some((index, now)) = cooldown {
+            if res.is_ok() && !self.dry_run && self.pending_confirmation.is_none() {
+                self.cooldowns[index].last_invocation = Some(now);
+            }
+        }
+    }
+
+    // Run a handler, where `input` is the statement it was looked up by, which is kept if the
+    // handler asks for confirmation
+    fn run_finalizer(&mut self, input: &[&str], fin: FinWithArgs<Type, C>) -> Feedback {
+        if let Some(ref allowed) = self.allowed_types {
+            if let Some(arg) = fin.1.iter().find(|arg| !allowed.contains(&arg.kind())) {
+                return Feedback::Err(format!["Argument type not allowed: {:?}", arg.kind()]);
+            }
+        }
+        let previous = DRY_RUN.with(|flag| flag.replace(self.dry_run));
+        let previous_events = EVENTS.with(|events| events.replace(Vec::new()));
+        let previous_request = NEEDS_CONFIRMATION.with(|flag| flag.replace(false));
+        let previous_presentation = PRESENTATION.with(|current| current.replace(None));
+        let (handler, args) = fin;
+        let res = match self.handler_timeout {
+            Some((timeout, runner)) => runner(handler, &mut self.context, args.clone(), timeout),
+            None => handler(&mut self.context, &args),
+        };
+        DRY_RUN.with(|flag| flag.set(previous));
+        if NEEDS_CONFIRMATION.with(|flag| flag.replace(previous_request))
+            && !self.dry_run
+            && self.current_depth == 0
+        {
+            let input = input.iter().map(|token| (*token).to_string()).collect();
+            self.pending_confirmation = Some((input, (handler, args)));
+        }
+        let res = match (res, self.max_output) {
+            (Ok(string), Some((max, overflow))) if string.len() > max => match overflow {
+                OutputOverflow::Truncate => {
+                    let mut end = max;
+                    while !string.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    Ok(string[..end].to_string() + "...(truncated)")
+                }
+                OutputOverflow::Error => Err(format![
+                    "Output too long: {} bytes, limit is {}",
+                    string.len(),
+                    max
+                ]),
+            },
+            (res, _) => res,
+        };
+        let presentation = PRESENTATION.with(|current| current.replace(previous_presentation));
+        if self.current_depth == 0 {
+            self.presentation = match (presentation, &res) {
+                (Some((presentation, labelled)), Ok(string)) if labelled == *string => {
+                    Some(presentation)
+                }
+                _ => None,
+            };
+        }
+        let events = EVENTS.with(|events| events.replace(previous_events));
+        if let Some(ref mut emitter) = self.event_emitter {
+            if !self.dry_run {
+                for event in events {
+                    emitter.emit(event);
+                }
+            }
+        }
+        res
+    }
+}
+
+impl<'a, C: Clone + Send + 'static> Evaluator<'a, C> {
+    /// Set the maximum time a single handler invocation may take, `None` disables the timeout.
+    ///
+    /// With a timeout set, each handler runs on its own thread using a clone of the context. When
+    /// the handler finishes in time, the clone replaces the context. When it does not, the
+    /// command fails with `"handler timed out"`, the thread is left to run to completion in the
+    /// background, and any changes it makes to its context are discarded.
+    pub fn set_handler_timeout(&mut self, timeout: Option<Duration>) {
+        let runner: TimedRunner<C> = run_with_timeout::<C>;
+        self.handler_timeout = timeout.map(|timeout| (timeout, runner));
+    }
+}
+
+#[cfg(feature = "with-futures")]
+impl<'a: 's, 's, C: 's> Evaluator<'a, C> {
+    /// Interpret each line yielded by `lines` as a single statement, yielding the result of each
+    /// statement in turn.
+    ///
+    /// Lines are expected to be split already, so each must hold one complete statement. The
+    /// evaluator stays borrowed for as long as the returned stream lives.
+    pub fn interpret_stream(
+        &'s mut self,
+        lines: impl futures::Stream<Item = String> + 's,
+    ) -> LocalBoxStream<'s, Result<Feedback, ParseError>> {
+        Box::pin(lines.map(move |line| self.interpret_single(&line)))
+    }
+}
+
+impl<'a, C> Evaluate<Feedback> for Evaluator<'a, C> {
+    fn interpret_single(&mut self, statement: &str) -> Result<Feedback, ParseError> {
+        let tokens = match &self.tokenizer {
+            Some(tokenizer) => tokenizer.tokenize(statement)?,
+            None => return DefaultSyntax(self).interpret_single(statement),
+        };
+        if tokens.is_empty() {
+            return Err(ParseError::NothingToParse);
+        }
+        let data = tokens
+            .iter()
+            .map(|token| Data::Atom(token))
+            .collect::<SVec<_>>();
+        Ok(self.evaluate(&data))
+    }
+
+    /// Interpret newline-separated statements and return the feedback of the last statement
+    /// interpreted, see [Evaluator::interpret_all].
+    fn interpret_multiple(&mut self, code: &str) -> Result<Feedback, ParseError> {
+        self.interpret_all(code)
+            .map(|mut results| results.pop().expect("interpret_all returned no results"))
+    }
+
+    fn evaluate(&mut self, commands: &[Data]) -> Feedback {
+        if self.current_depth == 0 {
+            self.reset_transient();
+        }
+        self.operations += 1;
+        let res = match self.operation_budget {
+            Some(budget) if self.operations > budget => {
+                Feedback::Err(format!["Operation budget exhausted: {}", budget])
+            }
+            _ if self.current_depth == 0 && self.pending_confirmation.is_some() => {
+                self.answer_confirmation(commands)
+            }
+            _ => self.evaluate_statement(commands),
+        };
+        if self.current_depth == 0 {
+            if let Some(ref mut sink) = self.replay_sink {
+                let _ = write_replay(sink, commands, &res);
+            }
+        }
+        res
+    }
+}
+
+// Parses with the default `interpret_single` of metac, which `Evaluator` overrides to support
+// tokenizers
+struct DefaultSyntax<'e, 'a, C>(&'e mut Evaluator<'a, C>);
+
+impl<'e, 'a, C> Evaluate<Feedback> for DefaultSyntax<'e, 'a, C> {
+    fn evaluate(&mut self, commands: &[Data]) -> Feedback {
+        self.0.evaluate(commands)
+    }
+}
+
+impl<'a, C> Evaluator<'a, C> {
+    // Run the command waiting for confirmation if `commands` is `confirm`, cancel it otherwise
+    fn answer_confirmation(&mut self, commands: &[Data]) -> Feedback {
+        let pending = self.pending_confirmation.take();
+        match (commands, pending) {
+            ([Data::Atom("confirm")], Some((input, fin))) => {
+                let input = input.iter().map(|token| &token[..]).collect::<Vec<_>>();
+                let cooldown = self.check_access(&input)?;
+                let previous = CONFIRMED.with(|flag| flag.replace(true));
+                let res = self.run_finalizer(&input, fin);
+                CONFIRMED.with(|flag| flag.set(previous));
+                self.start_cooldown(cooldown, &res);
+                res
+            }
+            _ => Feedback::Err("cancelled".into()),
+        }
+    }
+
+    fn evaluate_statement(&mut self, commands: &[Data]) -> Feedback {
+        // Fast path for single-literal commands such as `status`, avoids allocating the
+        // intermediate vectors below. Anything that does not match falls through to the general
+        // path
+        if let [Data::Atom(atom)] = commands {
+            if self.prefix.is_empty() {
+                if let Ok(fin) = self.lookup(&[atom]) {
+                    return self.run_permitted(&[atom], fin);
+                }
+            }
+        }
+
+        let mut frame = self.take_frame();
+        let res = self.evaluate_content(commands, &mut frame);
+        self.put_frame(frame);
+        res
+    }
+
+    fn evaluate_content(&mut self, commands: &[Data], frame: &mut ScratchFrame) -> Feedback {
+        if let [Data::Atom("explain"), statement @ ..] = commands {
+            if self.resolve_path(&["explain"]).is_none() {
+                return Feedback::Ok(self.explain(statement)?.join("\n"));
+            }
+        }
+        self.parse_subcommands(commands, frame)?;
+        let content_ref = frame.content.iter().map(|s| &s[..]).collect::<SVec<_>>();
+
+        if !self.prefix.is_empty() {
+            let prefix = self.prefix.clone();
+            let prefixed = prefix
+                .iter()
+                .map(|s| &s[..])
+                .chain(content_ref.iter().cloned())
+                .collect::<SVec<_>>();
+            // Only statements whose first token is not below the prefix are looked up as is,
+            // other failures are reported against the prefixed statement
+            let below_prefix = self.below_prefix(content_ref.first().copied());
+            match self.lookup(&prefixed[..]) {
+                Ok(fin) => return self.run_permitted(&prefixed[..], fin),
+                Err(LookError::UnknownMapping(_)) if !below_prefix => {}
+                Err(_) if self.lookup_timed_out() => {
+                    return Feedback::Err("command parsing timed out".into());
+                }
+                Err(err) => return self.lookerr_to_evalres(&prefixed[..], err),
+            }
+        }
+
+        let res = self.lookup(&content_ref[..]);
+        match res {
+            Ok(fin) => self.run_permitted(&content_ref[..], fin),
+            Err(_) if self.lookup_timed_out() => Feedback::Err("command parsing timed out".into()),
+            Err(err) => {
+                if let Some(result) = self.handle_any_builtin_commands(&content_ref[..]) {
+                    return result;
+                }
+                if let Some(handler) = self.default_handler {
+                    let first = self.fold_case(&content_ref[..1]);
+                    if self.mapping.partial_lookup(&[&first[0][..]]).is_err() {
+                        let args = content_ref
+                            .iter()
+                            .map(|arg| Type::String((*arg).into()))
+                            .collect();
+                        return self.run_finalizer(&content_ref[..], (handler, args));
+                    }
+                }
+                self.lookerr_to_evalres(&content_ref[..], err)
+            }
+        }
+    }
+}
+
+// The text of a part of a statement as it was written
+fn tree_text(tree: &Tree) -> String {
+    match tree {
+        Tree::Atom(atom) => atom.clone(),
+        Tree::String(string) => format!["(#{})", string],
+        Tree::Command(parts) => {
+            let parts = parts.iter().map(tree_text).collect::<Vec<_>>();
+            format!["({})", parts.join(" ")]
+        }
+    }
+}
+
+fn write_replay(sink: &mut dyn Write, commands: &[Data], res: &Feedback) -> io::Result<()> {
+    let statement = commands
+        .iter()
+        .map(|data| match data {
+            Data::Atom(string) => (*string).to_string(),
+            Data::Command(string) => format!["({})", string],
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    writeln![sink, "> {}", statement.escape_debug()]?;
+    writeln![sink, "< {:?}", res]?;
+    sink.flush()
+}
+
+/// Read a log written to a replay sink, see [Evaluator::set_replay_sink], returning each statement
+/// along with its recorded feedback.
+///
+/// Fails with [io::ErrorKind::InvalidData] if the log is not in the replay format.
+pub fn read_replay(log: impl BufRead) -> io::Result<Vec<(String, Feedback)>> {
+    let invalid = |line: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!["not a replay line: {}", line],
+        )
+    };
+    let mut entries = vec![];
+    let mut lines = log.lines();
+    while let Some(line) = lines.next() {
+        let line = line?;
+        let statement = line
+            .strip_prefix("> ")
+            .and_then(unescape)
+            .ok_or_else(|| invalid(&line))?;
+        let line = lines.next().unwrap_or_else(|| Ok(String::new()))?;
+        let feedback = line
+            .strip_prefix("< Ok(\"")
+            .and_then(|rest| rest.strip_suffix("\")"))
+            .and_then(unescape)
+            .map(Feedback::Ok)
+            .or_else(|| {
+                line.strip_prefix("< Err(\"")
+                    .and_then(|rest| rest.strip_suffix("\")"))
+                    .and_then(unescape)
+                    .map(Feedback::Err)
+            })
+            .ok_or_else(|| invalid(&line))?;
+        entries.push((statement, feedback));
+    }
+    Ok(entries)
+}
+
+// Undo `escape_debug`, `None` if the string contains an invalid escape or an unescaped control
+// character
+fn unescape(string: &str) -> Option<String> {
+    let mut output = String::with_capacity(string.len());
+    let mut chars = string.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            if ch.is_control() {
+                return None;
+            }
+            output.push(ch);
+            continue;
+        }
+        output.push(match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'u' => {
+                if chars.next()? != '{' {
+                    return None;
+                }
+                let hex = chars
+                    .by_ref()
+                    .take_while(|ch| *ch != '}')
+                    .collect::<String>();
+                char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+            }
+            ch @ ('\\' | '\'' | '"') => ch,
+            _ => return None,
+        });
+    }
+    Some(output)
+}
+
+fn run_with_timeout<C: Clone + Send + 'static>(
+    handler: Finalizer<Type, C>,
+    context: &mut C,
+    args: SVec<Type>,
+    timeout: Duration,
+) -> Feedback {
+    let (tx, rx) = mpsc::channel();
+    let mut clone = context.clone();
+    let dry_run = is_dry_run();
+    let confirmed = is_confirmed();
+    thread::spawn(move || {
+        DRY_RUN.with(|flag| flag.set(dry_run));
+        CONFIRMED.with(|flag| flag.set(confirmed));
+        let res = handler(&mut clone, &args);
+        let events = EVENTS.with(|events| events.replace(Vec::new()));
+        let request = NEEDS_CONFIRMATION.with(Cell::get);
+        let presentation = PRESENTATION.with(|current| current.replace(None));
+        let _ = tx.send((clone, res, events, request, presentation));
+    });
+    match rx.recv_timeout(timeout) {
+        Ok((clone, res, mut events, request, presentation)) => {
+            *context = clone;
+            EVENTS.with(|current| current.borrow_mut().append(&mut events));
+            NEEDS_CONFIRMATION.with(|flag| flag.set(request));
+            PRESENTATION.with(|current| *current.borrow_mut() = presentation);
+            res
+        }
+        Err(RecvTimeoutError::Timeout) => Feedback::Err("handler timed out".into()),
+        Err(RecvTimeoutError::Disconnected) => Feedback::Err("handler panicked".into()),
+    }
+}
+
+// The form of a literal that case-insensitive matching compares
+fn fold_literal(text: &str) -> String {
+    text.chars().flat_map(char::to_lowercase).collect()
+}
+
+// The Levenshtein distance between two strings in characters
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right = right.chars().collect::<Vec<_>>();
+    let mut row = (0..=right.len()).collect::<Vec<_>>();
+    for (index, left) in left.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = index + 1;
+        for (column, right) in right.iter().enumerate() {
+            let substitution = diagonal + usize::from(left != *right);
+            diagonal = row[column + 1];
+            row[column + 1] = substitution.min(row[column] + 1).min(diagonal + 1);
+        }
+    }
+    row[right.len()]
+}
+
+pub(crate) fn is_hidden<C>(
+    node: &Mapping<'_, Type, String, C>,
+    hidden: &[&Mapping<'_, Type, String, C>],
+) -> bool {
+    hidden.iter().any(|hidden| std::ptr::eq(node, *hidden))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::predicates::*;
+    use crossbeam_channel::{bounded, Sender};
+
+    #[test]
+    fn evaluate_simple() {
+        let mut eval = Evaluator::new(0usize);
+
+        fn handler(_context: &mut usize, _args: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        eval.register((&[("call", None)], handler)).unwrap();
+        assert_eq!(
+            Feedback::Ok("".into()),
+            eval.interpret_single("call").unwrap()
+        );
+    }
+
+    #[test]
+    fn list_available() {
+        let mut eval = Evaluator::new(0usize);
+
+        fn handler(_context: &mut usize, _args: &[Type]) -> Result<String, String> {
+            Ok("fafa".into())
+        }
+
+        eval.register((&[("call", ANY_F32), ("something", None)], handler))
+            .unwrap();
+        eval.register((&[("call", None), ("abc", MANY_I32)], handler))
+            .unwrap();
+        eval.register((
+            &[("log", None), ("context", MANY_I32), ("level", ANY_ATOM)],
+            handler,
+        ))
+        .unwrap();
+        assert_eq!(
+            Feedback::Ok("call <f32> abc <i32> ...\ncall <f32> something \nlog context <i32> ... level <atom>".into()),
+            eval.interpret_single("?").unwrap()
+        );
+    }
+
+    #[test]
+    fn list_available_using_regex() {
+        let mut eval = Evaluator::new(0usize);
+
+        fn handler(_context: &mut usize, _args: &[Type]) -> Result<String, String> {
+            Ok("fafa".into())
+        }
+
+        eval.register((&[("call", ANY_F32), ("something", None)], handler))
+            .unwrap();
+        eval.register((&[("call", None), ("abc", MANY_I32)], handler))
+            .unwrap();
+        eval.register((
+            &[("log", None), ("context", MANY_I32), ("level", ANY_ATOM)],
+            handler,
+        ))
+        .unwrap();
+        assert_eq!(
+            Feedback::Ok("call <f32> abc <i32> ...\ncall <f32> something ".into()),
+            eval.interpret_single("? call").unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok("call <f32> abc <i32> ...".into()),
+            eval.interpret_single("? abc").unwrap()
+        );
+        assert_eq!(
+            Feedback::Err("Regex could not be compiled: regex parse error:\n    .*\\x.*\n        ^\nerror: invalid hexadecimal digit".into()),
+            eval.interpret_single("? \\x").unwrap()
+        );
+    }
+
+    #[test]
+    fn evaluate_any_f32() {
+        let mut eval = Evaluator::new(0usize);
+
+        fn handler(_context: &mut usize, _args: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        eval.register((&[("call", ANY_F32)], handler)).unwrap();
+        assert_eq!(
+            Feedback::Err(
+                "Expected <f32>, e.g. 1.5. Decider: Too few elements: [], length: 0, expected: 1"
+                    .into()
+            ),
+            eval.interpret_single("call").unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok("".into()),
+            eval.interpret_single("call 3.14159").unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok("".into()),
+            eval.interpret_single("call 3").unwrap()
+        );
+        assert_eq!(
+            Feedback::Err("Expected <f32>, e.g. 1.5. Decider: got string: alpha".into()),
+            eval.interpret_single("call alpha").unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_statements_go_to_default_handler() {
+        let mut eval = Evaluator::new(());
+
+        fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("command".into())
+        }
+
+        fn default(_: &mut (), args: &[Type]) -> Result<String, String> {
+            let words = args
+                .iter()
+                .map(|arg| match arg {
+                    Type::String(word) => &word[..],
+                    _ => panic!("Input was not a string"),
+                })
+                .collect::<Vec<_>>();
+            Ok(words.join(" "))
+        }
+
+        eval.register((&[("call", ANY_I32)], handler)).unwrap();
+        eval.set_default_handler(Some(default));
+
+        assert_eq!(
+            Ok("hello there".into()),
+            eval.interpret_single("hello there").unwrap()
+        );
+        assert_eq!(
+            Ok("say command".into()),
+            eval.interpret_single("say (call 1)").unwrap()
+        );
+        assert_eq!(
+            Ok("command".into()),
+            eval.interpret_single("call 1").unwrap()
+        );
+        assert_eq!(
+            Err("Expected <i32>, e.g. -42. Decider: got string: x".into()),
+            eval.interpret_single("call x").unwrap()
+        );
+        assert_eq!(Ok("call <i32>".into()), eval.interpret_single("?").unwrap());
+
+        eval.set_default_handler(None);
+        assert_eq!(
+            Err("Unrecognized mapping: hello".into()),
+            eval.interpret_single("hello there").unwrap()
+        );
+    }
+
+    #[test]
+    fn dry_run() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn add(context: &mut u32, args: &[Type]) -> Result<String, String> {
+            let amount = match args[0] {
+                Type::U8(amount) => u32::from(amount),
+                _ => panic!("Input was not a u8"),
+            };
+            if !is_dry_run() {
+                *context += amount;
+            }
+            Ok((*context + amount).to_string())
+        }
+
+        eval.register((&[("add", ANY_U8)], add)).unwrap();
+
+        eval.set_dry_run(true);
+        assert_eq!(Ok("5".into()), eval.interpret_single("add 5").unwrap());
+        assert_eq!(0, *eval.context());
+        assert!(!is_dry_run());
+
+        eval.set_dry_run(false);
+        assert_eq!(Ok("10".into()), eval.interpret_single("add 5").unwrap());
+        assert_eq!(5, *eval.context());
+
+        eval.set_dry_run(true);
+        eval.set_handler_timeout(Some(Duration::from_secs(10)));
+        assert_eq!(Ok("10".into()), eval.interpret_single("add 5").unwrap());
+        assert_eq!(5, *eval.context());
+    }
+
+    #[test]
+    fn register_runtime_decider() {
+        let mut eval = Evaluator::new(0i32);
+
+        fn ranged<const MIN: i32, const MAX: i32>(
+            input: &[&str],
+            out: &mut SVec<Type>,
+        ) -> Decision<String> {
+            match input.first().map(|arg| arg.parse::<i32>()) {
+                Some(Ok(number)) if MIN <= number && number <= MAX => {
+                    out.push(Type::I32(number));
+                    Decision::Accept(1)
+                }
+                _ => Decision::Deny(format!["not in range {}..={}", MIN, MAX]),
+            }
+        }
+
+        fn set(context: &mut i32, args: &[Type]) -> Result<String, String> {
+            match args[0] {
+                Type::I32(number) => *context = number,
+                _ => panic!("Input was not an i32"),
+            }
+            Ok("".into())
+        }
+
+        let (min, max) = (-5, 5);
+        let decider = Box::new(Decider {
+            description: Box::leak(format!["<{}..={}>", min, max].into_boxed_str()),
+            decider: ranged::<-5, 5>,
+        });
+        eval.register_with_decider(&["volume", "set"], decider, set)
+            .unwrap();
+
+        assert_eq!(
+            Ok("".into()),
+            eval.interpret_single("volume set 3").unwrap()
+        );
+        assert_eq!(3, *eval.context());
+        assert_eq!(
+            Err("Expected <-5..=5>. Decider: not in range -5..=5".into()),
+            eval.interpret_single("volume set 6").unwrap()
+        );
+        assert_eq!(
+            Ok("volume set <-5..=5>".into()),
+            eval.interpret_single("?").unwrap()
+        );
+    }
+
+    #[test]
+    fn partial_results_in_nested_error() {
+        let mut eval = Evaluator::new(());
+
+        fn echo(_: &mut (), args: &[Type]) -> Result<String, String> {
+            match &args[0] {
+                Type::String(string) => Ok(string.clone()),
+                _ => panic!("Input was not a string"),
+            }
+        }
+
+        fn fail(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Err("failed".into())
+        }
+
+        eval.register((&[("echo", ANY_STRING)], echo)).unwrap();
+        eval.register((&[("fail", None)], fail)).unwrap();
+        eval.register((&[("join", MANY_STRING)], echo)).unwrap();
+
+        assert_eq!(
+            Err("failed".into()),
+            eval.interpret_single("join (echo a) (echo b) (fail)")
+                .unwrap()
+        );
+
+        eval.set_partial_results(true);
+        assert_eq!(
+            Err("failed (partial: [a, b])".into()),
+            eval.interpret_single("join (echo a) (echo b) (fail)")
+                .unwrap()
+        );
+        assert_eq!(
+            Err("failed (partial: [b])".into()),
+            eval.interpret_single("join a (echo b) (fail) c").unwrap()
+        );
+        assert_eq!(
+            Err("failed (partial: []) (partial: [a])".into()),
+            eval.interpret_single("join (echo a) (echo (fail))")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn replay_log() {
+        fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+            match &args[0] {
+                Type::String(string) => Ok(string.clone() + "\n\u{0}"),
+                _ => panic!("Input was not a string"),
+            }
+        }
+
+        let mut log = vec![];
+        {
+            let mut eval = Evaluator::new(());
+            eval.register((&[("call", ANY_STRING)], handler)).unwrap();
+            eval.set_replay_sink(Some(Box::new(&mut log)));
+
+            eval.interpret_single("call  x").unwrap().unwrap();
+            eval.interpret_single("call (#a\nb\u{1b}\")")
+                .unwrap()
+                .unwrap();
+            eval.interpret_single("nothing").unwrap().unwrap_err();
+        }
+
+        assert_eq!(
+            "> call x\n\
+             < Ok(\"x\\n\\0\")\n\
+             > call (#a\\nb\\u{1b}\\\")\n\
+             < Ok(\"a\\nb\\u{1b}\\\"\\n\\0\")\n\
+             > nothing\n\
+             < Err(\"Unrecognized mapping: nothing\")\n",
+            String::from_utf8(log.clone()).unwrap()
+        );
+
+        assert_eq!(
+            vec![
+                ("call x".to_string(), Ok("x\n\0".to_string())),
+                ("call (#a\nb\u{1b}\")".into(), Ok("a\nb\u{1b}\"\n\0".into())),
+                (
+                    "nothing".into(),
+                    Err("Unrecognized mapping: nothing".into())
+                ),
+            ],
+            read_replay(&log[..]).unwrap()
+        );
+
+        let mut eval = Evaluator::new(());
+        eval.register((&[("call", ANY_STRING)], handler)).unwrap();
+        assert!(eval.replay(&log[..]).unwrap().is_empty());
+
+        let mut eval = Evaluator::new(());
+        assert_eq!(
+            vec![(
+                "call x".to_string(),
+                Ok("x\n\0".to_string()),
+                Err("Unrecognized mapping: call".to_string())
+            )],
+            eval.replay(&b"> call x\n< Ok(\"x\\n\\0\")\n"[..]).unwrap()
+        );
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            read_replay(&b"> call x\n< Ok(x)\n"[..]).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn denial_shows_example() {
+        let mut eval = Evaluator::new(());
+
+        fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        eval.register((&[("call", ANY_I32)], handler)).unwrap();
+        assert_eq!(
+            Feedback::Err("Expected <i32>, e.g. -42. Decider: got string: alpha".into()),
+            eval.interpret_single("call alpha").unwrap()
+        );
+
+        eval.set_example("<i32>", "7");
+        assert_eq!(
+            Feedback::Err("Expected <i32>, e.g. 7. Decider: got string: alpha".into()),
+            eval.interpret_single("call alpha").unwrap()
+        );
+    }
+
+    #[test]
+    fn a_command_triggers_a_channel() {
+        let (tx, rx) = bounded(1);
+
+        let mut eval = Evaluator::new(tx);
+
+        fn handler(context: &mut Sender<f32>, args: &[Type]) -> Result<String, String> {
+            match args[0] {
+                Type::F32(number) => context.send(number).unwrap(),
+                _ => panic!("Input was not an f32"),
+            }
+            Ok("".into())
+        }
+
+        eval.register((&[("call", ANY_F32)], handler)).unwrap();
+
+        eval.interpret_single("call 3.14159").unwrap().unwrap();
+
+        assert_eq!(3.14159, rx.recv().unwrap());
+    }
+
+    #[test]
+    fn reuse_scratch_across_nested_statements() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn add(context: &mut u32, args: &[Type]) -> Result<String, String> {
+            *context += 1;
+            let sum = args
+                .iter()
+                .map(|arg| match arg {
+                    Type::I32(number) => number,
+                    _ => panic!("Input was not an i32"),
+                })
+                .sum::<i32>();
+            Ok(sum.to_string())
+        }
+
+        eval.register((&[("add", MANY_I32)], add)).unwrap();
+
+        let mut scratch = ParseScratch::default();
+        for _ in 0..3 {
+            assert_eq!(
+                Feedback::Ok("10".into()),
+                eval.interpret_single_with("add (add 1 (add 1 2)) (add 3 (#3))", &mut scratch)
+                    .unwrap()
+            );
+            assert_eq!(
+                Feedback::Ok("3".into()),
+                eval.interpret_single_with("add 1 2", &mut scratch).unwrap()
+            );
+        }
+        assert_eq!(15, *eval.context());
+        assert_eq!(3, scratch.frames.len());
+        assert!(eval.scratch.is_none());
+
+        assert_eq!(
+            Feedback::Err("Unrecognized mapping: nope".into()),
+            eval.interpret_single_with("add (add 1 (nope)) 1", &mut scratch)
+                .unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok("5".into()),
+            eval.interpret_single_with("add (add 1 1) 3", &mut scratch)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn limit_output_length() {
+        let mut eval = Evaluator::new(());
+
+        fn dump(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("åbcdefghij".into())
+        }
+
+        eval.register((&[("dump", None)], dump)).unwrap();
+
+        eval.set_max_output_len(Some(11), OutputOverflow::Truncate);
+        assert_eq!(
+            Feedback::Ok("åbcdefghij".into()),
+            eval.interpret_single("dump").unwrap()
+        );
+
+        eval.set_max_output_len(Some(4), OutputOverflow::Truncate);
+        assert_eq!(
+            Feedback::Ok("åbc...(truncated)".into()),
+            eval.interpret_single("dump").unwrap()
+        );
+        eval.set_max_output_len(Some(1), OutputOverflow::Truncate);
+        assert_eq!(
+            Feedback::Ok("...(truncated)".into()),
+            eval.interpret_single("dump").unwrap()
+        );
+
+        eval.set_max_output_len(Some(4), OutputOverflow::Error);
+        assert_eq!(
+            Feedback::Err("Output too long: 11 bytes, limit is 4".into()),
+            eval.interpret_single("dump").unwrap()
+        );
+
+        eval.set_max_output_len(None, OutputOverflow::Error);
+        assert_eq!(
+            Feedback::Ok("åbcdefghij".into()),
+            eval.interpret_single("dump").unwrap()
+        );
+    }
+
+    #[test]
+    fn disabled_substitution_passes_literal_text() {
+        let mut eval = Evaluator::new(());
+
+        fn echo(_: &mut (), args: &[Type]) -> Result<String, String> {
+            match args[0] {
+                Type::String(ref string) => Ok(string.clone()),
+                _ => panic!("Input was not a string"),
+            }
+        }
+
+        eval.register((&[("echo", ANY_STRING)], echo)).unwrap();
+        eval.interpret_single("set x 1").unwrap().unwrap();
+        assert_eq!(
+            Feedback::Ok("1".into()),
+            eval.interpret_single("echo (get x)").unwrap()
+        );
+
+        eval.set_substitution_enabled(false);
+        assert_eq!(
+            Feedback::Ok("get x".into()),
+            eval.interpret_single("echo (get x)").unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok("get x".into()),
+            eval.interpret_single("echo (#get x)").unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok("echo (get x)".into()),
+            eval.interpret_single("echo (echo (get x))").unwrap()
+        );
+    }
+
+    #[test]
+    fn handlers_emit_events_to_the_host() {
+        #[derive(Debug, PartialEq)]
+        enum Event {
+            Spawn(String),
+            Despawn(String),
+        }
+
+        fn spawn(_: &mut (), args: &[Type]) -> Result<String, String> {
+            if let Type::Atom(ref name) = args[0] {
+                emit(Event::Spawn(name.clone()));
+                emit(Event::Despawn(name.clone()));
+                emit("not an event");
+            }
+            Ok("spawned".into())
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let mut eval = Evaluator::new(());
+        eval.register((&[("spawn", ANY_ATOM)], spawn)).unwrap();
+
+        eval.interpret_single("spawn orc").unwrap().unwrap();
+        assert!(rx.try_recv().is_err());
+
+        eval.set_event_emitter(Some(Box::new(tx)));
+        assert_eq!(
+            Feedback::Ok("spawned".into()),
+            eval.interpret_single("spawn orc").unwrap()
+        );
+        assert_eq!(Event::Spawn("orc".into()), rx.try_recv().unwrap());
+        assert_eq!(Event::Despawn("orc".into()), rx.try_recv().unwrap());
+        assert!(rx.try_recv().is_err());
+
+        eval.set_dry_run(true);
+        eval.interpret_single("spawn orc").unwrap().unwrap();
+        assert!(rx.try_recv().is_err());
+
+        eval.set_dry_run(false);
+        eval.set_handler_timeout(Some(Duration::from_secs(5)));
+        eval.interpret_single("spawn goblin").unwrap().unwrap();
+        assert_eq!(Event::Spawn("goblin".into()), rx.try_recv().unwrap());
+        assert_eq!(Event::Despawn("goblin".into()), rx.try_recv().unwrap());
+    }
+
+    #[test]
+    fn base64_decoding_into_raw() {
+        let (tx, rx) = bounded(1);
+
+        let mut eval = Evaluator::new(tx);
+
+        fn handler(context: &mut Sender<Vec<u8>>, args: &[Type]) -> Result<String, String> {
+            match args[0] {
+                Type::Raw(ref bytes) => context.send(bytes.clone()).unwrap(),
+                _ => panic!("Input not raw bytes"),
+            }
+            Ok("".into())
+        }
+
+        eval.register((&[("call", ANY_BASE64)], handler)).unwrap();
+
+        eval.interpret_single("call iVBORw0KGgo").unwrap().unwrap();
+
+        // PNG magic number
+        static PNG_MAGIC_NUMBER: &[u8] = &[0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
+        assert_eq!(PNG_MAGIC_NUMBER, &rx.recv().unwrap()[..]);
+    }
+
+    #[test]
+    fn disallowed_types_are_rejected() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("".into())
+        }
+
+        eval.register((&[("raw", ANY_BASE64)], handler)).unwrap();
+        eval.register((&[("number", ANY_I32)], handler)).unwrap();
+        eval.set_allowed_types(&[TypeKind::I32, TypeKind::String]);
+
+        assert_eq!(
+            Err("Argument type not allowed: Raw".into()),
+            eval.interpret_single("raw iVBORw0KGgo").unwrap()
+        );
+        assert_eq!(0, *eval.context());
+        assert_eq!(Ok("".into()), eval.interpret_single("number 1").unwrap());
+        assert_eq!(1, *eval.context());
+
+        eval.allow_all_types();
+        assert_eq!(
+            Ok("".into()),
+            eval.interpret_single("raw iVBORw0KGgo").unwrap()
+        );
+        assert_eq!(2, *eval.context());
+    }
+
+    #[test]
+    fn batch_of_statements() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok(context.to_string())
+        }
+
+        eval.register((&[("call", ANY_STRING)], handler)).unwrap();
+
+        assert_eq!(
+            vec![
+                Ok(Ok("1".into())),
+                Ok(Err("Unrecognized mapping: nothing".into())),
+                Ok(Ok("3".into())),
+                Err(ParseError::PrematureRightParenthesis),
+            ],
+            eval.interpret_batch("call a; nothing ;; call (call (#;));)")
+        );
+        assert_eq!(3, *eval.context());
+    }
+
+    #[test]
+    fn newline_in_parentheses() {
+        let mut eval = Evaluator::new(());
+
+        fn echo(_: &mut (), args: &[Type]) -> Result<String, String> {
+            match args {
+                [Type::String(string)] => Ok(string.clone()),
+                _ => Err("expected a string".into()),
+            }
+        }
+
+        eval.register((&[("echo", ANY_STRING)], echo)).unwrap();
+
+        let code = "echo (echo\nhi)\necho x";
+        assert_eq!(
+            Ok(vec![Ok("hi".into()), Ok("x".into())]),
+            eval.interpret_all(code)
+        );
+
+        eval.set_newline_closes_in_paren(true);
+        assert_eq!(
+            Err(ParseError::DanglingLeftParenthesis),
+            eval.interpret_all(code)
+        );
+        assert_eq!(
+            Ok(vec![Ok("hi".into()), Ok("x".into())]),
+            eval.interpret_all("echo (echo hi)\necho x")
+        );
+        assert_eq!(
+            Ok(Ok("x".into())),
+            eval.interpret_multiple("echo (echo hi)\necho x")
+        );
+    }
+
+    #[test]
+    fn continue_or_stop_after_handler_error() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn add(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok(context.to_string())
+        }
+
+        fn fail(_: &mut u32, _: &[Type]) -> Result<String, String> {
+            Err("failed".into())
+        }
+
+        eval.register((&[("add", None)], add)).unwrap();
+        eval.register((&[("fail", None)], fail)).unwrap();
+
+        let code = "add\nfail\n\n  add\n";
+        assert_eq!(
+            Ok(vec![Ok("1".into()), Err("failed".into()), Ok("2".into())]),
+            eval.interpret_all(code)
+        );
+        assert_eq!(Ok(Ok("4".into())), eval.interpret_multiple(code));
+        assert_eq!(4, *eval.context());
+
+        eval.set_stop_on_handler_error(true);
+        assert_eq!(
+            Ok(vec![Ok("5".into()), Err("failed".into())]),
+            eval.interpret_all(code)
+        );
+        assert_eq!(Ok(Err("failed".into())), eval.interpret_multiple(code));
+        assert_eq!(6, *eval.context());
+
+        assert_eq!(
+            Err(ParseError::PrematureRightParenthesis),
+            eval.interpret_all("add\n)")
+        );
+        assert_eq!(7, *eval.context());
+        assert_eq!(Err(ParseError::NothingToParse), eval.interpret_all(" \n "));
+    }
+
+    #[test]
+    fn touching_subcommand() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("string".into())
+        }
+
+        eval.register((&[("call", ANY_STRING)], handler)).unwrap();
+        eval.interpret_single("call(call _)").unwrap().unwrap();
+        assert_eq!(2, *eval.context());
+        eval.interpret_single("call(call(call _))")
+            .unwrap()
+            .unwrap();
+        assert_eq!(2 + 3, *eval.context());
+        eval.interpret_multiple("call(call(call _))")
+            .unwrap()
+            .unwrap();
+        assert_eq!(2 + 3 + 3, *eval.context());
+    }
+
+    #[test]
+    fn recursive_call_stack() {
+        let mut eval = Evaluator::new(());
+
+        fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("string".into())
+        }
+
+        eval.register((&[("call", ANY_STRING)], handler)).unwrap();
+
+        let mut call = "call".to_string();
+        let count = 1000;
+        for _ in 0..count {
+            call += "(call";
+        }
+        call += " _";
+        for _ in 0..count {
+            call += ")";
+        }
+
+        assert_eq!(
+            Err("Recursion limit reached: 100".into()),
+            eval.interpret_single(&call).unwrap()
+        );
+        assert_eq!(0, eval.depth());
+    }
+
+    #[test]
+    fn check_depth_before_recursing() {
+        let mut inner = Evaluator::new(());
+        inner.set_recursion_limit(2);
+
+        fn id(_: &mut (), args: &[Type]) -> Result<String, String> {
+            match &args[0] {
+                Type::String(string) => Ok(string.clone()),
+                _ => panic!("Input was not a string"),
+            }
+        }
+
+        inner.register((&[("id", ANY_STRING)], id)).unwrap();
+
+        fn nest(context: &mut Evaluator<()>, args: &[Type]) -> Result<String, String> {
+            let levels = match args[0] {
+                Type::Usize(levels) => levels,
+                _ => panic!("Input was not a usize"),
+            };
+            if context.would_exceed_limit(levels) {
+                return Ok("too deep".into());
+            }
+            let mut statement = "id".to_string();
+            for _ in 0..levels {
+                statement += " (id";
+            }
+            statement += " x";
+            for _ in 0..levels {
+                statement += ")";
+            }
+            context.interpret_single(&statement).unwrap()
+        }
+
+        let mut eval = Evaluator::new(inner);
+        eval.register((&[("nest", ANY_USIZE)], nest)).unwrap();
+
+        assert_eq!(0, eval.context().depth());
+        assert_eq!(2, eval.context().recursion_limit());
+        assert_eq!(Ok("x".into()), eval.interpret_single("nest 2").unwrap());
+        assert_eq!(
+            Ok("too deep".into()),
+            eval.interpret_single("nest 3").unwrap()
+        );
+        assert_eq!(
+            Err("Recursion limit reached: 2".into()),
+            eval.context_mut()
+                .interpret_single("id (id (id (id x)))")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn question_only_handles_one() {
+        let mut eval = Evaluator::new(());
+        assert_eq!("", eval.interpret_single("? _").unwrap().unwrap());
+        assert_eq!(
+            "Too many arguments to: ?",
+            eval.interpret_single("? _ _").unwrap().unwrap_err()
+        );
+        assert_eq!(
+            "Too many arguments to: ?",
+            eval.interpret_single("? _ _ _ _ _").unwrap().unwrap_err()
+        );
+        assert_eq!(
+            "Too many arguments to: ?",
+            eval.interpret_multiple("? _ _ _ _ _").unwrap().unwrap_err()
+        );
+    }
+
+    #[test]
+    fn hidden_commands_are_not_listed() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("".into())
+        }
+
+        eval.register((&[("debug", None), ("visible", None)], handler))
+            .unwrap();
+        eval.register_hidden((&[("debug", None), ("secret", ANY_I32)], handler))
+            .unwrap();
+
+        eval.interpret_single("debug secret 1").unwrap().unwrap();
+        assert_eq!(1, *eval.context());
+
+        assert_eq!(
+            Ok("debug visible ".into()),
+            eval.interpret_single("?").unwrap()
+        );
+        assert_eq!(
+            Ok("visible (final)".into()),
+            eval.interpret_single("autocomplete debug").unwrap()
+        );
+        assert!(eval.export_schema().get(&["debug", "secret"]).is_none());
+
+        eval.set_show_hidden(true);
+        assert_eq!(
+            Ok("debug secret <i32>\ndebug visible ".into()),
+            eval.interpret_single("?").unwrap()
+        );
+        assert_eq!(
+            Ok("secret <i32> (final), visible (final)".into()),
+            eval.interpret_single("autocomplete debug").unwrap()
+        );
+    }
+
+    #[cfg(feature = "with-futures")]
+    #[test]
+    fn interpret_lines_from_stream() {
+        use futures::{executor::block_on, stream, StreamExt};
+
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok(context.to_string())
+        }
+
+        eval.register((&[("call", None)], handler)).unwrap();
+
+        let lines = stream::iter(vec!["call".to_string(), "x".into(), "call (".into()]);
+        let results = block_on(eval.interpret_stream(lines).collect::<Vec<_>>());
+        assert_eq!(
+            vec![
+                Ok(Ok("1".into())),
+                Ok(Err("Unrecognized mapping: x".into())),
+                Err(ParseError::DanglingLeftParenthesis),
+            ],
+            results
+        );
+        assert_eq!(1, *eval.context());
+    }
+
+    #[cfg(feature = "with-futures")]
+    #[test]
+    fn list_and_kill_jobs() {
+        use futures::{
+            future::{pending, BoxFuture},
+            task::noop_waker,
+            FutureExt,
+        };
+        use std::task::{Context, Poll};
+
+        struct Server {
+            jobs: JobRegistry,
+            runs: Vec<BoxFuture<'static, ()>>,
+        }
+
+        fn slow(context: &mut Server, _: &[Type]) -> Result<String, String> {
+            let (id, run) = context.jobs.spawn("slow", pending());
+            context.runs.push(run.boxed());
+            Ok(id.to_string())
+        }
+
+        let jobs = JobRegistry::new();
+        let mut eval = Evaluator::new(Server {
+            jobs: jobs.clone(),
+            runs: vec![],
+        });
+        eval.register((&[("slow", None)], slow)).unwrap();
+
+        assert_eq!(
+            Err("Unrecognized mapping: jobs".into()),
+            eval.interpret_single("jobs").unwrap()
+        );
+        eval.set_jobs(Some(jobs), 1);
+        assert_eq!(
+            Err("insufficient permission".into()),
+            eval.interpret_single("jobs").unwrap()
+        );
+        eval.set_permission_level(1);
+        assert_eq!(Ok("No jobs".into()), eval.interpret_single("jobs").unwrap());
+
+        assert_eq!(Ok("1".into()), eval.interpret_single("slow").unwrap());
+        let mut run = eval.context_mut().runs.pop().unwrap();
+        let waker = noop_waker();
+        let mut context = Context::from_waker(&waker);
+        assert_eq!(Poll::Pending, run.as_mut().poll(&mut context));
+        assert_eq!(
+            Ok("1 slow: running".into()),
+            eval.interpret_single("jobs").unwrap()
+        );
+
+        assert_eq!(Ok("".into()), eval.interpret_single("kill 1").unwrap());
+        assert_eq!(Poll::Ready(()), run.as_mut().poll(&mut context));
+        assert_eq!(
+            Ok("1 slow: cancelled".into()),
+            eval.interpret_single("jobs").unwrap()
+        );
+        assert_eq!(
+            Err("Job is not running: 1".into()),
+            eval.interpret_single("kill 1").unwrap()
+        );
+        assert_eq!(
+            Err("Unknown job: 2".into()),
+            eval.interpret_single("kill 2").unwrap()
+        );
+        assert_eq!(
+            Err("Usage: kill <id>".into()),
+            eval.interpret_single("kill").unwrap()
+        );
+    }
+
+    #[test]
+    fn consistency_check_catches_anomalies() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(_: &mut u32, _: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        eval.register((&[("debug", None), ("speed", ANY_F32)], handler))
+            .unwrap();
+        eval.register_hidden((&[("debug", None), ("secret", None)], handler))
+            .unwrap();
+        eval.register_with_perm((&[("shutdown", None)], handler), 1)
+            .unwrap();
+        assert_eq!(Ok(()), eval.check_consistency());
+
+        // Literals that only differ in case are ambiguous when matching case-insensitively
+        eval.register((&[("debug", None), ("Speed", None)], handler))
+            .unwrap();
+        assert_eq!(Ok(()), eval.check_consistency());
+        eval.set_case_insensitive(true);
+        assert_eq!(
+            Err(vec![
+                "Literals differ only in case: debug Speed, debug speed".into()
+            ]),
+            eval.check_consistency()
+        );
+        eval.unregister(&["debug", "Speed"]).unwrap();
+        assert_eq!(Ok(()), eval.check_consistency());
+
+        // A literal following a decider that may take it as an argument is ambiguous
+        eval.register((&[("volume", MANY_STRING), ("up", None)], handler))
+            .unwrap();
+        eval.register((&[("speed", MANY_I32), ("up", None)], handler))
+            .unwrap();
+        assert_eq!(
+            Err(vec![
+                "Decider <string> ... of volume accepts the literal following it: up".into()
+            ]),
+            eval.check_consistency()
+        );
+    }
+
+    #[test]
+    fn commands_using_decider() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(_: &mut u32, _: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        eval.register_many(&[
+            (&[("move", ANY_F32), ("turn", ANY_F32)], handler),
+            (&[("spawn", ANY_STRING)], handler),
+            (&[("speed", POSITIVE_F32)], handler),
+            (&[("debug", None), ("scale", ANY_F32_LOCALE)], handler),
+            (&[("status", None)], handler),
+        ])
+        .unwrap();
+        eval.register_hidden((&[("debug", None), ("zoom", ANY_F32)], handler))
+            .unwrap();
+        assert!(eval.register((&[("spawn", ANY_F32)], handler)).is_err());
+
+        assert_eq!(
+            vec![
+                vec!["move".to_string(), "turn".to_string()],
+                vec!["debug".to_string(), "scale".to_string()],
+                vec!["debug".to_string(), "zoom".to_string()],
+            ],
+            eval.commands_using("<f32>")
+        );
+        assert_eq!(
+            vec![vec!["spawn".to_string()]],
+            eval.commands_using("<string>")
+        );
+        assert_eq!(
+            vec![vec!["speed".to_string()]],
+            eval.commands_using("<f32>=0>")
+        );
+        assert!(eval.commands_using("<i32>").is_empty());
+    }
+
+    #[test]
+    fn ghost_hints() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(_: &mut u32, _: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        eval.register((&[("status", None)], handler)).unwrap();
+        eval.register((&[("debug", None), ("visible", None)], handler))
+            .unwrap();
+        eval.register((&[("debug", None), ("verbose", None)], handler))
+            .unwrap();
+        eval.register_hidden((&[("debug", None), ("secret", None)], handler))
+            .unwrap();
+        eval.register((&[("call", ANY_F32), ("then", None)], handler))
+            .unwrap();
+
+        // Mid-literal
+        assert_eq!(Some("tus".into()), eval.ghost_hint("sta"));
+        assert_eq!(Some("ebug".into()), eval.ghost_hint("d"));
+        assert_eq!(Some("sible".into()), eval.ghost_hint("debug vi"));
+        assert_eq!(None, eval.ghost_hint("debug v"));
+        assert_eq!(None, eval.ghost_hint("debug s"));
+        assert_eq!(None, eval.ghost_hint("status"));
+        assert_eq!(None, eval.ghost_hint("x"));
+
+        // After a complete literal
+        assert_eq!(Some("verbose|visible".into()), eval.ghost_hint("debug "));
+        assert_eq!(Some("<f32>".into()), eval.ghost_hint("call "));
+        assert_eq!(None, eval.ghost_hint("call 1"));
+        assert_eq!(Some("then".into()), eval.ghost_hint("call 1.5 "));
+        assert_eq!(Some("en".into()), eval.ghost_hint("call 1.5 th"));
+        assert_eq!(None, eval.ghost_hint("status "));
+        assert_eq!(None, eval.ghost_hint("x "));
+        assert_eq!(None, eval.ghost_hint(""));
+    }
+
+    #[test]
+    fn many_f32_leaves_the_rest_to_the_next_node() {
+        let mut eval = Evaluator::new(());
+
+        fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+            Ok(format!["{:?}", args])
+        }
+
+        eval.register((&[("call", MANY_F32), ("x", None)], handler))
+            .unwrap();
+
+        assert_eq!(
+            Ok("[F32(1.0), F32(2.5)]".into()),
+            eval.interpret_single("call 1.0 2.5 x").unwrap()
+        );
+        assert_eq!(
+            Ok("call <f32> ...: consumed 2 [F32(1.0), F32(2.5)]\n\
+                x: no decider\n\
+                finalizer: present"
+                .into()),
+            eval.interpret_single("test-decider call 1.0 2.5 x")
+                .unwrap()
+        );
+        assert_eq!(Ok("[]".into()), eval.interpret_single("call x").unwrap());
+    }
+
+    #[test]
+    fn many_u8_collects_one_raw_argument() {
+        let mut eval = Evaluator::new(());
+
+        fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+            match args {
+                [Type::Raw(bytes), Type::U8(offset)] => Ok(format!["{:?} at {}", bytes, offset]),
+                _ => panic!("Wrong arguments: {:?}", args),
+            }
+        }
+
+        eval.register((&[("write", MANY_U8), ("at", ANY_U8)], handler))
+            .unwrap();
+
+        assert_eq!(
+            Ok("[10, 20, 30, 255] at 4".into()),
+            eval.interpret_single("write 10 20 30 255 at 4").unwrap()
+        );
+        assert_eq!(
+            Ok("[] at 0".into()),
+            eval.interpret_single("write at 0").unwrap()
+        );
+    }
+
+    #[test]
+    fn substitution_limit() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("1".into())
+        }
+
+        eval.register((&[("one", None)], handler)).unwrap();
+        eval.register((&[("sum", MANY_I32)], handler)).unwrap();
+        eval.set_max_substitutions_per_statement(10);
+        assert_eq!(10, eval.max_substitutions_per_statement());
+
+        let wide = |count| String::from("sum") + &" (one)".repeat(count);
+
+        eval.interpret_single(&wide(10)).unwrap().unwrap();
+        assert_eq!(11, *eval.context());
+        eval.interpret_single(&wide(10)).unwrap().unwrap();
+        assert_eq!(22, *eval.context());
+
+        assert_eq!(
+            Err("Substitution limit reached: 10".into()),
+            eval.interpret_single(&wide(1000)).unwrap()
+        );
+        assert_eq!(32, *eval.context());
+
+        // Nested substitutions count towards the same limit
+        assert_eq!(
+            Err("Substitution limit reached: 10".into()),
+            eval.interpret_single(&(wide(5) + " (" + &wide(6) + ")"))
+                .unwrap()
+        );
+        eval.interpret_single("sum (#1) (#2) (#3) (#4) (#5) (#6) (#7) (#8) (#9) (#10) (#11)")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn operation_budget_resets_between_commands() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("1".into())
+        }
+
+        eval.register((&[("call", ANY_I32)], handler)).unwrap();
+        eval.set_operation_budget(Some(3));
+
+        eval.interpret_single("call (call (call 1))")
+            .unwrap()
+            .unwrap();
+        assert_eq!(3, eval.operations());
+        eval.interpret_single("call (call (call 1))")
+            .unwrap()
+            .unwrap();
+        assert_eq!(3, eval.operations());
+        assert_eq!(6, *eval.context());
+
+        assert_eq!(
+            Err("Operation budget exhausted: 3".into()),
+            eval.interpret_single("call (call (call (call 1)))")
+                .unwrap()
+        );
+        assert_eq!(6, *eval.context());
+
+        eval.interpret_single("call 1").unwrap().unwrap();
+        assert_eq!(1, eval.operations());
+
+        eval.set_operation_budget(None);
+        eval.interpret_single("call (call (call (call 1)))")
+            .unwrap()
+            .unwrap();
+        assert_eq!(4, eval.operations());
+    }
+
+    #[test]
+    fn lookup_time_budget() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn slow(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+            thread::sleep(Duration::from_millis(50));
+            (ANY_I32.unwrap().decider)(input, out)
+        }
+
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("".into())
+        }
+
+        const SLOW: SomeDec = Some(&Decider {
+            description: "<slow>",
+            decider: slow,
+        });
+
+        eval.register((&[("call", SLOW)], handler)).unwrap();
+        eval.register((&[("move", SLOW), ("to", SLOW)], handler))
+            .unwrap();
+        eval.set_lookup_time_budget(Some(Duration::from_millis(20)));
+
+        eval.interpret_single("call 1").unwrap().unwrap();
+        assert_eq!(
+            Err("command parsing timed out".into()),
+            eval.interpret_single("move 1 to 2").unwrap()
+        );
+        assert_eq!(
+            Err("command parsing timed out".into()),
+            eval.interpret_single("call (call 1)").unwrap()
+        );
+        assert_eq!(2, *eval.context());
+
+        eval.set_lookup_time_budget(None);
+        eval.interpret_single("move 1 to 2").unwrap().unwrap();
+        assert_eq!(3, *eval.context());
+    }
+
+    #[test]
+    fn trace_keeps_input_and_resolved_command() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn kick(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("".into())
+        }
+
+        fn one(_: &mut u32, _: &[Type]) -> Result<String, String> {
+            Ok("1".into())
+        }
+
+        eval.register((&[("server", None), ("kick", ANY_I32)], kick))
+            .unwrap();
+        eval.register((&[("one", None)], one)).unwrap();
+        eval.interpret_single("use server").unwrap().unwrap();
+
+        let (res, trace) = eval.interpret_traced("kick (one)");
+        res.unwrap().unwrap();
+        assert_eq!(
+            Trace {
+                input: "kick (one)".into(),
+                resolved: vec!["server".into(), "kick".into(), "1".into()],
+            },
+            trace
+        );
+        assert_eq!(1, *eval.context());
+
+        let (res, trace) = eval.interpret_traced("unknown");
+        assert!(res.unwrap().is_err());
+        assert_eq!("unknown", trace.input);
+        assert!(trace.resolved.is_empty());
+    }
+
+    #[test]
+    fn decider_warnings() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn mode(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+            match input.first() {
+                Some(&"fast") => {}
+                Some(&"quick") => warn("'quick' is deprecated, use 'fast'"),
+                _ => return Decision::Deny("expected fast".into()),
+            }
+            out.push(Type::String("fast".into()));
+            Decision::Accept(1)
+        }
+
+        fn handler(context: &mut u32, args: &[Type]) -> Result<String, String> {
+            *context += 1;
+            match &args[0] {
+                Type::String(mode) => Ok(mode.clone()),
+                _ => Err("wrong argument".into()),
+            }
+        }
+
+        const MODE: Decider<Type, String> = Decider {
+            description: "<mode>",
+            decider: mode,
+        };
+        eval.register((&[("mode", Some(&MODE))], handler)).unwrap();
+        eval.register((
+            &[("other", None), ("mode", Some(&MODE)), ("x", None)],
+            handler,
+        ))
+        .unwrap();
+
+        assert_eq!(
+            Ok("fast".into()),
+            eval.interpret_single("mode quick").unwrap()
+        );
+        assert_eq!(
+            &["'quick' is deprecated, use 'fast'".to_string()],
+            eval.warnings()
+        );
+        assert_eq!(1, *eval.context());
+
+        eval.interpret_single("mode fast").unwrap().unwrap();
+        assert!(eval.warnings().is_empty());
+
+        // Nested statements contribute to the warnings of the top-level statement
+        eval.interpret_single("mode (mode quick)").unwrap().unwrap();
+        assert_eq!(1, eval.warnings().len());
+
+        // Failed lookups do not leave warnings behind
+        eval.interpret_single("other mode quick")
+            .unwrap()
+            .unwrap_err();
+        assert!(eval.warnings().is_empty());
+
+        // Warnings of deciders run outside of a lookup do not pile up across statements
+        eval.set_case_insensitive(true);
+        for _ in 0..3 {
+            eval.interpret_single("MODE quick").unwrap().unwrap();
+            assert_eq!(1, eval.warnings().len());
+        }
+        assert!(WARNINGS.with(|warnings| warnings.borrow().len()) <= 1);
+    }
+
+    #[test]
+    fn cooldowns() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("".into())
+        }
+
+        let start = Instant::now();
+        let elapsed = Arc::new(RwLock::new(Duration::from_secs(0)));
+        let clock = elapsed.clone();
+        eval.set_clock(move || start + *clock.read().unwrap());
+
+        eval.register_with_cooldown((&[("restart", None)], handler), Duration::from_secs(10))
+            .unwrap();
+        eval.register_with_cooldown(
+            (&[("server", None), ("kick", ANY_I32)], handler),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        eval.register((&[("status", None)], handler)).unwrap();
+
+        eval.interpret_single("restart").unwrap().unwrap();
+        assert_eq!(
+            Err("on cooldown, 10 s remaining".into()),
+            eval.interpret_single("restart").unwrap()
+        );
+        assert_eq!(1, *eval.context());
+
+        // Other commands are not affected
+        eval.interpret_single("status").unwrap().unwrap();
+        eval.interpret_single("status").unwrap().unwrap();
+        eval.interpret_single("server kick 1").unwrap().unwrap();
+        assert_eq!(4, *eval.context());
+
+        *elapsed.write().unwrap() = Duration::from_millis(3500);
+        assert_eq!(
+            Err("on cooldown, 7 s remaining".into()),
+            eval.interpret_single("restart").unwrap()
+        );
+        eval.interpret_single("use server").unwrap().unwrap();
+        assert_eq!(
+            Err("on cooldown, 2 s remaining".into()),
+            eval.interpret_single("kick 2").unwrap()
+        );
+        eval.interpret_single("use").unwrap().unwrap();
+
+        *elapsed.write().unwrap() = Duration::from_secs(10);
+        eval.interpret_single("restart").unwrap().unwrap();
+        eval.interpret_single("server kick 2").unwrap().unwrap();
+        assert_eq!(6, *eval.context());
+        assert_eq!(
+            Err("on cooldown, 10 s remaining".into()),
+            eval.interpret_single("restart").unwrap()
+        );
+    }
+
+    #[test]
+    fn cooldown_starts_after_successful_run() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(context: &mut u32, args: &[Type]) -> Result<String, String> {
+            if let Type::I32(0) = args[0] {
+                return Err("zero".into());
+            }
+            if is_dry_run() {
+                return Ok("".into());
+            }
+            if !is_confirmed() {
+                return needs_confirmation();
+            }
+            *context += 1;
+            Ok("".into())
+        }
+
+        let start = Instant::now();
+        eval.set_clock(move || start);
+        eval.register_with_cooldown((&[("nuke", ANY_I32)], handler), Duration::from_secs(10))
+            .unwrap();
+
+        eval.set_dry_run(true);
+        eval.interpret_single("nuke 1").unwrap().unwrap();
+        eval.set_dry_run(false);
+        eval.interpret_single("nuke 0").unwrap().unwrap_err();
+        eval.set_allowed_types(&[TypeKind::String]);
+        eval.interpret_single("nuke 1").unwrap().unwrap_err();
+        eval.allow_all_types();
+
+        // Asking for confirmation does not start the cooldown, confirming does
+        eval.interpret_single("nuke 1").unwrap().unwrap();
+        eval.interpret_single("no").unwrap().unwrap_err();
+        eval.interpret_single("nuke 1").unwrap().unwrap();
+        eval.interpret_single("confirm").unwrap().unwrap();
+        assert_eq!(1, *eval.context());
+        assert_eq!(
+            Err("on cooldown, 10 s remaining".into()),
+            eval.interpret_single("nuke 1").unwrap()
+        );
+    }
+
+    #[test]
+    fn permission_levels() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("".into())
+        }
+
+        eval.register((&[("debug", None), ("visible", None)], handler))
+            .unwrap();
+        eval.register_with_perm((&[("debug", None), ("kick", ANY_I32)], handler), 2)
+            .unwrap();
+        eval.register_with_perm((&[("shutdown", None)], handler), 2)
+            .unwrap();
+
+        assert_eq!(0, eval.permission_level());
+        assert_eq!(
+            Ok("debug visible ".into()),
+            eval.interpret_single("?").unwrap()
+        );
+        assert_eq!(
+            Ok("visible (final)".into()),
+            eval.interpret_single("autocomplete debug").unwrap()
+        );
+        assert!(eval.export_schema().get(&["debug", "kick"]).is_none());
+        assert_eq!(
+            Err("insufficient permission".into()),
+            eval.interpret_single("debug kick 1").unwrap()
+        );
+        assert_eq!(
+            Err("insufficient permission".into()),
+            eval.interpret_single("shutdown").unwrap()
+        );
+        eval.interpret_single("use debug").unwrap().unwrap();
+        assert_eq!(
+            Err("insufficient permission".into()),
+            eval.interpret_single("kick 1").unwrap()
+        );
+        eval.interpret_single("use").unwrap().unwrap();
+        assert_eq!(0, *eval.context());
+
+        eval.set_show_hidden(true);
+        assert_eq!(
+            Ok("debug visible ".into()),
+            eval.interpret_single("?").unwrap()
+        );
+
+        eval.set_permission_level(2);
+        assert_eq!(
+            Ok("debug kick <i32>\ndebug visible \nshutdown ".into()),
+            eval.interpret_single("?").unwrap()
+        );
+        eval.interpret_single("debug kick 1").unwrap().unwrap();
+        eval.interpret_single("shutdown").unwrap().unwrap();
+        assert_eq!(2, *eval.context());
+    }
+
+    #[test]
+    fn handler_timeout() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn sleeper(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            thread::sleep(Duration::from_secs(1));
+            Ok("".into())
+        }
+
+        fn quick(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("quick".into())
+        }
+
+        eval.register((&[("sleep", None)], sleeper)).unwrap();
+        eval.register((&[("quick", None)], quick)).unwrap();
+        eval.set_handler_timeout(Some(Duration::from_millis(10)));
+
+        assert_eq!(
+            Err("handler timed out".into()),
+            eval.interpret_single("sleep").unwrap()
+        );
+        assert_eq!(0, *eval.context());
+
+        assert_eq!(Ok("quick".into()), eval.interpret_single("quick").unwrap());
+        assert_eq!(1, *eval.context());
+
+        eval.set_handler_timeout(None);
+        assert_eq!(Ok("".into()), eval.interpret_single("sleep").unwrap());
+        assert_eq!(2, *eval.context());
+    }
+
+    #[test]
+    fn sticky_prefix() {
+        let mut eval = Evaluator::new(());
+
+        fn entities(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("entities".into())
+        }
+
+        fn memory(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("memory".into())
+        }
+
+        eval.register((&[("debug", None), ("entities", None)], entities))
+            .unwrap();
+        eval.register((&[("memory", None)], memory)).unwrap();
+
+        assert_eq!(
+            Err("Unrecognized mapping: entities".into()),
+            eval.interpret_single("entities").unwrap()
+        );
+
+        assert_eq!(Ok("".into()), eval.interpret_single("use debug").unwrap());
+        assert_eq!(&["debug".to_string()], eval.prefix());
+        assert_eq!(
+            Ok("entities".into()),
+            eval.interpret_single("entities").unwrap()
+        );
+        assert_eq!(
+            Ok("entities".into()),
+            eval.interpret_single("debug entities").unwrap()
+        );
+        assert_eq!(
+            Ok("memory".into()),
+            eval.interpret_single("memory").unwrap()
+        );
+
+        // Failures below the prefix are reported instead of retrying without the prefix
+        eval.register((&[("debug", None), ("level", ANY_U8)], entities))
+            .unwrap();
+        eval.register((&[("level", ANY_STRING)], memory)).unwrap();
+        assert_eq!(
+            Ok("entities".into()),
+            eval.interpret_single("level 3").unwrap()
+        );
+        assert_eq!(
+            Err("Expected <u8>, e.g. 255. Decider: got string: high".into()),
+            eval.interpret_single("level high").unwrap()
+        );
+
+        assert_eq!(Ok("".into()), eval.interpret_single("use").unwrap());
+        assert!(eval.prefix().is_empty());
+        assert_eq!(
+            Err("Unrecognized mapping: entities".into()),
+            eval.interpret_single("entities").unwrap()
+        );
+
+        assert_eq!(
+            Err("Unrecognized mapping: nothing".into()),
+            eval.interpret_single("use nothing").unwrap()
+        );
+        assert!(eval.prefix().is_empty());
+    }
+
+    #[test]
+    fn session_variables_over_shared_global() {
+        let global: GlobalVariables = Arc::new(RwLock::new(HashMap::new()));
+        global
+            .write()
+            .unwrap()
+            .insert("motd".into(), "welcome".into());
+
+        let mut first = Evaluator::new(());
+        let mut second = Evaluator::new(());
+        first.set_global_variables(Some(global.clone()));
+        second.set_global_variables(Some(global.clone()));
+
+        assert_eq!(
+            Ok("welcome".into()),
+            first.interpret_single("get motd").unwrap()
+        );
+        assert_eq!(Ok("".into()), first.interpret_single("set x 1").unwrap());
+        assert_eq!(
+            Ok("".into()),
+            first.interpret_single("set motd hi").unwrap()
+        );
+
+        assert_eq!(Ok("1".into()), first.interpret_single("get x").unwrap());
+        assert_eq!(Ok("hi".into()), first.interpret_single("get motd").unwrap());
+        assert_eq!(
+            Err("Unknown variable: x".into()),
+            second.interpret_single("get x").unwrap()
+        );
+        assert_eq!(
+            Ok("welcome".into()),
+            second.interpret_single("get motd").unwrap()
+        );
+        assert_eq!(
+            Some("welcome"),
+            global.read().unwrap().get("motd").map(|s| &s[..])
+        );
+
+        global.write().unwrap().insert("x".into(), "global".into());
+        assert_eq!(Ok("1".into()), first.interpret_single("get x").unwrap());
+        assert_eq!(
+            Ok("global".into()),
+            second.interpret_single("get x").unwrap()
+        );
+
+        assert_eq!(
+            Err("Usage: set <name> <value>".into()),
+            second.interpret_single("set x").unwrap()
+        );
+        assert_eq!(
+            Ok("".into()),
+            second.interpret_single("set y (get motd)").unwrap()
+        );
+        assert_eq!(
+            Ok("welcome".into()),
+            second.interpret_single("get y").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decider_reports_consumption() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("".into())
+        }
+
+        eval.register((&[("call", ANY_F32), ("x", TWO_STRINGS)], handler))
+            .unwrap();
+        eval.register((&[("call", None), ("y", None)], handler))
+            .unwrap();
+        eval.register_hidden((&[("call", None), ("secret", None)], handler))
+            .unwrap();
+        eval.register_with_perm((&[("admin", ANY_U8)], handler), 1)
+            .unwrap();
+
+        assert_eq!(
+            Ok("call <f32>: consumed 1 [F32(1.5)]\n\
+                x <string> <string>: consumed 2 [String(\"a\"), String(\"b\")]\n\
+                finalizer: present"
+                .into()),
+            eval.interpret_single("test-decider call 1.5 x a b")
+                .unwrap()
+        );
+        assert_eq!(
+            Ok("call <f32>: consumed 1 [F32(1.5)]\nfinalizer: absent".into()),
+            eval.interpret_single("test-decider call 1.5").unwrap()
+        );
+        assert_eq!(
+            Ok("call <f32>: consumed 1 [F32(1.5)]\n\
+                x <string> <string>: denied: expected 1 more string"
+                .into()),
+            eval.interpret_single("test-decider call 1.5 x a").unwrap()
+        );
+        assert_eq!(
+            Ok("call <f32>: consumed 1 [F32(2.0)]\ny: no decider\nfinalizer: present".into()),
+            eval.interpret_single("test-decider call 2 y").unwrap()
+        );
+        assert_eq!(
+            Ok("call <f32>: consumed 1 [F32(2.0)]\nz: unknown literal".into()),
+            eval.interpret_single("test-decider call 2 z").unwrap()
+        );
+        assert_eq!(
+            Ok("call <f32>: consumed 1 [F32(2.0)]\nsecret: unknown literal".into()),
+            eval.interpret_single("test-decider call 2 secret").unwrap()
+        );
+        assert_eq!(
+            Ok("admin: unknown literal".into()),
+            eval.interpret_single("test-decider admin 1").unwrap()
+        );
+        assert_eq!(0, *eval.context());
+    }
+
+    #[test]
+    fn explain_without_running() {
+        let mut eval = Evaluator::new(0);
+
+        fn handler(context: &mut usize, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("".into())
+        }
+
+        eval.register((&[("call", ANY_F32), ("x", TWO_STRINGS)], handler))
+            .unwrap();
+        eval.register((&[("get", ANY_ATOM)], handler)).unwrap();
+
+        assert_eq!(
+            Ok(
+                "tokens: [\"call\", \"1.5\", \"x\", \"(get name)\", \"(#a b)\"]\n\
+                nested (get name):\n    \
+                    tokens: [\"get\", \"name\"]\n    \
+                    path: get\n    \
+                    get <atom>: consumed 1 [Atom(\"name\")]\n    \
+                    finalizer: present\n\
+                path: call\n\
+                call <f32>: consumed 1 [F32(1.5)]\n\
+                x <string> <string>: depends on nested command (get name)"
+                    .into()
+            ),
+            eval.interpret_single("explain call 1.5 x (get name) (#a b)")
+                .unwrap()
+        );
+        assert_eq!(
+            Ok("tokens: [\"(get name)\", \"x\"]\n\
+                nested (get name):\n    \
+                    tokens: [\"get\", \"name\"]\n    \
+                    path: get\n    \
+                    get <atom>: consumed 1 [Atom(\"name\")]\n    \
+                    finalizer: present\n\
+                path: \n\
+                (get name): depends on nested command"
+                .into()),
+            eval.interpret_single("explain (get name) x").unwrap()
+        );
+
+        // Hidden commands are unknown, and without substitution nested commands are strings
+        eval.register_hidden((&[("secret", None)], handler))
+            .unwrap();
+        assert_eq!(
+            Ok("tokens: [\"secret\"]\npath: \nsecret: unknown literal".into()),
+            eval.interpret_single("explain secret").unwrap()
+        );
+        eval.set_substitution_enabled(false);
+        assert_eq!(
+            Ok(
+                "tokens: [\"call\", \"1.5\", \"x\", \"(#get name)\", \"(#a b)\"]\n\
+                path: call x\n\
+                call <f32>: consumed 1 [F32(1.5)]\n\
+                x <string> <string>: consumed 2 [String(\"get name\"), String(\"a b\")]\n\
+                finalizer: present"
+                    .into()
+            ),
+            eval.interpret_single("explain call 1.5 x (get name) (#a b)")
+                .unwrap()
+        );
+        assert_eq!(
+            Ok("tokens: [\"call\", \"x\"]\n\
+                path: \n\
+                call <f32>: denied: got string: x"
+                .into()),
+            eval.interpret_single("explain call x").unwrap()
+        );
+        assert_eq!(
+            Err("Usage: explain <command>".into()),
+            eval.interpret_single("explain").unwrap()
+        );
+        assert_eq!(0, *eval.context());
+    }
+
+    #[test]
+    fn deciders_see_earlier_arguments() {
+        let mut eval = Evaluator::new(());
+
+        fn value_for_category(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+            let allowed: &[&str] = match out.last() {
+                Some(Type::Atom(category)) if category == "mode" => &["fast", "slow"],
+                Some(Type::Atom(category)) if category == "color" => &["red", "blue"],
+                _ => &[],
+            };
+            match input.first() {
+                Some(value) if allowed.contains(value) => {
+                    out.push(Type::Atom((*value).into()));
+                    Decision::Accept(1)
+                }
+                _ => Decision::Deny(format!["expected one of: {}", allowed.join(", ")]),
+            }
+        }
+
+        const VALUE: SomeDec = Some(&Decider {
+            description: "<value>",
+            decider: value_for_category,
+        });
+
+        fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+            Ok(format!["{:?}", args])
+        }
+
+        eval.register_with_perm((&[("option", ANY_ATOM), ("to", VALUE)], handler), 1)
+            .unwrap();
+        eval.set_permission_level(1);
+
+        assert_eq!(
+            Ok("[Atom(\"mode\"), Atom(\"fast\")]".into()),
+            eval.interpret_single("option mode to fast").unwrap()
+        );
+        assert_eq!(
+            Ok("[Atom(\"color\"), Atom(\"red\")]".into()),
+            eval.interpret_single("option color to red").unwrap()
+        );
+        assert_eq!(
+            Err("Expected <value>. Decider: expected one of: red, blue".into()),
+            eval.interpret_single("option color to fast").unwrap()
+        );
+        assert_eq!(
+            Ok("option <atom>: consumed 1 [Atom(\"mode\")]\n\
+                to <value>: consumed 1 [Atom(\"slow\")]\n\
+                finalizer: present"
+                .into()),
+            eval.interpret_single("test-decider option mode to slow")
+                .unwrap()
+        );
+        assert_eq!(Some("<value>".into()), eval.ghost_hint("option mode to "));
+
+        eval.set_permission_level(0);
+        assert_eq!(
+            Err("insufficient permission".into()),
+            eval.interpret_single("option mode to fast").unwrap()
+        );
+    }
+
+    #[test]
+    fn confirm_or_cancel() {
+        let mut eval = Evaluator::new(3u32);
+
+        fn delete_all(count: &mut u32, _: &[Type]) -> Result<String, String> {
+            if !is_confirmed() {
+                return needs_confirmation();
+            }
+            *count = 0;
+            Ok("deleted".into())
+        }
+
+        fn count(count: &mut u32, _: &[Type]) -> Result<String, String> {
+            Ok(count.to_string())
+        }
+
+        eval.register((&[("delete-all", None)], delete_all))
+            .unwrap();
+        eval.register((&[("count", None)], count)).unwrap();
+
+        assert_eq!(
+            Ok("type 'confirm' to proceed".into()),
+            eval.interpret_single("delete-all").unwrap()
+        );
+        assert!(eval.pending_confirmation());
+        assert_eq!(
+            Err("cancelled".into()),
+            eval.interpret_single("count").unwrap()
+        );
+        assert!(!eval.pending_confirmation());
+        assert_eq!(Ok("3".into()), eval.interpret_single("count").unwrap());
+        assert_eq!(
+            Err("Unrecognized mapping: confirm".into()),
+            eval.interpret_single("confirm").unwrap()
+        );
+
+        eval.interpret_single("delete-all").unwrap().unwrap();
+        assert_eq!(3, *eval.context());
+        assert_eq!(
+            Ok("deleted".into()),
+            eval.interpret_single("confirm").unwrap()
+        );
+        assert_eq!(0, *eval.context());
+        assert!(!eval.pending_confirmation());
+
+        eval.set_dry_run(true);
+        eval.interpret_single("delete-all").unwrap().unwrap();
+        assert!(!eval.pending_confirmation());
+    }
+
+    #[test]
+    fn confirmation_checks_permission_and_ignores_nested_requests() {
+        let mut eval = Evaluator::new(3u32);
+
+        fn wipe(count: &mut u32, _: &[Type]) -> Result<String, String> {
+            if !is_confirmed() {
+                return needs_confirmation();
+            }
+            *count = 0;
+            Ok("wiped".into())
+        }
+
+        fn echo(_: &mut u32, args: &[Type]) -> Result<String, String> {
+            Ok(format!["{:?}", args])
+        }
+
+        eval.register_with_perm((&[("wipe", None)], wipe), 5)
+            .unwrap();
+        eval.register((&[("echo", ANY_STRING)], echo)).unwrap();
+
+        eval.set_permission_level(5);
+        eval.interpret_single("wipe").unwrap().unwrap();
+        eval.set_permission_level(0);
+        assert_eq!(
+            Err("insufficient permission".into()),
+            eval.interpret_single("confirm").unwrap()
+        );
+        assert_eq!(3, *eval.context());
+        assert!(!eval.pending_confirmation());
+
+        eval.set_permission_level(5);
+        assert_eq!(
+            Ok("[String(\"type 'confirm' to proceed\")]".into()),
+            eval.interpret_single("echo (wipe)").unwrap()
+        );
+        assert!(!eval.pending_confirmation());
+
+        eval.interpret_single("wipe").unwrap().unwrap();
+        eval.cancel_confirmation();
+        assert_eq!(
+            Err("Unrecognized mapping: confirm".into()),
+            eval.interpret_single("confirm").unwrap()
+        );
+        assert_eq!(3, *eval.context());
+    }
+
+    #[test]
+    fn debug_args_renders_instead_of_running() {
+        let mut eval = Evaluator::new(0);
+
+        fn handler(count: &mut usize, _: &[Type]) -> Result<String, String> {
+            *count += 1;
+            Ok("ran".into())
+        }
+
+        eval.register((
+            &[("move", ANY_I32), ("to", ANY_STRING), ("speed", ANY_F32)],
+            handler,
+        ))
+        .unwrap();
+
+        eval.set_debug_args(true);
+        assert_eq!(
+            Feedback::Ok("I32 -3\nString north east\nF32 1.5".into()),
+            eval.interpret_single("move -3 to (#north east) speed 1.5")
+                .unwrap()
+        );
+        assert_eq!(0, *eval.context());
+
+        assert_eq!(
+            Feedback::Ok("".into()),
+            eval.interpret_single("debug-args off").unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok("ran".into()),
+            eval.interpret_single("move 1 to x speed 2").unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok("".into()),
+            eval.interpret_single("debug-args on").unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok("I32 1\nString y\nF32 0".into()),
+            eval.interpret_single("move 1 to y speed 0").unwrap()
+        );
+        assert_eq!(1, *eval.context());
+        assert_eq!(
+            Feedback::Err("Usage: debug-args on|off".into()),
+            eval.interpret_single("debug-args").unwrap()
+        );
+    }
+
+    #[test]
+    fn optional_argument_before_literal() {
+        let mut eval = Evaluator::new(());
+
+        fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+            Ok(format!["{:?}", args])
+        }
+
+        let level: &'static Decider<Type, String> =
+            Box::leak(Box::new(optional(ANY_I32.unwrap()).unwrap()));
+        eval.register((&[("volume", Some(level)), ("up", None)], handler))
+            .unwrap();
+        assert_eq!(
+            Ok("[I32(3)]".into()),
+            eval.interpret_single("volume 3 up").unwrap()
+        );
+        assert_eq!(Ok("[]".into()), eval.interpret_single("volume up").unwrap());
+    }
+
+    #[test]
+    fn help_lists_usage_and_examples() {
+        let mut eval = Evaluator::new(());
+
+        fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        eval.register_with_examples(
+            (&[("set", None), ("log", None), ("level", ANY_U8)], handler),
+            &["set log level 0", "set log level 3"],
+        )
+        .unwrap();
+        eval.register((
+            &[("set", None), ("log", None), ("file", ANY_STRING)],
+            handler,
+        ))
+        .unwrap();
+        eval.register_with_examples((&[("quit", None)], handler), &["quit"])
+            .unwrap();
+        eval.register_hidden((&[("set", None), ("log", None), ("trace", None)], handler))
+            .unwrap();
+        eval.register_with_examples(
+            (
+                &[("set", None), ("log", None), ("trace", None), ("on", None)],
+                handler,
+            ),
+            &["set log trace on"],
+        )
+        .unwrap();
+        eval.register_with_perm(
+            (&[("set", None), ("log", None), ("debug", None)], handler),
+            1,
+        )
+        .unwrap();
+        eval.register_with_examples(
+            (
+                &[("set", None), ("log", None), ("debug", None), ("on", None)],
+                handler,
+            ),
+            &["set log debug on"],
+        )
+        .unwrap();
+
+        assert_eq!(
+            Feedback::Ok(
+                "set log level <u8>\n\
+                 Examples:\n    set log level 0\n    set log level 3"
+                    .into()
+            ),
+            eval.interpret_single("help set log level").unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok(
+                "set log file <string>\nset log level <u8>\n\
+                 Examples:\n    set log level 0\n    set log level 3"
+                    .into()
+            ),
+            eval.interpret_single("help set log").unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok("quit\nExamples:\n    quit".into()),
+            eval.interpret_single("help quit").unwrap()
+        );
+        assert_eq!(
+            &["set log level 0", "set log level 3"][..],
+            eval.command_examples(&["set", "log", "level"])
+        );
+        assert_eq!(
+            Feedback::Err("Unrecognized mapping: set volume".into()),
+            eval.interpret_single("help set volume").unwrap()
+        );
+        assert_eq!(Ok(()), eval.check_consistency());
+    }
+
+    #[test]
+    fn shell_quote_tokenizer() {
+        struct ShellQuote;
+
+        impl Tokenizer for ShellQuote {
+            fn tokenize(&self, statement: &str) -> Result<Vec<String>, ParseError> {
+                let mut tokens = vec![];
+                let mut token: Option<String> = None;
+                let mut quoted = false;
+                for ch in statement.chars() {
+                    match ch {
+                        '"' => {
+                            quoted = !quoted;
+                            token.get_or_insert_with(String::new);
+                        }
+                        ch if ch.is_whitespace() && !quoted => tokens.extend(token.take()),
+                        ch => token.get_or_insert_with(String::new).push(ch),
+                    }
                 }
-                lookerr_to_evalres(err)
+                if quoted {
+                    return Err(ParseError::DanglingLeftParenthesis);
+                }
+                tokens.extend(token);
+                Ok(tokens)
             }
         }
-    }
-}
 
-fn lookerr_to_evalres(err: LookError<String>) -> Feedback {
-    match err {
-        LookError::DeciderAdvancedTooFar => Feedback::Err("Decider advanced too far".into()),
-        LookError::DeciderDenied(desc, decider) => {
-            Feedback::Err(format!["Expected {}. Decider: {}", desc, decider])
-        }
-        LookError::FinalizerDoesNotExist => Feedback::Err("Finalizer does not exist".into()),
-        LookError::UnknownMapping(token) => {
-            Feedback::Err(format!["Unrecognized mapping: {}", token])
+        let mut eval = Evaluator::new(());
+
+        fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+            Ok(format!["{:?}", args])
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::predicates::*;
-    use crossbeam_channel::{bounded, Sender};
+        eval.register((&[("say", ANY_STRING), ("to", ANY_ATOM)], handler))
+            .unwrap();
+
+        let code = "say \"a b\" to bob";
+        assert_eq!(
+            Ok(Feedback::Err("Unrecognized mapping: b\"".into())),
+            eval.interpret_single(code)
+        );
+
+        eval.set_tokenizer(Some(Box::new(ShellQuote)));
+        assert_eq!(
+            Ok(Feedback::Ok("[String(\"a b\"), Atom(\"bob\")]".into())),
+            eval.interpret_single(code)
+        );
+        assert_eq!(
+            Ok(Feedback::Ok("[String(\"(x)\"), Atom(\"bob\")]".into())),
+            eval.interpret_single("say (x) to bob")
+        );
+        assert_eq!(
+            Err(ParseError::DanglingLeftParenthesis),
+            eval.interpret_single("say \"a b to bob")
+        );
+        assert_eq!(Err(ParseError::NothingToParse), eval.interpret_single("  "));
+
+        eval.set_tokenizer(None);
+        assert_eq!(
+            Ok(Feedback::Ok("[String(\"x\"), Atom(\"bob\")]".into())),
+            eval.interpret_single("say (#x) to bob")
+        );
+    }
 
     #[test]
-    fn evaluate_simple() {
-        let mut eval = Evaluator::new(0usize);
+    fn duplicate_paths() {
+        const LEVEL: &[(&str, SomeDec)] = &[("log", None), ("level", ANY_U8)];
+        const FILE: &[(&str, SomeDec)] = &[("log", None), ("file", ANY_STRING)];
+        const LOG: &[(&str, SomeDec)] = &[("log", None)];
 
-        fn handler(_context: &mut usize, _args: &[Type]) -> Result<String, String> {
-            Ok("".into())
+        assert!(!has_duplicate_path(&[LEVEL, FILE, LOG]));
+        assert!(has_duplicate_path(&[
+            LEVEL,
+            FILE,
+            LOG,
+            &[("log", None), ("level", None)]
+        ]));
+        assert!(!has_duplicate_path(&[]));
+
+        let mut eval = Evaluator::new(0);
+
+        fn handler(count: &mut usize, _: &[Type]) -> Result<String, String> {
+            *count += 1;
+            Ok(count.to_string())
         }
 
-        eval.register((&[("call", None)], handler)).unwrap();
+        register_table!(eval, [(LEVEL, handler), (FILE, handler), (LOG, handler)]).unwrap();
         assert_eq!(
-            Feedback::Ok("".into()),
-            eval.interpret_single("call").unwrap()
+            Feedback::Ok("1".into()),
+            eval.interpret_single("log file x").unwrap()
+        );
+        assert_eq!(
+            Feedback::Ok("2".into()),
+            eval.interpret_single("log").unwrap()
         );
     }
 
     #[test]
-    fn list_available() {
-        let mut eval = Evaluator::new(0usize);
+    fn count_and_list_command_paths() {
+        let mut eval = Evaluator::new(());
 
-        fn handler(_context: &mut usize, _args: &[Type]) -> Result<String, String> {
-            Ok("fafa".into())
+        fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("".into())
         }
 
-        eval.register((&[("call", ANY_F32), ("something", None)], handler))
+        assert_eq!(0, eval.command_count());
+        eval.register((&[("list", ANY_U8)], handler)).unwrap();
+        eval.register((&[("list", None), ("all", ANY_ATOM)], handler))
             .unwrap();
-        eval.register((&[("call", None), ("abc", MANY_I32)], handler))
+        eval.register_hidden((&[("list", None), ("all", None), ("sorted", None)], handler))
             .unwrap();
-        eval.register((
-            &[("log", None), ("context", MANY_I32), ("level", ANY_ATOM)],
-            handler,
-        ))
-        .unwrap();
+
+        assert_eq!(3, eval.command_count());
         assert_eq!(
-            Feedback::Ok("call <f32> abc <i32> ...\ncall <f32> something \nlog context <i32> ... level <atom>".into()),
-            eval.interpret_single("?").unwrap()
+            vec![
+                vec!["list"],
+                vec!["list", "all"],
+                vec!["list", "all", "sorted"]
+            ],
+            eval.command_paths().collect::<Vec<_>>()
         );
     }
 
     #[test]
-    fn list_available_using_regex() {
-        let mut eval = Evaluator::new(0usize);
+    fn suggest_near_literal() {
+        let mut eval = Evaluator::new(());
 
-        fn handler(_context: &mut usize, _args: &[Type]) -> Result<String, String> {
-            Ok("fafa".into())
+        fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("".into())
         }
 
-        eval.register((&[("call", ANY_F32), ("something", None)], handler))
-            .unwrap();
-        eval.register((&[("call", None), ("abc", MANY_I32)], handler))
+        eval.register((&[("quit", None)], handler)).unwrap();
+        eval.register((&[("log", ANY_U8), ("level", ANY_ATOM)], handler))
             .unwrap();
-        eval.register((
-            &[("log", None), ("context", MANY_I32), ("level", ANY_ATOM)],
-            handler,
-        ))
-        .unwrap();
+        eval.register_hidden((&[("qiet", None)], handler)).unwrap();
+
         assert_eq!(
-            Feedback::Ok("call <f32> abc <i32> ...\ncall <f32> something ".into()),
-            eval.interpret_single("? call").unwrap()
+            Err("Unrecognized mapping: qiut; did you mean 'quit'?".into()),
+            eval.interpret_single("qiut").unwrap()
         );
         assert_eq!(
-            Feedback::Ok("call <f32> abc <i32> ...".into()),
-            eval.interpret_single("? abc").unwrap()
+            Err("Unrecognized mapping: levle; did you mean 'level'?".into()),
+            eval.interpret_single("log 1 levle x").unwrap()
         );
         assert_eq!(
-            Feedback::Err("Regex could not be compiled: regex parse error:\n    .*\\x.*\n        ^\nerror: invalid hexadecimal digit".into()),
-            eval.interpret_single("? \\x").unwrap()
+            Err("Unrecognized mapping: shutdown".into()),
+            eval.interpret_single("shutdown").unwrap()
         );
+        assert_eq!(
+            Err("Unrecognized mapping: qiex".into()),
+            eval.interpret_single("qiex").unwrap()
+        );
+
+        assert_eq!(0, edit_distance("quit", "quit"));
+        assert_eq!(2, edit_distance("qiut", "quit"));
+        assert_eq!(3, edit_distance("", "abc"));
+        assert_eq!(1, edit_distance("fäil", "fail"));
     }
 
     #[test]
-    fn evaluate_any_f32() {
-        let mut eval = Evaluator::new(0usize);
+    fn case_insensitive_literals() {
+        let mut eval = Evaluator::new(0u32);
 
-        fn handler(_context: &mut usize, _args: &[Type]) -> Result<String, String> {
+        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
+            *context += 1;
             Ok("".into())
         }
 
-        eval.register((&[("call", ANY_F32)], handler)).unwrap();
+        eval.register((&[("Quit", None)], handler)).unwrap();
+        eval.register_with_perm((&[("debug", ANY_U8), ("Secret", None)], handler), 1)
+            .unwrap();
+
         assert_eq!(
-            Feedback::Err(
-                "Expected <f32>. Decider: Too few elements: [], length: 0, expected: 1".into()
-            ),
-            eval.interpret_single("call").unwrap()
+            Err("Unrecognized mapping: quit; did you mean 'Quit'?".into()),
+            eval.interpret_single("quit").unwrap()
         );
+
+        eval.set_case_insensitive(true);
+        for input in &["Quit", "quit", "QUIT", "qUiT"] {
+            assert_eq!(Ok("".into()), eval.interpret_single(input).unwrap());
+        }
+        assert_eq!(4, *eval.context());
         assert_eq!(
-            Feedback::Ok("".into()),
-            eval.interpret_single("call 3.14159").unwrap()
+            Err("insufficient permission".into()),
+            eval.interpret_single("DEBUG 1 secret").unwrap()
         );
+        eval.set_permission_level(1);
         assert_eq!(
-            Feedback::Ok("".into()),
-            eval.interpret_single("call 3").unwrap()
+            Ok("".into()),
+            eval.interpret_single("DEBUG 1 secret").unwrap()
         );
+        assert_eq!(5, *eval.context());
+
+        eval.set_case_insensitive(false);
         assert_eq!(
-            Feedback::Err("Expected <f32>. Decider: got string: alpha".into()),
-            eval.interpret_single("call alpha").unwrap()
+            Err("Unrecognized mapping: QUIT".into()),
+            eval.interpret_single("QUIT").unwrap()
         );
     }
 
     #[test]
-    fn a_command_triggers_a_channel() {
-        let (tx, rx) = bounded(1);
-
-        let mut eval = Evaluator::new(tx);
+    fn case_insensitive_builtins() {
+        let mut eval = Evaluator::new(());
 
-        fn handler(context: &mut Sender<f32>, args: &[Type]) -> Result<String, String> {
-            match args[0] {
-                Type::F32(number) => context.send(number).unwrap(),
-                _ => panic!("Input was not an f32"),
-            }
-            Ok("".into())
+        fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+            Ok(format!["{:?}", args])
         }
 
-        eval.register((&[("call", ANY_F32)], handler)).unwrap();
-
-        eval.interpret_single("call 3.14159").unwrap().unwrap();
+        eval.register_with_examples(
+            (&[("log", None), ("level", ANY_U8)], handler),
+            &["log level 3"],
+        )
+        .unwrap();
+        eval.set_case_insensitive(true);
 
-        assert_eq!(3.14159, rx.recv().unwrap());
+        assert_eq!(
+            Ok("[U8(1)]".into()),
+            eval.interpret_single("LOG level 1").unwrap()
+        );
+        assert_eq!(
+            Ok("level <u8> (final)".into()),
+            eval.interpret_single("autocomplete LOG").unwrap()
+        );
+        assert_eq!(
+            Ok("log level <u8>\nExamples:\n    log level 3".into()),
+            eval.interpret_single("help LOG Level").unwrap()
+        );
+        assert_eq!(
+            Ok("LOG: no decider\nlevel <u8>: consumed 1 [U8(2)]\nfinalizer: present".into()),
+            eval.interpret_single("test-decider LOG level 2").unwrap()
+        );
+        assert_eq!(Ok("".into()), eval.interpret_single("use LOG").unwrap());
+        assert_eq!(&["log".to_string()], eval.prefix());
+        assert_eq!(
+            Ok("tokens: [\"LEVEL\", \"4\"]\npath: log level\nlog: no decider\nLEVEL <u8>: consumed 1 [U8(4)]\nfinalizer: present".into()),
+            eval.interpret_single("explain LEVEL 4").unwrap()
+        );
+        assert_eq!(
+            Ok("[U8(5)]".into()),
+            eval.interpret_single("LEVEL 5").unwrap()
+        );
     }
 
     #[test]
-    fn base64_decoding_into_raw() {
-        let (tx, rx) = bounded(1);
-
-        let mut eval = Evaluator::new(tx);
+    fn alias_multi_segment_command() {
+        let mut eval = Evaluator::new(());
 
-        fn handler(context: &mut Sender<Vec<u8>>, args: &[Type]) -> Result<String, String> {
-            match args[0] {
-                Type::Raw(ref bytes) => context.send(bytes.clone()).unwrap(),
-                _ => panic!("Input not raw bytes"),
-            }
-            Ok("".into())
+        fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+            Ok(format!["{:?}", args])
         }
 
-        eval.register((&[("call", ANY_BASE64)], handler)).unwrap();
+        eval.register((&[("list", ANY_U8)], handler)).unwrap();
+        eval.register_with_perm((&[("list", None), ("all", ANY_ATOM)], handler), 1)
+            .unwrap();
+        eval.alias(&["ls"], &["list"]).unwrap();
+        eval.alias(&["show", "everything"], &["list", "all"])
+            .unwrap();
 
-        eval.interpret_single("call iVBORw0KGgo").unwrap().unwrap();
+        assert_eq!(Ok("[U8(1)]".into()), eval.interpret_single("ls 1").unwrap());
+        assert_eq!(
+            Err("insufficient permission".into()),
+            eval.interpret_single("ls 1 all x").unwrap()
+        );
+        eval.set_permission_level(1);
+        assert_eq!(
+            Ok("[U8(1), Atom(\"x\")]".into()),
+            eval.interpret_single("ls 1 all x").unwrap()
+        );
+        assert_eq!(
+            Ok("[Atom(\"x\")]".into()),
+            eval.interpret_single("show everything x").unwrap()
+        );
 
-        // PNG magic number
-        static PNG_MAGIC_NUMBER: &[u8] = &[0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
-        assert_eq!(PNG_MAGIC_NUMBER, &rx.recv().unwrap()[..]);
+        assert_eq!(
+            Ok("list <u8>\nlist <u8> all <atom>\nls <u8>\nls <u8> all <atom>\nshow everything <atom>"
+                .into()),
+            eval.interpret_single("?").unwrap()
+        );
+        assert_eq!(
+            Ok("everything <atom> (final)".into()),
+            eval.interpret_single("autocomplete show").unwrap()
+        );
+
+        assert_eq!(
+            Err(RegError::DeciderAlreadyExists),
+            eval.alias(&["list"], &["ls"])
+        );
+        assert_eq!(Ok(()), eval.unregister(&["ls"]));
+        assert_eq!(
+            Ok("[U8(2)]".into()),
+            eval.interpret_single("list 2").unwrap()
+        );
+        assert_eq!(Ok(()), eval.check_consistency());
     }
 
     #[test]
-    fn touching_subcommand() {
-        let mut eval = Evaluator::new(0u32);
+    fn alias_conflict_registers_nothing() {
+        let mut eval = Evaluator::new(());
 
-        fn handler(context: &mut u32, _: &[Type]) -> Result<String, String> {
-            *context += 1;
-            Ok("string".into())
+        fn admin(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("admin".into())
         }
 
-        eval.register((&[("call", ANY_STRING)], handler)).unwrap();
-        eval.interpret_single("call(call _)").unwrap().unwrap();
-        assert_eq!(2, *eval.context());
-        eval.interpret_single("call(call(call _))")
-            .unwrap()
+        fn sudo(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("sudo".into())
+        }
+
+        eval.register_with_perm((&[("admin", None)], admin), 5)
             .unwrap();
-        assert_eq!(2 + 3, *eval.context());
-        eval.interpret_multiple("call(call(call _))")
-            .unwrap()
+        eval.register((&[("admin", None), ("x", None)], admin))
             .unwrap();
-        assert_eq!(2 + 3 + 3, *eval.context());
+        eval.register((&[("sudo", None), ("x", None)], sudo))
+            .unwrap();
+
+        assert_eq!(
+            Err(RegError::FinalizerAlreadyExists),
+            eval.alias(&["sudo"], &["admin"])
+        );
+        assert_eq!(
+            Err("Finalizer does not exist".into()),
+            eval.interpret_single("sudo").unwrap()
+        );
+        assert_eq!(Ok("sudo".into()), eval.interpret_single("sudo x").unwrap());
+        assert_eq!(
+            Err("insufficient permission".into()),
+            eval.interpret_single("admin").unwrap()
+        );
+        assert_eq!(Ok(()), eval.check_consistency());
     }
 
     #[test]
-    fn recursive_call_stack() {
+    fn unregister_drops_runtime_deciders() {
         let mut eval = Evaluator::new(());
 
         fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
-            Ok("string".into())
+            Ok("".into())
         }
 
-        eval.register((&[("call", ANY_STRING)], handler)).unwrap();
+        eval.register_with_decider(&["roll"], bounded_i32(1, 6).unwrap(), handler)
+            .unwrap();
+        eval.register_with_decider(&["mode"], one_of_literals(&["on"]).unwrap(), handler)
+            .unwrap();
+        eval.register((&[("mode", None), ("reset", None)], handler))
+            .unwrap();
+        assert_eq!(
+            Err(RegError::DeciderAlreadyExists),
+            eval.register_with_decider(&["roll"], bounded_i32(1, 8).unwrap(), handler)
+        );
+        assert_eq!(2, eval.runtime_deciders.len());
 
-        let mut call = "call".to_string();
-        let count = 1000;
-        for _ in 0..count {
-            call += "(call";
+        eval.unregister(&["roll"]).unwrap();
+        assert_eq!(1, eval.runtime_deciders.len());
+        assert!(eval.interpret_single("roll 6").unwrap().is_err());
+
+        // The node of `mode` remains for `mode reset`, and so does its decider
+        eval.unregister(&["mode"]).unwrap();
+        assert_eq!(1, eval.runtime_deciders.len());
+        eval.unregister(&["mode", "reset"]).unwrap();
+        assert!(eval.runtime_deciders.is_empty());
+    }
+
+    #[test]
+    fn unregister_commands() {
+        let mut eval = Evaluator::new(());
+
+        fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+            Ok(format!["{:?}", args])
         }
-        call += " _";
-        for _ in 0..count {
-            call += ")";
+
+        eval.register((&[("list", ANY_U8)], handler)).unwrap();
+        eval.register_with_perm((&[("list", None), ("all", ANY_ATOM)], handler), 0)
+            .unwrap();
+        eval.register((&[("quit", None)], handler)).unwrap();
+
+        assert_eq!(Ok(()), eval.unregister(&["list"]));
+        assert_eq!(
+            Err("Finalizer does not exist".into()),
+            eval.interpret_single("list 1").unwrap()
+        );
+        assert_eq!(
+            Ok("[U8(1), Atom(\"x\")]".into()),
+            eval.interpret_single("list 1 all x").unwrap()
+        );
+        assert_eq!(Ok("[]".into()), eval.interpret_single("quit").unwrap());
+        assert_eq!(
+            Err(LookError::FinalizerDoesNotExist),
+            eval.unregister(&["list"])
+        );
+
+        assert_eq!(Ok(()), eval.unregister(&["list", "all"]));
+        assert_eq!(
+            Err("Unrecognized mapping: list".into()),
+            eval.interpret_single("list 1 all x").unwrap()
+        );
+        assert_eq!(
+            Err(LookError::UnknownMapping("list".into())),
+            eval.unregister(&["list", "all"])
+        );
+        assert_eq!(Ok(()), eval.check_consistency());
+
+        eval.register((&[("list", ANY_U8)], handler)).unwrap();
+        assert_eq!(
+            Ok("[U8(2)]".into()),
+            eval.interpret_single("list 2").unwrap()
+        );
+
+        // Paths resolve like lookups do
+        eval.set_case_insensitive(true);
+        assert_eq!(Ok(()), eval.unregister(&["LIST"]));
+        assert_eq!(
+            Err("Unrecognized mapping: list".into()),
+            eval.interpret_single("list 2").unwrap()
+        );
+        assert_eq!(Ok(()), eval.check_consistency());
+    }
+
+    #[test]
+    fn unregister_drops_pending_confirmation() {
+        let mut eval = Evaluator::new(0u32);
+
+        fn wipe(count: &mut u32, _: &[Type]) -> Result<String, String> {
+            if !is_confirmed() {
+                return needs_confirmation();
+            }
+            *count += 1;
+            Ok("".into())
         }
 
+        eval.register((&[("wipe", None)], wipe)).unwrap();
+        eval.register((&[("reset", None)], wipe)).unwrap();
+
+        eval.interpret_single("wipe").unwrap().unwrap();
+        eval.unregister(&["reset"]).unwrap();
+        eval.interpret_single("confirm").unwrap().unwrap();
+        assert_eq!(1, *eval.context());
+
+        eval.interpret_single("wipe").unwrap().unwrap();
+        eval.unregister(&["wipe"]).unwrap();
         assert_eq!(
-            Err("Recursion limit reached: 100".into()),
-            eval.interpret_single(&call).unwrap()
+            Err("Unrecognized mapping: confirm".into()),
+            eval.interpret_single("confirm").unwrap()
         );
-        assert_eq!(0, eval.get_current_depth());
+        assert_eq!(1, *eval.context());
     }
 
     #[test]
-    fn question_only_handles_one() {
+    fn clear_only_with_ansi() {
         let mut eval = Evaluator::new(());
-        assert_eq!("", eval.interpret_single("? _").unwrap().unwrap());
         assert_eq!(
-            "Too many arguments to: ?",
-            eval.interpret_single("? _ _").unwrap().unwrap_err()
+            Feedback::Err("Unrecognized mapping: clear".into()),
+            eval.interpret_single("clear").unwrap()
         );
+
+        eval.set_ansi(true);
         assert_eq!(
-            "Too many arguments to: ?",
-            eval.interpret_single("? _ _ _ _ _").unwrap().unwrap_err()
+            Feedback::Ok("\x1b[2J\x1b[H".into()),
+            eval.interpret_single("clear").unwrap()
         );
+
+        fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("cleared the map".into())
+        }
+
+        eval.register((&[("clear", None)], handler)).unwrap();
         assert_eq!(
-            "Too many arguments to: ?",
-            eval.interpret_multiple("? _ _ _ _ _").unwrap().unwrap_err()
+            Feedback::Ok("cleared the map".into()),
+            eval.interpret_single("clear").unwrap()
         );
     }
 
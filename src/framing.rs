@@ -0,0 +1,172 @@
+//! Length-prefixed framing with a checksum, for clients on lossy links
+//!
+//! Instead of newline-terminated statements, a [crate::GameShell] using [Framing::Checksummed]
+//! reads frames consisting of an 8-byte header followed by the payload:
+//!
+//! | Bytes | Content                                        |
+//! |-------|------------------------------------------------|
+//! | 2     | Magic, the ASCII characters `GS`               |
+//! | 2     | Length of the payload, big endian              |
+//! | 4     | CRC-32 (IEEE) of the payload, big endian       |
+//!
+//! The payload is a single statement, without a trailing newline. Bytes that do not start with
+//! the magic are skipped one at a time until a frame starts. A frame whose checksum does not
+//! match its payload is not interpreted, the shell writes a `ChecksumError` instead. Responses are
+//! written as they are with the text framing.
+use crate::incconsumer::Validation;
+
+/// The bytes every frame starts with
+pub const MAGIC: [u8; 2] = *b"GS";
+/// The size of the frame header in bytes
+pub const HEADER_LEN: usize = 8;
+
+/// How a [crate::GameShell] splits its input into statements
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Framing {
+    /// Newline-terminated statements, the default
+    Text,
+    /// Frames with a length prefix and a checksum, see [crate::framing]
+    Checksummed,
+}
+
+/// Wrap a statement in a frame for [Framing::Checksummed].
+///
+/// # Panics
+///
+/// Panics if the payload is longer than `u16::MAX` bytes.
+pub fn encode_frame(payload: &[u8]) -> Vec<u8> {
+    assert!(payload.len() <= u16::MAX as usize, "Payload too long");
+    let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+    frame.extend_from_slice(&MAGIC);
+    frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    frame.extend_from_slice(&crc32(payload).to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Compute the CRC-32 (IEEE) checksum of some bytes.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// Get the payload of a complete frame, fails if the checksum does not match
+pub(crate) fn payload(frame: &[u8]) -> Result<&[u8], String> {
+    let mut expected = [0u8; 4];
+    expected.copy_from_slice(&frame[4..HEADER_LEN]);
+    let expected = u32::from_be_bytes(expected);
+    let payload = &frame[HEADER_LEN..];
+    let actual = crc32(payload);
+    if expected == actual {
+        Ok(payload)
+    } else {
+        Err(format![
+            "ChecksumError(\"Expected {:08x}, computed {:08x}\")",
+            expected, actual
+        ])
+    }
+}
+
+// Finds frame boundaries byte by byte, the checksum is validated afterwards by `payload`
+#[derive(Default)]
+pub(crate) struct FrameParser {
+    seen: usize,
+    length: usize,
+}
+
+impl FrameParser {
+    pub(crate) fn parse_increment(&mut self, byte: u8) -> Validation {
+        let index = self.seen;
+        self.seen += 1;
+        if index < MAGIC.len() {
+            if byte != MAGIC[index] {
+                // Skip the first byte of the mismatched magic only. As the magic is two bytes
+                // long, the byte at hand is all that remains to check for the start of a frame
+                *self = Self::default();
+                if byte == MAGIC[0] {
+                    self.seen = 1;
+                    return Validation::Restart;
+                }
+                return Validation::Discard;
+            }
+        } else if index < 4 {
+            self.length = (self.length << 8) | usize::from(byte);
+        }
+        if self.seen == HEADER_LEN + self.length && self.seen >= HEADER_LEN {
+            *self = Self::default();
+            return Validation::Ready;
+        }
+        Validation::Unready
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+        assert_eq!(0, crc32(b""));
+    }
+
+    #[test]
+    fn frame_round_trip() {
+        let frame = encode_frame(b"call 1");
+        assert_eq!(&b"GS\x00\x06"[..], &frame[0..4]);
+
+        let mut parser = FrameParser::default();
+        let (last, rest) = frame.split_last().unwrap();
+        for byte in rest {
+            assert!(matches!(parser.parse_increment(*byte), Validation::Unready));
+        }
+        assert!(matches!(parser.parse_increment(*last), Validation::Ready));
+        assert_eq!(Ok(&b"call 1"[..]), payload(&frame));
+    }
+
+    #[test]
+    fn garbage_before_frame_is_discarded() {
+        let mut parser = FrameParser::default();
+        assert!(matches!(parser.parse_increment(b'x'), Validation::Discard));
+        assert!(matches!(parser.parse_increment(b'G'), Validation::Unready));
+        assert!(matches!(parser.parse_increment(b'x'), Validation::Discard));
+    }
+
+    #[test]
+    fn frame_after_corrupt_header_is_found() {
+        let mut parser = FrameParser::default();
+        assert!(matches!(parser.parse_increment(b'G'), Validation::Unready));
+        let frame = encode_frame(b"call 1");
+        assert!(matches!(
+            parser.parse_increment(frame[0]),
+            Validation::Restart
+        ));
+        let (last, rest) = frame[1..].split_last().unwrap();
+        for byte in rest {
+            assert!(matches!(parser.parse_increment(*byte), Validation::Unready));
+        }
+        assert!(matches!(parser.parse_increment(*last), Validation::Ready));
+    }
+
+    #[test]
+    fn empty_payload() {
+        let frame = encode_frame(b"");
+        let mut parser = FrameParser::default();
+        let results = frame
+            .iter()
+            .map(|byte| matches!(parser.parse_increment(*byte), Validation::Ready))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![false; 7], &results[..7]);
+        assert!(results[7]);
+    }
+}
@@ -30,6 +30,9 @@ pub enum Validation {
     Unready,
     /// Discard the current accumulation buffer
     Discard,
+    /// Discard the current accumulation buffer except for the byte just validated, which starts
+    /// the next one
+    Restart,
     /// Stop the system
     Stop,
 }
@@ -80,6 +83,7 @@ pub trait IncConsumer {
                         begin += 1;
                         match self.validate(*ch) {
                             Validation::Discard => shift = begin,
+                            Validation::Restart => shift = begin - 1,
                             Validation::Ready => {
                                 match self.process(&buf[shift..begin]) {
                                     Process::Continue => {}
@@ -0,0 +1,217 @@
+//! Registry of long-running asynchronous commands
+//!
+//! A handler that starts work which outlives its invocation spawns it through a [JobRegistry],
+//! usually kept in the context of the evaluator. The registry assigns the work a [JobId] and hands
+//! back a future that the caller passes on to the executor of its choice. With the registry set
+//! on an evaluator, see [crate::evaluator::Evaluator::set_jobs], the builtin `jobs` lists the
+//! spawned work and `kill <id>` cancels it.
+use crate::Feedback;
+use futures::future::{abortable, AbortHandle};
+use std::{
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+/// Identifier of a job spawned through a [JobRegistry]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(pub u64);
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// State of a job spawned through a [JobRegistry]
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobStatus {
+    /// The job has not completed yet
+    Running,
+    /// The job completed with the given result
+    Finished(Feedback),
+    /// The job was cancelled before it completed, see [JobRegistry::kill]
+    Cancelled,
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JobStatus::Running => write!(f, "running"),
+            JobStatus::Finished(Ok(_)) => write!(f, "finished"),
+            JobStatus::Finished(Err(_)) => write!(f, "failed"),
+            JobStatus::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+struct Job {
+    id: JobId,
+    description: String,
+    status: JobStatus,
+    abort: AbortHandle,
+}
+
+#[derive(Default)]
+struct Jobs {
+    next: u64,
+    jobs: Vec<Job>,
+}
+
+/// A table of spawned jobs, shared between its clones
+///
+/// Jobs stay in the table after completing so that their status can be listed, until
+/// [JobRegistry::prune] removes them.
+#[derive(Clone, Default)]
+pub struct JobRegistry {
+    jobs: Arc<Mutex<Jobs>>,
+}
+
+impl JobRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assign a new job to `future` and return its id along with the future that runs it.
+    ///
+    /// The job only makes progress while the returned future is polled, so pass it on to an
+    /// executor, such as `tokio::spawn`. The returned future completes when the job completes or
+    /// when it is cancelled, in which case `future` is dropped without being polled again.
+    pub fn spawn(
+        &self,
+        description: impl Into<String>,
+        future: impl Future<Output = Feedback>,
+    ) -> (JobId, impl Future<Output = ()>) {
+        let (future, abort) = abortable(future);
+        let id = {
+            let mut jobs = self.jobs.lock().unwrap();
+            jobs.next += 1;
+            let id = JobId(jobs.next);
+            jobs.jobs.push(Job {
+                id,
+                description: description.into(),
+                status: JobStatus::Running,
+                abort,
+            });
+            id
+        };
+        let registry = self.clone();
+        let run = async move {
+            let status = match future.await {
+                Ok(res) => JobStatus::Finished(res),
+                Err(_) => JobStatus::Cancelled,
+            };
+            registry.update(id, status);
+        };
+        (id, run)
+    }
+
+    /// List the id, description, and status of every job in the order they were spawned.
+    pub fn list(&self) -> Vec<(JobId, String, JobStatus)> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .jobs
+            .iter()
+            .map(|job| (job.id, job.description.clone(), job.status.clone()))
+            .collect()
+    }
+
+    /// Get the status of a job, `None` if no such job is in the registry.
+    pub fn status(&self, id: JobId) -> Option<JobStatus> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .jobs
+            .iter()
+            .find(|job| job.id == id)
+            .map(|job| job.status.clone())
+    }
+
+    /// Cancel a running job. Its future is dropped the next time the executor polls it.
+    pub fn kill(&self, id: JobId) -> Result<(), String> {
+        let mut jobs = self.jobs.lock().unwrap();
+        match jobs.jobs.iter_mut().find(|job| job.id == id) {
+            Some(job) if job.status == JobStatus::Running => {
+                job.abort.abort();
+                job.status = JobStatus::Cancelled;
+                Ok(())
+            }
+            Some(_) => Err(format!["Job is not running: {}", id]),
+            None => Err(format!["Unknown job: {}", id]),
+        }
+    }
+
+    /// Remove every job that is no longer running.
+    pub fn prune(&self) {
+        self.jobs
+            .lock()
+            .unwrap()
+            .jobs
+            .retain(|job| job.status == JobStatus::Running);
+    }
+
+    // Record the outcome of a job, unless it was cancelled in the meantime
+    fn update(&self, id: JobId, status: JobStatus) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs.jobs.iter_mut().find(|job| job.id == id) {
+            if job.status == JobStatus::Running {
+                job.status = status;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{
+        executor::block_on,
+        future::{pending, ready},
+        task::noop_waker,
+    };
+    use std::task::{Context, Poll};
+
+    #[test]
+    fn finished_job_keeps_its_result() {
+        let registry = JobRegistry::new();
+        let (id, run) = registry.spawn("fetch", ready(Ok("done".into())));
+        assert_eq!(Some(JobStatus::Running), registry.status(id));
+        block_on(run);
+        assert_eq!(
+            Some(JobStatus::Finished(Ok("done".into()))),
+            registry.status(id)
+        );
+        assert_eq!(Err("Job is not running: 1".into()), registry.kill(id));
+
+        registry.prune();
+        assert_eq!(None, registry.status(id));
+        assert_eq!(Err("Unknown job: 1".into()), registry.kill(id));
+    }
+
+    #[test]
+    fn killed_job_drops_its_future() {
+        let registry = JobRegistry::new();
+        let alive = Arc::new(());
+        let held = alive.clone();
+        let (id, run) = registry.spawn("wait", async move {
+            let _held = held;
+            pending::<Feedback>().await
+        });
+        let mut run = Box::pin(run);
+        let waker = noop_waker();
+        let mut context = Context::from_waker(&waker);
+
+        assert_eq!(Poll::Pending, run.as_mut().poll(&mut context));
+        assert_eq!(2, Arc::strong_count(&alive));
+
+        registry.kill(id).unwrap();
+        assert_eq!(Poll::Ready(()), run.as_mut().poll(&mut context));
+        assert_eq!(1, Arc::strong_count(&alive));
+        assert_eq!(
+            vec![(id, "wait".to_string(), JobStatus::Cancelled)],
+            registry.list()
+        );
+    }
+}
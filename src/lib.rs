@@ -88,21 +88,73 @@
 //!
 //! # Builtin commands #
 //!
-//! GameShell has 2 builtin commands:
+//! GameShell has the following builtin commands:
 //! ```ignore
 //! ?
 //! ```
 //! List all registered commands and their potential arguments. An argument to this command will
 //! regex filter the output: `? lorem`.
-//! and
 //! ```ignore
 //! autocomplete
 //! ```
 //! Autocomplete a query.
+//! ```ignore
+//! help <command>
+//! ```
+//! Show the usage of a command and its examples, see [Evaluator::register_with_examples].
+//! ```ignore
+//! use
+//! ```
+//! Set a prefix that is prepended to subsequent commands: `use debug` makes `entities` run
+//! `debug entities`. If the prefixed command does not exist, the command is run without the
+//! prefix. `use` without arguments clears the prefix.
+//! ```ignore
+//! test-decider
+//! ```
+//! Run the deciders of a query and report what each of them consumed and produced, without
+//! running the handler: `test-decider lorem ipsum 1.23`.
+//! ```ignore
+//! explain <statement>
+//! ```
+//! Show the tokens, the command path, and the deciders of a statement and its nested commands
+//! without running any of them.
+//! ```ignore
+//! debug-args on|off
+//! ```
+//! Toggle reporting the arguments passed to each handler, see [Evaluator::set_debug_args].
+//! ```ignore
+//! clear
+//! ```
+//! Clear the terminal, only available with [Evaluator::set_ansi] enabled.
+//! ```ignore
+//! jobs
+//! kill <id>
+//! ```
+//! List and cancel jobs spawned by handlers, only available with a job registry set using
+//! `Evaluator::set_jobs`.
+//! ```ignore
+//! confirm
+//! ```
+//! Run the command waiting for confirmation, see [needs_confirmation].
+//! ```ignore
+//! set <name> <value>
+//! get <name>
+//! ```
+//! Set and get variables. Variables are local to the [Evaluator], which may additionally read
+//! from a set of global variables shared between evaluators, see
+//! [Evaluator::set_global_variables]. Local variables take precedence over global ones.
 //!
 //! These commands return strings that contain useful information to be displayed to the user. If
 //! you do not wish to expose these commands then you overwrite these commands using a command
-//! handler.
+//! handler. The exception is `confirm`, which is only recognized while a command is waiting for
+//! confirmation and then takes precedence over every other command.
+//!
+//! Commands registered using [Evaluator::register_hidden] are runnable but do not show up in
+//! `?` or `autocomplete`.
+//!
+//! Commands registered using [Evaluator::register_with_perm] require a minimum permission
+//! level, set per session with [Evaluator::set_permission_level]. Below that level they do not
+//! show up in `?` or `autocomplete`, and running them fails with `"insufficient permission"`.
 #![deny(
     missing_docs,
     trivial_casts,
@@ -111,42 +163,152 @@
     unused_import_braces,
     unused_qualifications
 )]
-pub use crate::{evaluator::Evaluator, incconsumer::IncConsumer};
 use crate::{
+    evaluator::Presented,
+    framing::{FrameParser, Framing},
     incconsumer::{Consumption, Process, Validation},
+    predicates::RuntimeDecider,
     types::Type,
 };
+pub use crate::{
+    evaluator::{
+        emit, is_confirmed, is_dry_run, needs_confirmation, read_replay, warn, Evaluator,
+        EventEmitter, Tokenizer,
+    },
+    incconsumer::IncConsumer,
+};
 use cmdmat::RegError;
 pub use cmdmat::{self, Spec};
-pub use metac::{Evaluate, PartialParse, PartialParseOp};
+pub use metac::{Evaluate, ParseError, PartialParse, PartialParseOp};
 use std::{
+    borrow::Cow,
     io::{Read, Write},
     str::from_utf8,
 };
 
 #[cfg(feature = "with-tokio")]
 mod applicator;
+pub mod console;
 pub mod evaluator;
+pub mod framing;
 mod incconsumer;
+#[cfg(feature = "with-futures")]
+mod jobs;
 pub mod predicates;
+#[cfg(feature = "with-arc-swap")]
+mod registry;
+pub mod schema;
+mod tree;
 pub mod types;
 
 #[cfg(feature = "with-tokio")]
 pub use applicator::tokio_apply;
+pub use console::run_stdio;
+#[cfg(feature = "with-futures")]
+pub use jobs::{JobId, JobRegistry, JobStatus};
+#[cfg(feature = "with-arc-swap")]
+pub use registry::SharedRegistry;
+pub use tree::{parse_tree, Tree};
 
 /// Feedback provided by the interpreter. All results are either a success string or an error
 /// string.
 /// Errors will abort any nested expressions and return the error immediately.
 pub type Feedback = Result<String, String>;
 
+type Prompt<'a, C> = Box<dyn Fn(&C) -> String + 'a>;
+
+/// How the successful result of a handler is to be presented, see [Evaluator::presentation]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Presentation {
+    /// The result is base64-encoded binary data, see [raw_feedback]
+    Raw,
+    /// The result is a body with the given content type, see [typed_feedback]
+    Typed(String),
+}
+
+/// Create the result of a handler that returns binary data.
+///
+/// Handlers can only return strings, so the bytes are base64-encoded, and the result is marked as
+/// [Presentation::Raw] alongside the string rather than inside it. The mark only applies if the
+/// handler returns the string as is. Nested commands receive the base64, which
+/// [predicates::ANY_BASE64] decodes back into [Type::Raw]. [GameShell] writes the result as
+/// `Raw(<length>)` followed by the bytes themselves, and as `Raw("<base64>")` when batching.
+pub fn raw_feedback(bytes: &[u8]) -> String {
+    let result = base64::encode(bytes);
+    evaluator::present(Presentation::Raw, &result);
+    result
+}
+
+/// Create the result of a handler that labels its body with a content type such as
+/// `application/json`, so clients can tell how to render it.
+///
+/// Handlers can only return strings, so the result is the body, and the content type is kept
+/// alongside it as [Presentation::Typed]. Like with [raw_feedback], the label only applies if the
+/// handler returns the body as is, so a result cut short by [Evaluator::set_max_output_len] is an
+/// ordinary string. Nested commands receive the body. [GameShell] writes the result as
+/// `Typed("<content type>", "<body>")`, also when batching, and the console writes the body.
+pub fn typed_feedback(content_type: &str, body: &str) -> String {
+    evaluator::present(Presentation::Typed(content_type.into()), body);
+    body.into()
+}
+
+// Encode a success string for the wire, binary data is written as `Raw(<length>)` followed by
+// the bytes and labelled results as `Typed("<content type>", "<body>")`
+pub(crate) fn ok_response(result: &str, presentation: Option<&Presentation>) -> Vec<u8> {
+    match (presentation, base64::decode(result)) {
+        (Some(Presentation::Raw), Ok(mut bytes)) => {
+            let mut response = format!("Raw({})", bytes.len()).into_bytes();
+            response.append(&mut bytes);
+            response
+        }
+        (Some(Presentation::Typed(content_type)), _) => {
+            format!("Typed({:?}, {:?})", content_type, result).into_bytes()
+        }
+        _ => format!("Ok({:?})", result).into_bytes(),
+    }
+}
+
+// Format the results of a batch as a single response: `[Ok("..."), Err("..."), ...]`, where the
+// warnings of a statement precede its result as `Warning("...")`
+pub(crate) fn batch_response(results: &[Presented]) -> String {
+    let results = results
+        .iter()
+        .flat_map(|(result, presentation, warnings)| {
+            let result = match result {
+                Ok(Feedback::Ok(res)) => match presentation {
+                    Some(Presentation::Raw) => format!("Raw({:?})", res),
+                    Some(Presentation::Typed(content_type)) => {
+                        format!("Typed({:?}, {:?})", content_type, res)
+                    }
+                    None => format!("Ok({:?})", res),
+                },
+                Ok(Feedback::Err(res)) => format!("Err({:?})", res),
+                Err(parse_error) => {
+                    format!("ParseError(\"Unable to parse input: {:?}\")", parse_error)
+                }
+            };
+            warnings
+                .iter()
+                .map(|warning| format!("Warning({:?})", warning))
+                .chain(Some(result))
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", results.join(", "))
+}
+
 /// The main virtual machine wrapper for a game shell
 ///
 /// This wrapper consumes an input and output stream through which it writes messages.
 pub struct GameShell<'a, C, R: Read, W: Write> {
     evaluator: Evaluator<'a, C>,
     parser: PartialParse,
+    frame_parser: FrameParser,
+    framing: Framing,
     reader: R,
     writer: W,
+    trim_output: bool,
+    utf8_lossy: bool,
+    prompt: Option<Prompt<'a, C>>,
 }
 
 impl<'a, C, R: Read, W: Write> GameShell<'a, C, R, W> {
@@ -155,72 +317,111 @@ impl<'a, C, R: Read, W: Write> GameShell<'a, C, R, W> {
         Self {
             evaluator: Evaluator::new(context),
             parser: PartialParse::default(),
+            frame_parser: FrameParser::default(),
+            framing: Framing::Text,
             reader,
             writer,
+            trim_output: false,
+            utf8_lossy: false,
+            prompt: None,
         }
     }
 
-    /// Get a reference to the current evaluator.
-    pub fn evaluator(&mut self) -> &mut Evaluator<'a, C> {
-        &mut self.evaluator
+    /// Replace the reader and writer, returning the previous ones, so the same evaluator and
+    /// registered commands can serve another connection.
+    ///
+    /// Any partially read statement from the previous reader is discarded, and so is a command
+    /// waiting for confirmation, see [needs_confirmation]. The evaluator, including its
+    /// context, variables, and prefix, is kept as is.
+    pub fn rebind(&mut self, reader: R, writer: W) -> (R, W) {
+        self.evaluator.cancel_confirmation();
+        self.parser = PartialParse::default();
+        self.frame_parser = FrameParser::default();
+        (
+            std::mem::replace(&mut self.reader, reader),
+            std::mem::replace(&mut self.writer, writer),
+        )
     }
 
-    /// Get a reference to the current context.
-    pub fn context(&self) -> &C {
-        self.evaluator.context()
+    /// Set how input is split into statements. Defaults to [Framing::Text].
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.framing = framing;
     }
 
-    /// Get a mutable reference to the current context.
-    pub fn context_mut(&mut self) -> &mut C {
-        self.evaluator.context_mut()
+    /// Set whether trailing whitespace is trimmed from successful results before they are
+    /// written. Defaults to `false`.
+    pub fn set_trim_output(&mut self, trim: bool) {
+        self.trim_output = trim;
     }
 
-    /// Register a command specificator to this gameshell instance.
-    pub fn register(&mut self, spec: Spec<'_, 'a, Type, String, C>) -> Result<(), RegError> {
-        self.evaluator.register(spec)
+    /// Set whether invalid UTF-8 in a statement is replaced with U+FFFD and the statement is still
+    /// interpreted. Defaults to `false`, where invalid UTF-8 writes a `DecodeError` and
+    /// disconnects.
+    pub fn set_utf8_lossy(&mut self, lossy: bool) {
+        self.utf8_lossy = lossy;
     }
 
-    /// Register multiple command specifications to this gameshell instance.
-    pub fn register_many(
-        &mut self,
-        spec: &[Spec<'_, 'a, Type, String, C>],
-    ) -> Result<(), RegError> {
-        self.evaluator.register_many(spec)
+    /// Set a prompt written after each response, computed from the context at the time it is
+    /// written so it can reflect state changed by the command. No prompt is written by default.
+    pub fn set_dynamic_prompt(&mut self, prompt: Box<dyn Fn(&C) -> String + 'a>) {
+        self.prompt = Some(prompt);
     }
-}
 
-impl<'a, C, R: Read, W: Write> IncConsumer for GameShell<'a, C, R, W> {
-    fn consume(&mut self, output: &mut [u8]) -> Consumption {
-        if output.is_empty() {
-            let _ = self
-                .writer
-                .write(b"DecodeError(\"Internal buffer is full, disconnecting\")");
-            return Consumption::Stop;
-        }
-        match self.reader.read(output) {
-            Ok(0) => Consumption::Stop,
-            Ok(count) => Consumption::Consumed(count),
-            Err(_) => Consumption::Stop,
-        }
-    }
-    fn validate(&mut self, input: u8) -> Validation {
-        match self.parser.parse_increment(input) {
-            PartialParseOp::Ready => Validation::Ready,
-            PartialParseOp::Unready => Validation::Unready,
-            PartialParseOp::Discard => Validation::Discard,
-        }
-    }
-    fn process(&mut self, input: &[u8]) -> Process {
-        let string = from_utf8(input);
-        if let Ok(string) = string {
-            let result = self.evaluator.interpret_single(string);
-            match result {
+    fn respond(&mut self, input: &[u8]) -> Process {
+        let input = match self.framing {
+            Framing::Text => input,
+            Framing::Checksummed => match framing::payload(input) {
+                Ok(payload) => payload,
+                Err(error) => {
+                    if self.writer.write_all(error.as_bytes()).is_err()
+                        || self.writer.flush().is_err()
+                    {
+                        return Process::Stop;
+                    }
+                    return Process::Continue;
+                }
+            },
+        };
+        let string = if self.utf8_lossy {
+            Some(String::from_utf8_lossy(input))
+        } else {
+            from_utf8(input).ok().map(Cow::Borrowed)
+        };
+        if let Some(string) = string {
+            if self.evaluator.batching() {
+                let results = self.evaluator.interpret_batch_presented(&string);
+                let results = results
+                    .into_iter()
+                    .map(|(result, presentation, warnings)| {
+                        (self.trim(result), presentation, warnings)
+                    })
+                    .collect::<Vec<_>>();
+                let response = batch_response(&results);
+                if self.writer.write_all(response.as_bytes()).is_err()
+                    || self.writer.flush().is_err()
+                {
+                    return Process::Stop;
+                }
+                return Process::Continue;
+            }
+            let result = self.evaluator.interpret_single(&string);
+            for warning in self.evaluator.warnings() {
+                if self
+                    .writer
+                    .write_all(format!("Warning({:?})", warning).as_bytes())
+                    .is_err()
+                {
+                    return Process::Stop;
+                }
+            }
+            match self.trim(result) {
                 Ok(result) => {
                     match result {
                         Feedback::Ok(res) => {
+                            let presentation = self.evaluator.presentation();
                             if self
                                 .writer
-                                .write_all(format!("Ok({:?})", res).as_bytes())
+                                .write_all(&ok_response(&res, presentation))
                                 .is_err()
                             {
                                 return Process::Stop;
@@ -271,6 +472,146 @@ impl<'a, C, R: Read, W: Write> IncConsumer for GameShell<'a, C, R, W> {
             Process::Stop
         }
     }
+
+    fn write_prompt(&mut self) -> Process {
+        if let Some(prompt) = &self.prompt {
+            let prompt = prompt(self.evaluator.context());
+            if self.writer.write_all(prompt.as_bytes()).is_err() || self.writer.flush().is_err() {
+                return Process::Stop;
+            }
+        }
+        Process::Continue
+    }
+
+    fn trim(&self, result: Result<Feedback, ParseError>) -> Result<Feedback, ParseError> {
+        match result {
+            Ok(Feedback::Ok(res)) if self.trim_output => {
+                Ok(Feedback::Ok(res.trim_end().to_string()))
+            }
+            result => result,
+        }
+    }
+
+    /// Get a reference to the current evaluator.
+    pub fn evaluator(&mut self) -> &mut Evaluator<'a, C> {
+        &mut self.evaluator
+    }
+
+    /// Get a reference to the current context.
+    pub fn context(&self) -> &C {
+        self.evaluator.context()
+    }
+
+    /// Get a mutable reference to the current context.
+    pub fn context_mut(&mut self) -> &mut C {
+        self.evaluator.context_mut()
+    }
+
+    /// Register a command specificator to this gameshell instance.
+    pub fn register(&mut self, spec: Spec<'_, 'a, Type, String, C>) -> Result<(), RegError> {
+        self.evaluator.register(spec)
+    }
+
+    /// Register a command using a decider built at runtime, see
+    /// [Evaluator::register_with_decider].
+    pub fn register_with_decider(
+        &mut self,
+        literals: &[&'static str],
+        decider: impl Into<RuntimeDecider>,
+        handler: cmdmat::Finalizer<Type, C>,
+    ) -> Result<(), RegError> {
+        self.evaluator
+            .register_with_decider(literals, decider, handler)
+    }
+
+    /// Register a hidden command specificator to this gameshell instance, see
+    /// [Evaluator::register_hidden].
+    pub fn register_hidden(&mut self, spec: Spec<'_, 'a, Type, String, C>) -> Result<(), RegError> {
+        self.evaluator.register_hidden(spec)
+    }
+
+    /// Register a command specificator requiring a permission level to this gameshell instance,
+    /// see [Evaluator::register_with_perm].
+    pub fn register_with_perm(
+        &mut self,
+        spec: Spec<'_, 'a, Type, String, C>,
+        level: u32,
+    ) -> Result<(), RegError> {
+        self.evaluator.register_with_perm(spec, level)
+    }
+
+    /// Register a command specificator with a cooldown to this gameshell instance, see
+    /// [Evaluator::register_with_cooldown].
+    pub fn register_with_cooldown(
+        &mut self,
+        spec: Spec<'_, 'a, Type, String, C>,
+        cooldown: std::time::Duration,
+    ) -> Result<(), RegError> {
+        self.evaluator.register_with_cooldown(spec, cooldown)
+    }
+
+    /// Register a command specificator with metadata to this gameshell instance, see
+    /// [Evaluator::register_with_meta].
+    pub fn register_with_meta(
+        &mut self,
+        spec: Spec<'_, 'a, Type, String, C>,
+        meta: std::collections::HashMap<&'static str, &'static str>,
+    ) -> Result<(), RegError> {
+        self.evaluator.register_with_meta(spec, meta)
+    }
+
+    /// Register a command specificator with example invocations to this gameshell instance, see
+    /// [Evaluator::register_with_examples].
+    pub fn register_with_examples(
+        &mut self,
+        spec: Spec<'_, 'a, Type, String, C>,
+        examples: &[&'static str],
+    ) -> Result<(), RegError> {
+        self.evaluator.register_with_examples(spec, examples)
+    }
+
+    /// Register multiple command specifications to this gameshell instance.
+    pub fn register_many(
+        &mut self,
+        spec: &[Spec<'_, 'a, Type, String, C>],
+    ) -> Result<(), RegError> {
+        self.evaluator.register_many(spec)
+    }
+}
+
+impl<'a, C, R: Read, W: Write> IncConsumer for GameShell<'a, C, R, W> {
+    fn consume(&mut self, output: &mut [u8]) -> Consumption {
+        if output.is_empty() {
+            let _ = self
+                .writer
+                .write_all(b"DecodeError(\"Internal buffer is full, disconnecting\")");
+            let _ = self.writer.flush();
+            return Consumption::Stop;
+        }
+        match self.reader.read(output) {
+            Ok(0) | Err(_) => {
+                let _ = self.writer.flush();
+                Consumption::Stop
+            }
+            Ok(count) => Consumption::Consumed(count),
+        }
+    }
+    fn validate(&mut self, input: u8) -> Validation {
+        if self.framing == Framing::Checksummed {
+            return self.frame_parser.parse_increment(input);
+        }
+        match self.evaluator.parse_increment(&mut self.parser, input) {
+            PartialParseOp::Ready => Validation::Ready,
+            PartialParseOp::Unready => Validation::Unready,
+            PartialParseOp::Discard => Validation::Discard,
+        }
+    }
+    fn process(&mut self, input: &[u8]) -> Process {
+        match self.respond(input) {
+            Process::Continue => self.write_prompt(),
+            Process::Stop => Process::Stop,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -338,6 +679,455 @@ mod tests {
         );
     }
 
+    #[test]
+    fn output_is_flushed_on_stop() {
+        use std::{cell::RefCell, io, rc::Rc};
+
+        struct Buffered {
+            buffer: Vec<u8>,
+            flushed: Rc<RefCell<Vec<u8>>>,
+        }
+
+        impl Write for Buffered {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.buffer.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                self.flushed.borrow_mut().append(&mut self.buffer);
+                Ok(())
+            }
+        }
+
+        let read = b"call 1.2\ncall 99.9999\n";
+        let flushed = Rc::new(RefCell::new(Vec::new()));
+        let write = Buffered {
+            buffer: Vec::new(),
+            flushed: flushed.clone(),
+        };
+
+        let mut eval = GameShell::new(0u8, &read[..], write);
+
+        fn handler(_: &mut u8, _args: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        eval.register((&[("call", ANY_F32)], handler)).unwrap();
+
+        let buffer = &mut [0u8; 10];
+        eval.run(buffer);
+
+        assert_eq!(
+            "Ok(\"\")DecodeError(\"Internal buffer is full, disconnecting\")",
+            from_utf8(&flushed.borrow()).unwrap()
+        );
+    }
+
+    #[test]
+    fn batched_frame() {
+        let read = b"call 1.2; call x; call (call 1)\n";
+        let mut write = [0u8; 1024];
+
+        let mut eval = GameShell::new(0u8, &read[..], &mut write[..]);
+        eval.evaluator().set_batching(true);
+
+        fn handler(context: &mut u8, _args: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok("1".into())
+        }
+
+        eval.register((&[("call", ANY_F32)], handler)).unwrap();
+
+        let buffer = &mut [0u8; 1024];
+        eval.run(buffer);
+
+        assert_eq!(3, *eval.context());
+
+        let index = write.iter().position(|&byte| byte == b'\0').unwrap();
+        assert_eq!(
+            "[Ok(\"1\"), Err(\"Expected <f32>, e.g. 1.5. Decider: got string: x\"), Ok(\"1\")]",
+            from_utf8(&write[0..index]).unwrap()
+        );
+    }
+
+    #[test]
+    fn checksummed_frames() {
+        let mut read = framing::encode_frame(b"call 1.2");
+        let mut corrupted = framing::encode_frame(b"call 2.3");
+        corrupted[9] ^= 0x01;
+        read.extend_from_slice(&corrupted);
+        read.extend_from_slice(b"noise");
+        read.extend(framing::encode_frame(b"call 3.4"));
+        read.push(b'G');
+        read.extend(framing::encode_frame(b"call 4.5"));
+        let mut write = [0u8; 1024];
+
+        let mut eval = GameShell::new(0u8, &read[..], &mut write[..]);
+        eval.set_framing(Framing::Checksummed);
+
+        fn handler(context: &mut u8, _args: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok(context.to_string())
+        }
+
+        eval.register((&[("call", ANY_F32)], handler)).unwrap();
+
+        let buffer = &mut [0u8; 1024];
+        eval.run(buffer);
+
+        assert_eq!(3, *eval.context());
+
+        let index = write.iter().position(|&byte| byte == b'\0').unwrap();
+        assert_eq!(
+            format![
+                "Ok(\"1\")ChecksumError(\"Expected {:08x}, computed {:08x}\")Ok(\"2\")Ok(\"3\")",
+                framing::crc32(b"call 2.3"),
+                framing::crc32(b"c`ll 2.3"),
+            ],
+            from_utf8(&write[0..index]).unwrap()
+        );
+    }
+
+    #[test]
+    fn warnings_precede_result() {
+        let read = b"mode 1\nmode 2\n";
+        let mut write = [0u8; 1024];
+
+        let mut eval = GameShell::new(0u8, &read[..], &mut write[..]);
+
+        fn mode(input: &[&str], out: &mut cmdmat::SVec<Type>) -> cmdmat::Decision<String> {
+            if input.first() == Some(&"1") {
+                warn("mode 1 is deprecated");
+            }
+            out.push(Type::String(input[0].into()));
+            cmdmat::Decision::Accept(1)
+        }
+
+        fn handler(_: &mut u8, _args: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        const MODE: cmdmat::Decider<Type, String> = cmdmat::Decider {
+            description: "<mode>",
+            decider: mode,
+        };
+        eval.register((&[("mode", Some(&MODE))], handler)).unwrap();
+
+        let buffer = &mut [0u8; 1024];
+        eval.run(buffer);
+
+        let index = write.iter().position(|&byte| byte == b'\0').unwrap();
+        assert_eq!(
+            "Warning(\"mode 1 is deprecated\")Ok(\"\")Ok(\"\")",
+            from_utf8(&write[0..index]).unwrap()
+        );
+
+        // Each statement of a batch carries its own warnings
+        let read = b"mode 1; mode 2; mode 1\n";
+        let mut write = [0u8; 1024];
+        let mut eval = GameShell::new(0u8, &read[..], &mut write[..]);
+        eval.evaluator().set_batching(true);
+        eval.register((&[("mode", Some(&MODE))], handler)).unwrap();
+        eval.run(buffer);
+
+        let index = write.iter().position(|&byte| byte == b'\0').unwrap();
+        assert_eq!(
+            "[Warning(\"mode 1 is deprecated\"), Ok(\"\"), Ok(\"\"), \
+             Warning(\"mode 1 is deprecated\"), Ok(\"\")]",
+            from_utf8(&write[0..index]).unwrap()
+        );
+    }
+
+    #[test]
+    fn trimmed_output() {
+        let read = b"call\ncall\n";
+        let mut write = [0u8; 1024];
+
+        let mut eval = GameShell::new(0u8, &read[..], &mut write[..]);
+
+        fn handler(context: &mut u8, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            if *context == 1 {
+                Ok("result\n".into())
+            } else {
+                Err("error\n".into())
+            }
+        }
+
+        eval.register((&[("call", None)], handler)).unwrap();
+        eval.set_trim_output(true);
+
+        let buffer = &mut [0u8; 1024];
+        eval.run(buffer);
+
+        let index = write.iter().position(|&byte| byte == b'\0').unwrap();
+        assert_eq!(
+            "Ok(\"result\")Err(\"error\\n\")",
+            from_utf8(&write[0..index]).unwrap()
+        );
+    }
+
+    #[test]
+    fn prompt_reflects_context() {
+        let read = b"whoami\nlogin alice\nwhoami\n";
+        let mut write = [0u8; 1024];
+
+        let mut eval = GameShell::new(None, &read[..], &mut write[..]);
+
+        fn login(context: &mut Option<String>, args: &[Type]) -> Result<String, String> {
+            if let [Type::String(user)] = args {
+                *context = Some(user.clone());
+            }
+            Ok("".into())
+        }
+
+        fn whoami(context: &mut Option<String>, _: &[Type]) -> Result<String, String> {
+            context.clone().ok_or_else(|| "not logged in".into())
+        }
+
+        eval.register((&[("login", ANY_STRING)], login)).unwrap();
+        eval.register((&[("whoami", None)], whoami)).unwrap();
+        eval.set_dynamic_prompt(Box::new(|user: &Option<String>| match user {
+            Some(user) => format!("{}$ ", user),
+            None => "$ ".into(),
+        }));
+
+        let buffer = &mut [0u8; 1024];
+        eval.run(buffer);
+
+        let index = write.iter().position(|&byte| byte == b'\0').unwrap();
+        assert_eq!(
+            "Err(\"not logged in\")$ Ok(\"\")alice$ Ok(\"alice\")alice$ ",
+            from_utf8(&write[0..index]).unwrap()
+        );
+    }
+
+    #[test]
+    fn newline_in_parentheses_ends_statement() {
+        fn echo(_: &mut u8, args: &[Type]) -> Result<String, String> {
+            match args {
+                [Type::String(string)] => Ok(string.clone()),
+                _ => Err("expected a string".into()),
+            }
+        }
+
+        let read = b"echo (echo\nhi)\necho x\n";
+        let mut write = [0u8; 1024];
+        let mut eval = GameShell::new(0u8, &read[..], &mut write[..]);
+        eval.register((&[("echo", ANY_STRING)], echo)).unwrap();
+        eval.run(&mut [0u8; 1024]);
+        let index = write.iter().position(|&byte| byte == b'\0').unwrap();
+        assert_eq!("Ok(\"hi\")Ok(\"x\")", from_utf8(&write[0..index]).unwrap());
+
+        let mut write = [0u8; 1024];
+        let mut eval = GameShell::new(0u8, &read[..], &mut write[..]);
+        eval.register((&[("echo", ANY_STRING)], echo)).unwrap();
+        eval.evaluator().set_newline_closes_in_paren(true);
+        eval.run(&mut [0u8; 1024]);
+        let index = write.iter().position(|&byte| byte == b'\0').unwrap();
+        assert_eq!(
+            "ParseError(\"Unable to parse input: DanglingLeftParenthesis\")\
+             ParseError(\"Unable to parse input: NothingToParse\")Ok(\"x\")",
+            from_utf8(&write[0..index]).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_disconnects_or_is_replaced() {
+        fn echo(_: &mut u8, args: &[Type]) -> Result<String, String> {
+            match args {
+                [Type::Atom(atom)] => Ok(atom.clone()),
+                _ => Err("expected an atom".into()),
+            }
+        }
+
+        let read = b"echo a\xffb\necho c\n";
+        let mut write = [0u8; 1024];
+        let mut eval = GameShell::new(0u8, &read[..], &mut write[..]);
+        eval.register((&[("echo", ANY_ATOM)], echo)).unwrap();
+        eval.run(&mut [0u8; 1024]);
+        let index = write.iter().position(|&byte| byte == b'\0').unwrap();
+        assert_eq!(
+            "DecodeError(\"Received invalid UTF-8 input, disconnecting\")",
+            from_utf8(&write[0..index]).unwrap()
+        );
+
+        let mut write = [0u8; 1024];
+        let mut eval = GameShell::new(0u8, &read[..], &mut write[..]);
+        eval.register((&[("echo", ANY_ATOM)], echo)).unwrap();
+        eval.set_utf8_lossy(true);
+        eval.run(&mut [0u8; 1024]);
+        let index = write.iter().position(|&byte| byte == b'\0').unwrap();
+        assert_eq!(
+            "Ok(\"a\u{FFFD}b\")Ok(\"c\")",
+            from_utf8(&write[0..index]).unwrap()
+        );
+    }
+
+    #[test]
+    fn rebind_to_another_connection() {
+        let mut eval = GameShell::new(0u8, &b"call\ncall (call"[..], vec![]);
+
+        fn handler(context: &mut u8, _: &[Type]) -> Result<String, String> {
+            *context += 1;
+            Ok(context.to_string())
+        }
+
+        eval.register((&[("call", None)], handler)).unwrap();
+
+        let buffer = &mut [0u8; 1024];
+        eval.run(buffer);
+        let (_, first) = eval.rebind(&b"call\n"[..], vec![]);
+        assert_eq!("Ok(\"1\")", from_utf8(&first).unwrap());
+
+        eval.run(buffer);
+        let (_, second) = eval.rebind(&b""[..], vec![]);
+        assert_eq!("Ok(\"2\")", from_utf8(&second).unwrap());
+        assert_eq!(2, *eval.context());
+    }
+
+    #[test]
+    fn rebind_cancels_pending_confirmation() {
+        let mut eval = GameShell::new(1u8, &b"wipe\n"[..], vec![]);
+
+        fn wipe(context: &mut u8, _: &[Type]) -> Result<String, String> {
+            if !is_confirmed() {
+                return needs_confirmation();
+            }
+            *context = 0;
+            Ok("wiped".into())
+        }
+
+        eval.register((&[("wipe", None)], wipe)).unwrap();
+
+        let buffer = &mut [0u8; 1024];
+        eval.run(buffer);
+        let (_, first) = eval.rebind(&b"confirm\n"[..], vec![]);
+        assert_eq!(
+            "Ok(\"type 'confirm' to proceed\")",
+            from_utf8(&first).unwrap()
+        );
+
+        eval.run(buffer);
+        let (_, second) = eval.rebind(&b""[..], vec![]);
+        assert_eq!(
+            "Err(\"Unrecognized mapping: confirm\")",
+            from_utf8(&second).unwrap()
+        );
+        assert_eq!(1, *eval.context());
+    }
+
+    #[test]
+    fn raw_output() {
+        let read = b"screenshot\nlength (screenshot)\n";
+        let mut write = [0u8; 1024];
+
+        let mut eval = GameShell::new(0u8, &read[..], &mut write[..]);
+
+        fn screenshot(_: &mut u8, _: &[Type]) -> Result<String, String> {
+            Ok(raw_feedback(&[0, 159, 146, 150]))
+        }
+
+        fn length(_: &mut u8, args: &[Type]) -> Result<String, String> {
+            match args[0] {
+                Type::Raw(ref bytes) => Ok(bytes.len().to_string()),
+                _ => Err("expected raw".into()),
+            }
+        }
+
+        eval.register((&[("screenshot", None)], screenshot))
+            .unwrap();
+        eval.register((&[("length", ANY_BASE64)], length)).unwrap();
+
+        let buffer = &mut [0u8; 1024];
+        eval.run(buffer);
+
+        assert_eq!(&b"Raw(4)\0\x9f\x92\x96Ok(\"4\")\0"[..], &write[0..18]);
+    }
+
+    #[test]
+    fn raw_feedback_is_labelled_out_of_band() {
+        let mut eval = Evaluator::new(());
+
+        fn hello(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok(raw_feedback(b"hello"))
+        }
+
+        fn echo(_: &mut (), args: &[Type]) -> Result<String, String> {
+            match args[0] {
+                Type::String(ref string) => Ok(string.clone()),
+                _ => Err("expected string".into()),
+            }
+        }
+
+        fn altered(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok(raw_feedback(b"hello") + "!")
+        }
+
+        eval.register((&[("hello", None)], hello)).unwrap();
+        eval.register((&[("echo", ANY_STRING)], echo)).unwrap();
+        eval.register((&[("altered", None)], altered)).unwrap();
+
+        assert_eq!(Ok(Ok("aGVsbG8=".into())), eval.interpret_single("hello"));
+        assert_eq!(Some(&Presentation::Raw), eval.presentation());
+        assert_eq!(
+            Ok(Ok("aGVsbG8=".into())),
+            eval.interpret_single("echo (hello)")
+        );
+        assert_eq!(None, eval.presentation());
+        eval.interpret_single("altered").unwrap().unwrap();
+        assert_eq!(None, eval.presentation());
+
+        assert_eq!(
+            "[Raw(\"aGVsbG8=\"), Ok(\"aGVsbG8=\")]",
+            batch_response(&eval.interpret_batch_presented("hello; echo aGVsbG8="))
+        );
+    }
+
+    #[test]
+    fn typed_output() {
+        let read = b"stats\nlength (stats)\n";
+        let mut write = vec![];
+
+        let mut eval = GameShell::new(0u8, &read[..], &mut write);
+
+        fn stats(_: &mut u8, _: &[Type]) -> Result<String, String> {
+            Ok(typed_feedback("application/json", "{\"hp\":10}"))
+        }
+
+        fn length(_: &mut u8, args: &[Type]) -> Result<String, String> {
+            match args[0] {
+                Type::String(ref body) => Ok(body.len().to_string()),
+                _ => Err("expected string".into()),
+            }
+        }
+
+        eval.register((&[("stats", None)], stats)).unwrap();
+        eval.register((&[("length", ANY_STRING)], length)).unwrap();
+
+        let buffer = &mut [0u8; 1024];
+        eval.run(buffer);
+
+        assert_eq!(
+            "Typed(\"application/json\", \"{\\\"hp\\\":10}\")Ok(\"9\")",
+            from_utf8(&write).unwrap()
+        );
+
+        let mut eval = Evaluator::new(0u8);
+        eval.register((&[("stats", None)], stats)).unwrap();
+        assert_eq!(
+            "[Typed(\"application/json\", \"{\\\"hp\\\":10}\")]",
+            batch_response(&eval.interpret_batch_presented("stats"))
+        );
+
+        eval.set_max_output_len(Some(4), evaluator::OutputOverflow::Truncate);
+        assert_eq!(
+            Ok(Ok("{\"hp...(truncated)".into())),
+            eval.interpret_single("stats")
+        );
+        assert_eq!(None, eval.presentation());
+    }
+
     #[test]
     fn partial_read_succeeds() {
         let read = b"call 1.2\nrock 3.1\n";
@@ -62,8 +62,60 @@
 //! eval.interpret_single("my-command 124").unwrap().unwrap();
 //! assert_eq!(Err("Expected <i32-over-123>. Decider: Number is not >123".into()), eval.interpret_single("my-command -9").unwrap());
 //! ```
+//!
+//! ## Depending on earlier arguments ##
+//!
+//! The `out` buffer a decider receives already holds the values pushed by the deciders before it
+//! in the same command, so a decider can validate its input against earlier arguments. Deciders
+//! must only push to `out`, never remove or modify the values already there.
+//!
+//! ```
+//! use gameshell::{predicates::ANY_ATOM, types::Type, Evaluate, Evaluator};
+//! use gameshell::cmdmat::{Decider, Decision, SVec};
+//!
+//! // Accepts a volume whose maximum depends on the category before it
+//! fn volume(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+//!     let max = match out.last() {
+//!         Some(Type::Atom(category)) if category == "music" => 10,
+//!         _ => 100,
+//!     };
+//!     match input.first().map(|arg| arg.parse::<u8>()) {
+//!         Some(Ok(level)) if level <= max => {
+//!             out.push(Type::U8(level));
+//!             Decision::Accept(1)
+//!         }
+//!         _ => Decision::Deny(format!["expected 0 to {}", max]),
+//!     }
+//! }
+//!
+//! fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+//!     Ok("".into())
+//! }
+//!
+//! let mut eval = Evaluator::new(());
+//! let volume = Some(&Decider { description: "<volume>", decider: volume });
+//! eval.register((&[("volume", ANY_ATOM), ("set", volume)], handler)).unwrap();
+//! assert!(eval.interpret_single("volume sfx set 50").unwrap().is_ok());
+//! assert!(eval.interpret_single("volume music set 50").unwrap().is_err());
+//! ```
+//!
+//! ## Testing a custom predicate ##
+//!
+//! [check_decider] checks the invariants the evaluator relies on for a single input. Run it on
+//! many generated inputs, for instance with `quickcheck`, to test a custom decider.
 use crate::types::Type;
 use cmdmat::{Decider, Decision, SVec};
+use regex::Regex;
+use std::{
+    any::Any,
+    net::{IpAddr, SocketAddr},
+    num::{IntErrorKind, ParseIntError},
+    ops::Deref,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 // ---
 
@@ -72,6 +124,14 @@ pub type SomeDec = Option<&'static Decider<Type, String>>;
 
 // Please keep this list sorted
 
+/// Accepts an angle with a `deg` or `rad` unit such as `90deg` or `1.57rad`, producing a
+/// [Type::F32] in radians
+///
+/// A bare number is denied since its unit would be ambiguous.
+pub const ANY_ANGLE: SomeDec = Some(&Decider {
+    description: "<angle>",
+    decider: any_angle_function,
+});
 /// Accepts a single string which does not contain whitespace
 pub const ANY_ATOM: SomeDec = Some(&Decider {
     description: "<atom>",
@@ -87,16 +147,116 @@ pub const ANY_BOOL: SomeDec = Some(&Decider {
     description: "<true/false>",
     decider: any_bool_function,
 });
+/// Accepts a single `true`/`false`, `yes`/`no`, `on`/`off`, or `1`/`0`, ignoring case
+///
+/// Use [ANY_BOOL] to accept only `true` and `false`.
+pub const ANY_BOOL_LOOSE: SomeDec = Some(&Decider {
+    description: "<yes/no>",
+    decider: any_bool_loose_function,
+});
+/// Accepts a single unicode scalar value
+pub const ANY_CHAR: SomeDec = Some(&Decider {
+    description: "<char>",
+    decider: any_char_function,
+});
+/// Accepts a single IP network in CIDR notation such as `10.0.0.0/8` or `fe80::/10`
+///
+/// The prefix may be at most 32 for IPv4 and 128 for IPv6. Host bits are not required to be zero.
+pub const ANY_CIDR: SomeDec = Some(&Decider {
+    description: "<ip/prefix>",
+    decider: any_cidr_function,
+});
+/// Accepts a single duration such as `1500ms` or `2s`, with a whole number of `ms`, `s`, `m`, or
+/// `h`
+pub const ANY_DURATION: SomeDec = Some(&Decider {
+    description: "<duration>",
+    decider: any_duration_function,
+});
 /// Accepts a single f32
+///
+/// This accepts anything the standard library parses as an `f32`, including `NaN`, `inf`, and
+/// `-inf`. Use [FINITE_F32] to reject those.
 pub const ANY_F32: SomeDec = Some(&Decider {
     description: "<f32>",
     decider: any_f32_function,
 });
+/// Accepts a single f64
+///
+/// Like [ANY_F32], this accepts `NaN`, `inf`, and `-inf`.
+pub const ANY_F64: SomeDec = Some(&Decider {
+    description: "<f64>",
+    decider: any_f64_function,
+});
+/// Accepts a single f32, using either a comma or a period as the decimal separator
+pub const ANY_F32_LOCALE: SomeDec = Some(&Decider {
+    description: "<f32>",
+    decider: any_f32_locale_function,
+});
+/// Accepts a single even-length string of hexadecimal digits such as `deadBEEF`, pushing the bytes
+/// it encodes as a [Type::Raw]
+pub const ANY_HEX_BYTES: SomeDec = Some(&Decider {
+    description: "<hex>",
+    decider: any_hex_bytes_function,
+});
+/// Accepts a single hexadecimal u32 such as `0xDEADBEEF`, the `0x` prefix is optional
+pub const ANY_HEX_U32: SomeDec = Some(&Decider {
+    description: "<hex-u32>",
+    decider: any_hex_u32_function,
+});
+/// Accepts a single i8, denying values that do not fit
+pub const ANY_I8: SomeDec = Some(&Decider {
+    description: "<i8>",
+    decider: any_i8_function,
+});
+/// Accepts a single i16, denying values that do not fit
+pub const ANY_I16: SomeDec = Some(&Decider {
+    description: "<i16>",
+    decider: any_i16_function,
+});
 /// Accepts a single i32
 pub const ANY_I32: SomeDec = Some(&Decider {
     description: "<i32>",
     decider: any_i32_function,
 });
+/// Accepts a single i64
+pub const ANY_I64: SomeDec = Some(&Decider {
+    description: "<i64>",
+    decider: any_i64_function,
+});
+/// Accepts a single IPv4 or IPv6 address such as `127.0.0.1`
+pub const ANY_IPADDR: SomeDec = Some(&Decider {
+    description: "<ip>",
+    decider: any_ipaddr_function,
+});
+/// Accepts a single filesystem path without checking the filesystem
+pub const ANY_PATH: SomeDec = Some(&Decider {
+    description: "<path>",
+    decider: any_path_function,
+});
+/// Accepts a single argument enclosed in double quotes, unescaping `\n`, `\t`, `\"`, and `\\`
+///
+/// Arguments are split on whitespace before deciders see them, so the quoted string cannot
+/// contain spaces. Use `\t` or [QUOTED_STRING], which joins several arguments, instead.
+pub const ANY_QUOTED: SomeDec = Some(&Decider {
+    description: "<\"escaped\">",
+    decider: any_quoted_function,
+});
+/// Accepts a single regular expression, pushing the pattern as a [Type::Regex] if it compiles
+pub const ANY_REGEX: SomeDec = Some(&Decider {
+    description: "<regex>",
+    decider: any_regex_function,
+});
+/// Accepts a semantic version such as `1.2.3-beta.1`
+#[cfg(feature = "with-semver")]
+pub const ANY_SEMVER: SomeDec = Some(&Decider {
+    description: "<semver>",
+    decider: any_semver_function,
+});
+/// Accepts a single IP address with a port such as `127.0.0.1:8080` or `[::1]:8080`
+pub const ANY_SOCKETADDR: SomeDec = Some(&Decider {
+    description: "<ip:port>",
+    decider: any_socketaddr_function,
+});
 /// Accepts a single string
 pub const ANY_STRING: SomeDec = Some(&Decider {
     description: "<string>",
@@ -107,16 +267,69 @@ pub const ANY_U8: SomeDec = Some(&Decider {
     description: "<u8>",
     decider: any_u8_function,
 });
+/// Accepts a single u16, denying values that do not fit
+pub const ANY_U16: SomeDec = Some(&Decider {
+    description: "<u16>",
+    decider: any_u16_function,
+});
+/// Accepts a single u32, denying values that do not fit
+pub const ANY_U32: SomeDec = Some(&Decider {
+    description: "<u32>",
+    decider: any_u32_function,
+});
+/// Accepts a single u64
+pub const ANY_U64: SomeDec = Some(&Decider {
+    description: "<u64>",
+    decider: any_u64_function,
+});
 /// Accepts a single usize
 pub const ANY_USIZE: SomeDec = Some(&Decider {
     description: "<usize>",
     decider: any_usize_function,
 });
+/// Accepts a single comma-separated list of i32s such as `1,2,3`, pushing a [Type::I32List]
+pub const CSV_I32: SomeDec = Some(&Decider {
+    description: "<i32,...>",
+    decider: csv_i32_function,
+});
+/// Accepts a single filesystem path that exists at the time the command is looked up
+///
+/// The path may be removed before the handler runs, so handlers must still handle a missing
+/// path.
+pub const EXISTING_PATH: SomeDec = Some(&Decider {
+    description: "<existing path>",
+    decider: existing_path_function,
+});
+/// Accepts a single f32 that is neither infinite nor `NaN`
+pub const FINITE_F32: SomeDec = Some(&Decider {
+    description: "<finite f32>",
+    decider: finite_f32_function,
+});
+/// Accepts a single file glob pattern such as `*.png`, without expanding it
+#[cfg(feature = "with-glob")]
+pub const GLOB: SomeDec = Some(&Decider {
+    description: "<glob>",
+    decider: glob_function,
+});
 /// Ignores all arguments
 pub const IGNORE_ALL: SomeDec = Some(&Decider {
     description: "<anything> ...",
     decider: ignore_all_function,
 });
+/// Accepts a single key chord such as `Ctrl+Shift+K`, pushing a [Type::KeyChord]
+///
+/// Modifiers are `Ctrl`, `Shift`, `Alt`, and `Super`, ignoring case, with `Control`, `Option`,
+/// `Meta`, `Cmd`, and `Win` as aliases. The key is a single character, which is uppercased, or
+/// an alphanumeric name such as `F5` or `Space`.
+pub const KEYCHORD: SomeDec = Some(&Decider {
+    description: "<keychord>",
+    decider: keychord_function,
+});
+/// Accepts 0 or more f32s, stopping at the first argument that is not an f32
+pub const MANY_F32: SomeDec = Some(&Decider {
+    description: "<f32> ...",
+    decider: many_f32_function,
+});
 /// Accepts 1 or more i32s
 pub const MANY_I32: SomeDec = Some(&Decider {
     description: "<i32> ...",
@@ -127,19 +340,803 @@ pub const MANY_STRING: SomeDec = Some(&Decider {
     description: "<string> ...",
     decider: many_string_function,
 });
+/// Accepts 0 or more u8s, stopping at the first argument that is not a u8, and pushes them as a
+/// single [Type::Raw]
+pub const MANY_U8: SomeDec = Some(&Decider {
+    description: "<u8> ...",
+    decider: many_u8_function,
+});
+/// Accepts a single i32 other than 0, for divisors and similar arguments
+pub const NONZERO_I32: SomeDec = Some(&Decider {
+    description: "<i32!=0>",
+    decider: nonzero_i32_function,
+});
+/// Accepts a single atom, normalizing it to Unicode NFC so that composed and decomposed forms of
+/// the same name compare equal
+///
+/// Input containing control characters is denied.
+#[cfg(feature = "with-unicode-normalization")]
+pub const NORMALIZED_ATOM: SomeDec = Some(&Decider {
+    description: "<atom>",
+    decider: normalized_atom_function,
+});
 /// Accepts a positive f32
 pub const POSITIVE_F32: SomeDec = Some(&Decider {
     description: "<f32>=0>",
     decider: positive_f32_function,
 });
+/// Accepts a string spanning multiple arguments, enclosed in double quotes
+pub const QUOTED_STRING: SomeDec = Some(&Decider {
+    description: "<\"string\">",
+    decider: quoted_string_function,
+});
 /// Accepts two strings
 pub const TWO_STRINGS: SomeDec = Some(&Decider {
     description: "<string> <string>",
     decider: two_string_function,
 });
 
+/// Example values for the predicates above, keyed by description
+///
+/// These are shown when a decider denies its input, see [crate::Evaluator::set_example].
+pub const EXAMPLES: &[(&str, &str)] = &[
+    ("<angle>", "90deg"),
+    ("<atom>", "hello"),
+    ("<base64>", "aGVsbG8="),
+    ("<true/false>", "true"),
+    ("<yes/no>", "yes"),
+    ("<char>", "a"),
+    ("<ip/prefix>", "10.0.0.0/8"),
+    ("<duration>", "1500ms"),
+    ("<f32>", "1.5"),
+    ("<f64>", "51.4769444444"),
+    ("<existing path>", "saves/slot1.sav"),
+    ("<finite f32>", "1.5"),
+    ("<glob>", "*.png"),
+    ("<hex>", "deadbeef"),
+    ("<hex-u32>", "0xDEADBEEF"),
+    ("<i8>", "-128"),
+    ("<i16>", "-32768"),
+    ("<i32>", "-42"),
+    ("<i64>", "1581589852000"),
+    ("<ip>", "127.0.0.1"),
+    ("<path>", "saves/slot1.sav"),
+    ("<\"escaped\">", "\"line\\nbreak\""),
+    ("<regex>", "^[a-z]+$"),
+    ("<semver>", "1.2.3-beta.1"),
+    ("<ip:port>", "127.0.0.1:8080"),
+    ("<string>", "hello"),
+    ("<u8>", "255"),
+    ("<u16>", "65535"),
+    ("<u32>", "4294967295"),
+    ("<u64>", "18446744073709551615"),
+    ("<usize>", "42"),
+    ("<i32,...>", "1,2,3"),
+    ("<keychord>", "Ctrl+Shift+K"),
+    ("<i32> ...", "1 2 3"),
+    ("<f32> ...", "1.0 2.5"),
+    ("<u8> ...", "10 20 30 255"),
+    ("<string> ...", "hello world"),
+    ("<i32!=0>", "2"),
+    ("<f32>=0>", "1.5"),
+    ("<\"string\">", "\"hello world\""),
+    ("<string> <string>", "hello world"),
+];
+
+/// The predicate constants above paired with their names, sorted by name
+///
+/// Use this to enumerate the available predicates, for instance to document them or to build
+/// autocompletion menus, instead of listing them by hand. Predicates behind a disabled feature are
+/// left out.
+pub fn builtin_predicates() -> &'static [(&'static str, SomeDec)] {
+    BUILTIN_PREDICATES
+}
+
+// Add new predicate constants here as well, keeping the names sorted
+const BUILTIN_PREDICATES: &[(&str, SomeDec)] = &[
+    ("ANY_ANGLE", ANY_ANGLE),
+    ("ANY_ATOM", ANY_ATOM),
+    ("ANY_BASE64", ANY_BASE64),
+    ("ANY_BOOL", ANY_BOOL),
+    ("ANY_BOOL_LOOSE", ANY_BOOL_LOOSE),
+    ("ANY_CHAR", ANY_CHAR),
+    ("ANY_CIDR", ANY_CIDR),
+    ("ANY_DURATION", ANY_DURATION),
+    ("ANY_F32", ANY_F32),
+    ("ANY_F32_LOCALE", ANY_F32_LOCALE),
+    ("ANY_F64", ANY_F64),
+    ("ANY_HEX_BYTES", ANY_HEX_BYTES),
+    ("ANY_HEX_U32", ANY_HEX_U32),
+    ("ANY_I16", ANY_I16),
+    ("ANY_I32", ANY_I32),
+    ("ANY_I64", ANY_I64),
+    ("ANY_I8", ANY_I8),
+    ("ANY_IPADDR", ANY_IPADDR),
+    ("ANY_PATH", ANY_PATH),
+    ("ANY_QUOTED", ANY_QUOTED),
+    ("ANY_REGEX", ANY_REGEX),
+    #[cfg(feature = "with-semver")]
+    ("ANY_SEMVER", ANY_SEMVER),
+    ("ANY_SOCKETADDR", ANY_SOCKETADDR),
+    ("ANY_STRING", ANY_STRING),
+    ("ANY_U16", ANY_U16),
+    ("ANY_U32", ANY_U32),
+    ("ANY_U64", ANY_U64),
+    ("ANY_U8", ANY_U8),
+    ("ANY_USIZE", ANY_USIZE),
+    ("CSV_I32", CSV_I32),
+    ("EXISTING_PATH", EXISTING_PATH),
+    ("FINITE_F32", FINITE_F32),
+    #[cfg(feature = "with-glob")]
+    ("GLOB", GLOB),
+    ("IGNORE_ALL", IGNORE_ALL),
+    ("KEYCHORD", KEYCHORD),
+    ("MANY_F32", MANY_F32),
+    ("MANY_I32", MANY_I32),
+    ("MANY_STRING", MANY_STRING),
+    ("MANY_U8", MANY_U8),
+    ("NONZERO_I32", NONZERO_I32),
+    #[cfg(feature = "with-unicode-normalization")]
+    ("NORMALIZED_ATOM", NORMALIZED_ATOM),
+    ("POSITIVE_F32", POSITIVE_F32),
+    ("QUOTED_STRING", QUOTED_STRING),
+    ("TWO_STRINGS", TWO_STRINGS),
+];
+
+// ---
+
+/// Try each decider in turn, accepting the input of the first one that accepts
+///
+/// If all alternatives deny the input, the denial lists the reason given by each alternative.
+/// `None` alternatives are skipped. Use this inside your own decider function:
+///
+/// ```
+/// use gameshell::{predicates::*, types::Type};
+/// use gameshell::cmdmat::{Decider, Decision, SVec};
+///
+/// pub const I32_OR_BOOL: SomeDec = Some(&Decider {
+///     description: "<i32/true/false>",
+///     decider: i32_or_bool,
+/// });
+///
+/// fn i32_or_bool(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+///     one_of(&[ANY_I32, ANY_BOOL], input, out)
+/// }
+///
+/// let out = &mut SVec::new();
+/// assert_eq!(Decision::Accept(1), i32_or_bool(&["true"], out));
+/// assert_eq!(
+///     Decision::Deny("tried <i32>: got string: x; tried <true/false>: got string: x".into()),
+///     i32_or_bool(&["x"], out)
+/// );
+/// ```
+pub fn one_of(alternatives: &[SomeDec], input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    let length = out.len();
+    let mut reasons = vec![];
+    for decider in alternatives.iter().flatten() {
+        match (decider.decider)(input, out) {
+            Decision::Accept(count) => return Decision::Accept(count),
+            Decision::Deny(reason) => {
+                out.truncate(length);
+                reasons.push(format!["tried {}: {}", decider.description, reason]);
+            }
+        }
+    }
+    Decision::Deny(reasons.join("; "))
+}
+
+/// Create a decider accepting a single argument parsed using [FromStr]
+///
+/// The parsed value is pushed as a [Type::Custom], which the handler downcasts back into `T`.
+///
+/// ```
+/// use gameshell::{predicates::*, types::Type, Evaluate, Evaluator};
+/// use std::str::FromStr;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Difficulty {
+///     Easy,
+///     Hard,
+/// }
+///
+/// impl FromStr for Difficulty {
+///     type Err = ();
+///     fn from_str(input: &str) -> Result<Self, Self::Err> {
+///         match input {
+///             "easy" => Ok(Difficulty::Easy),
+///             "hard" => Ok(Difficulty::Hard),
+///             _ => Err(()),
+///         }
+///     }
+/// }
+///
+/// const ANY_DIFFICULTY: SomeDec = Some(&from_str_decider::<Difficulty>("<easy/hard>"));
+///
+/// fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+///     match &args[0] {
+///         Type::Custom(any) => Ok(format!["{:?}", any.downcast_ref::<Difficulty>().unwrap()]),
+///         _ => panic!("Wrong arguments"),
+///     }
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// eval.register((&[("difficulty", ANY_DIFFICULTY)], handler)).unwrap();
+/// assert_eq!(Ok("Hard".into()), eval.interpret_single("difficulty hard").unwrap());
+/// assert_eq!(
+///     Err("Expected <easy/hard>. Decider: got string: normal".into()),
+///     eval.interpret_single("difficulty normal").unwrap()
+/// );
+/// ```
+pub const fn from_str_decider<T: FromStr + Any + Send + Sync>(
+    description: &'static str,
+) -> Decider<Type, String> {
+    Decider {
+        description,
+        decider: from_str_function::<T>,
+    }
+}
+
+fn from_str_function<T: FromStr + Any + Send + Sync>(
+    input: &[&str],
+    out: &mut SVec<Type>,
+) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<T>() {
+        Ok(value) => {
+            out.push(Type::Custom(Arc::new(value)));
+        }
+        Err(_) => {
+            return Decision::Deny("got string: ".to_string() + input[0]);
+        }
+    }
+    Decision::Accept(1)
+}
+
+/// Generate a decider for a newtype wrapping a type that implements [FromStr]
+///
+/// `decider_for_newtype!(EntityId, u32, "<entity-id>")` generates `EntityId::DECIDER`, which
+/// parses a single argument as a `u32`, wraps it in `EntityId`, and pushes it as a
+/// [Type::Custom]. Handlers extract the value using the generated `EntityId::from_type`.
+///
+/// ```
+/// use gameshell::{decider_for_newtype, types::Type, Evaluate, Evaluator};
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct EntityId(u32);
+///
+/// decider_for_newtype!(EntityId, u32, "<entity-id>");
+///
+/// fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+///     let id = EntityId::from_type(&args[0]).unwrap();
+///     Ok(format!["{:?}", id])
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// eval.register((&[("kill", EntityId::DECIDER)], handler)).unwrap();
+/// assert_eq!(Ok("EntityId(12)".into()), eval.interpret_single("kill 12").unwrap());
+/// ```
+#[macro_export]
+macro_rules! decider_for_newtype {
+    ($newtype:ident, $inner:ty, $description:expr) => {
+        impl $newtype {
+            /// Accepts a single value of this newtype
+            pub const DECIDER: $crate::predicates::SomeDec = Some(&$crate::cmdmat::Decider {
+                description: $description,
+                decider: {
+                    fn decider(
+                        input: &[&str],
+                        out: &mut $crate::cmdmat::SVec<$crate::types::Type>,
+                    ) -> $crate::cmdmat::Decision<::std::string::String> {
+                        match input.first().map(|arg| arg.parse::<$inner>()) {
+                            Some(Ok(value)) => {
+                                out.push($crate::types::Type::Custom(::std::sync::Arc::new(
+                                    $newtype(value),
+                                )));
+                                $crate::cmdmat::Decision::Accept(1)
+                            }
+                            Some(Err(_)) => $crate::cmdmat::Decision::Deny(
+                                ::std::string::String::from("got string: ") + input[0],
+                            ),
+                            None => $crate::cmdmat::Decision::Deny(format![
+                                "Too few elements: {:?}, length: {}, expected: {}",
+                                input,
+                                input.len(),
+                                1
+                            ]),
+                        }
+                    }
+                    decider
+                },
+            });
+
+            /// Extract a value of this newtype from an argument
+            pub fn from_type(arg: &$crate::types::Type) -> Option<&Self> {
+                match arg {
+                    $crate::types::Type::Custom(any) => any.downcast_ref::<Self>(),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+/// Check the invariants every decider must uphold for `input`, for use in property tests of
+/// custom deciders
+///
+/// A decider may not accept more arguments than it is given and may not push output when it
+/// denies. When it accepts, it may push at most one output per accepted argument, or a single
+/// output when it accepts none, as [MANY_U8] does for an empty list. Returns a description of
+/// the first invariant that does not hold.
+///
+/// ```
+/// use gameshell::predicates::{check_decider, ANY_I32, MANY_STRING};
+///
+/// assert_eq!(Ok(()), check_decider(ANY_I32.unwrap(), &["1", "2"]));
+/// assert_eq!(Ok(()), check_decider(MANY_STRING.unwrap(), &["a", "b"]));
+/// ```
+pub fn check_decider(decider: &Decider<Type, String>, input: &[&str]) -> Result<(), String> {
+    let out = &mut SVec::new();
+    match (decider.decider)(input, out) {
+        Decision::Accept(count) if count > input.len() => Err(format![
+            "{} accepted {} of {} arguments",
+            decider.description,
+            count,
+            input.len()
+        ]),
+        Decision::Accept(count) if out.len() > count.max(1) => Err(format![
+            "{} pushed {} outputs for {} arguments",
+            decider.description,
+            out.len(),
+            count
+        ]),
+        Decision::Deny(_) if !out.is_empty() => Err(format![
+            "{} pushed {} outputs and denied",
+            decider.description,
+            out.len()
+        ]),
+        _ => Ok(()),
+    }
+}
+
+/// The number of deciders the constructors of this module, such as [bounded_i32] and
+/// [regex_match], can keep alive at the same time
+///
+/// Decider functions cannot capture state, so each of these constructors returns a
+/// [RuntimeDecider] that keeps its state in one of a fixed number of slots shared by all of them.
+/// Dropping the decider frees its slot for the next one. Register it using
+/// [crate::Evaluator::register_with_decider], which keeps it until the command using it is
+/// unregistered. The constructors fail while every slot is taken.
+pub const RUNTIME_DECIDER_LIMIT: usize = 256;
+
+/// A decider built at runtime, see [RUNTIME_DECIDER_LIMIT]
+///
+/// Dereferences to its [Decider], which is leaked along with its description as the mapping only
+/// holds `'static` references. The state in its slot is not.
+pub struct RuntimeDecider {
+    inner: &'static Decider<Type, String>,
+    slot: Option<usize>,
+}
+
+impl RuntimeDecider {
+    // The decider for the mapping, which must not use it after this handle is dropped
+    pub(crate) fn leak(&self) -> &'static Decider<Type, String> {
+        self.inner
+    }
+}
+
+/// Wrap a decider built some other way, which takes no slot. The decider is leaked.
+impl From<Box<Decider<Type, String>>> for RuntimeDecider {
+    fn from(decider: Box<Decider<Type, String>>) -> Self {
+        Self {
+            inner: Box::leak(decider),
+            slot: None,
+        }
+    }
+}
+
+impl Deref for RuntimeDecider {
+    type Target = Decider<Type, String>;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
+impl Drop for RuntimeDecider {
+    fn drop(&mut self) {
+        if let (Some(slot), Ok(mut deciders)) = (self.slot, RUNTIME_DECIDERS.write()) {
+            deciders[slot] = None;
+        }
+    }
+}
+
+/// Create a decider that accepts a single argument if the whole argument matches `pattern`,
+/// pushing it as a [Type::String]
+///
+/// The pattern is compiled once, here. Fails if the pattern does not compile.
+///
+/// ```
+/// use gameshell::{predicates::regex_match, types::Type, Evaluate, Evaluator};
+///
+/// fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+///     Ok(format!["{:?}", args[0]])
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// let decider = regex_match("[a-z]{3,8}").unwrap();
+/// eval.register_with_decider(&["set-nick"], decider, handler).unwrap();
+/// assert_eq!(Ok("String(\"alice\")".into()), eval.interpret_single("set-nick alice").unwrap());
+/// ```
+pub fn regex_match(pattern: &str) -> Result<RuntimeDecider, String> {
+    pattern_decider(pattern, Type::String)
+}
+
+/// Create a decider that accepts a single argument if the whole argument matches `pattern`,
+/// pushing it as a [Type::Atom]
+///
+/// Works like [regex_match], which pushes a [Type::String] instead.
+///
+/// ```
+/// use gameshell::{predicates::matching, types::Type, Evaluate, Evaluator};
+///
+/// fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+///     Ok(format!["{:?}", args[0]])
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// eval.register_with_decider(&["goto"], matching("[A-H][1-8]").unwrap(), handler).unwrap();
+/// assert_eq!(Ok("Atom(\"E4\")".into()), eval.interpret_single("goto E4").unwrap());
+/// assert!(eval.interpret_single("goto E9").unwrap().is_err());
+/// ```
+pub fn matching(pattern: &'static str) -> Result<RuntimeDecider, String> {
+    pattern_decider(pattern, Type::Atom)
+}
+
+// Create a decider for `regex_match` and `matching`, which differ in the type they push
+fn pattern_decider(pattern: &str, output: fn(String) -> Type) -> Result<RuntimeDecider, String> {
+    let regex = Regex::new(&format!["^(?:{})$", pattern]).map_err(|err| err.to_string())?;
+    let description = format!["<{}>", pattern];
+    let pattern = pattern.to_string();
+    runtime_decider(description, move |input, out| {
+        aslen(input, 1)?;
+        if regex.is_match(input[0]) {
+            out.push(output(input[0].into()));
+            Decision::Accept(1)
+        } else {
+            Decision::Deny(format!["does not match {}", pattern])
+        }
+    })
+}
+
+/// Create a decider that accepts a single i32 between `min` and `max` inclusive
+///
+/// Fails if `min > max`.
+///
+/// ```
+/// use gameshell::{predicates::bounded_i32, types::Type, Evaluate, Evaluator};
+///
+/// fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+///     Ok(format!["{:?}", args[0]])
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// eval.register_with_decider(&["roll"], bounded_i32(1, 6).unwrap(), handler).unwrap();
+/// assert_eq!(Ok("I32(6)".into()), eval.interpret_single("roll 6").unwrap());
+/// assert!(eval.interpret_single("roll 7").unwrap().is_err());
+/// ```
+pub fn bounded_i32(min: i32, max: i32) -> Result<RuntimeDecider, String> {
+    if min > max {
+        return Err(format!["Empty range: {}..={}", min, max]);
+    }
+    runtime_decider(format!["<i32 {}..={}>", min, max], move |input, out| {
+        aslen(input, 1)?;
+        match input[0].parse::<i32>() {
+            Ok(num) if min <= num && num <= max => {
+                out.push(Type::I32(num));
+                Decision::Accept(1)
+            }
+            Ok(_) => Decision::Deny(format![
+                "value out of range {}..={}: {}",
+                min, max, input[0]
+            ]),
+            Err(_) => Decision::Deny("got string: ".to_string() + input[0]),
+        }
+    })
+}
+
+/// Create a decider that accepts a single f32 between `min` and `max` inclusive, see
+/// [bounded_i32]
+///
+/// `NaN` is never within bounds. Fails if `min > max` or either bound is `NaN`.
+pub fn bounded_f32(min: f32, max: f32) -> Result<RuntimeDecider, String> {
+    if min.is_nan() || max.is_nan() || min > max {
+        return Err(format!["Empty range: {}..={}", min, max]);
+    }
+    runtime_decider(format!["<f32 {}..={}>", min, max], move |input, out| {
+        aslen(input, 1)?;
+        match input[0].parse::<f32>() {
+            Ok(num) if min <= num && num <= max => {
+                out.push(Type::F32(num));
+                Decision::Accept(1)
+            }
+            Ok(_) => Decision::Deny(format![
+                "value out of range {}..={}: {}",
+                min, max, input[0]
+            ]),
+            Err(_) => Decision::Deny("got string: ".to_string() + input[0]),
+        }
+    })
+}
+
+/// Create a decider that accepts a single argument equal to one of `options`, pushing it as a
+/// [Type::Atom]
+///
+/// The description lists the options, as in `(fast|slow|off)`. Fails if `options` is empty.
+///
+/// ```
+/// use gameshell::{predicates::one_of_literals, types::Type, Evaluate, Evaluator};
+///
+/// fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+///     Ok(format!["{:?}", args[0]])
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// let mode = one_of_literals(&["fast", "slow", "off"]).unwrap();
+/// eval.register_with_decider(&["mode"], mode, handler).unwrap();
+/// assert_eq!(Ok("Atom(\"slow\")".into()), eval.interpret_single("mode slow").unwrap());
+/// assert!(eval.interpret_single("mode medium").unwrap().is_err());
+/// ```
+pub fn one_of_literals(options: &'static [&'static str]) -> Result<RuntimeDecider, String> {
+    if options.is_empty() {
+        return Err("No options given".into());
+    }
+    runtime_decider(format!["({})", options.join("|")], move |input, out| {
+        aslen(input, 1)?;
+        if options.contains(&input[0]) {
+            out.push(Type::Atom(input[0].into()));
+            Decision::Accept(1)
+        } else {
+            Decision::Deny(format!["expected one of: {}", options.join(", ")])
+        }
+    })
+}
+
+/// Create a decider that applies `inner` `count` times in a row, as in `<i32> <i32>` for
+/// `exactly(2, ANY_I32.unwrap())`
+///
+/// The arguments each application consumes are summed, and all values are pushed in order. If an
+/// application denies, the denial names its index, counting from 0. Fails if `count` is 0.
+///
+/// ```
+/// use gameshell::{predicates::{exactly, ANY_I32}, types::Type, Evaluate, Evaluator};
+///
+/// fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+///     Ok(format!["{:?}", args])
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// eval.register_with_decider(&["point"], exactly(2, ANY_I32.unwrap()).unwrap(), handler)
+///     .unwrap();
+/// assert_eq!(Ok("[I32(5), I32(6)]".into()), eval.interpret_single("point 5 6").unwrap());
+/// assert!(eval.interpret_single("point 5 abc").unwrap().is_err());
+/// ```
+pub fn exactly(
+    count: usize,
+    inner: &'static Decider<Type, String>,
+) -> Result<RuntimeDecider, String> {
+    if count == 0 {
+        return Err("Count must be at least 1".into());
+    }
+    let description = vec![inner.description; count].join(" ");
+    runtime_decider(description, move |input, out| {
+        let length = out.len();
+        let mut consumed = 0;
+        for index in 0..count {
+            let rest = input.get(consumed..).unwrap_or_default();
+            match (inner.decider)(rest, out) {
+                Decision::Accept(advance) => consumed += advance,
+                Decision::Deny(reason) => {
+                    out.truncate(length);
+                    return Decision::Deny(format![
+                        "{} at index {}: {}",
+                        inner.description, index, reason
+                    ]);
+                }
+            }
+        }
+        Decision::Accept(consumed)
+    })
+}
+
+/// Create a decider that applies `inner` if it accepts, and otherwise accepts nothing, as in
+/// `[<i32>]` for `optional(ANY_I32.unwrap())`
+///
+/// When `inner` denies, the decider consumes no arguments and pushes no values, so handlers check
+/// `args.len()` to see whether the argument was given. A lookup fails with `DeciderAdvancedTooFar`
+/// only when a decider accepts more arguments than follow its literal, so accepting nothing at the
+/// end of the input is fine. The argument is only skipped when `inner` denies it, so `volume up`
+/// falls through to a literal `up` after an optional `<i32>`.
+///
+/// ```
+/// use gameshell::{predicates::{optional, ANY_I32}, types::Type, Evaluate, Evaluator};
+///
+/// fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+///     Ok(format!["{:?}", args])
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// eval.register_with_decider(&["volume"], optional(ANY_I32.unwrap()).unwrap(), handler)
+///     .unwrap();
+/// assert_eq!(Ok("[I32(5)]".into()), eval.interpret_single("volume 5").unwrap());
+/// assert_eq!(Ok("[]".into()), eval.interpret_single("volume").unwrap());
+/// ```
+pub fn optional(inner: &'static Decider<Type, String>) -> Result<RuntimeDecider, String> {
+    runtime_decider(format!["[{}]", inner.description], move |input, out| {
+        let length = out.len();
+        match (inner.decider)(input, out) {
+            Decision::Accept(advance) => Decision::Accept(advance),
+            Decision::Deny(_) => {
+                out.truncate(length);
+                Decision::Accept(0)
+            }
+        }
+    })
+}
+
+/// Create a decider that accepts between `min` and `max` i32s inclusive, see [MANY_I32]
+///
+/// Like [MANY_I32], the decider takes the i32s at the start of the input. It denies when there
+/// are fewer than `min` or more than `max` of them, naming the bound that was violated. Fails if
+/// `min > max`.
+///
+/// ```
+/// use gameshell::{predicates::many_i32_bounded, types::Type, Evaluate, Evaluator};
+///
+/// fn handler(_: &mut (), args: &[Type]) -> Result<String, String> {
+///     Ok(args.len().to_string())
+/// }
+///
+/// let mut eval = Evaluator::new(());
+/// eval.register_with_decider(&["point"], many_i32_bounded(2, 3).unwrap(), handler).unwrap();
+/// assert_eq!(Ok("3".into()), eval.interpret_single("point 1 2 3").unwrap());
+/// assert!(eval.interpret_single("point 1").unwrap().is_err());
+/// ```
+pub fn many_i32_bounded(min: usize, max: usize) -> Result<RuntimeDecider, String> {
+    if min > max {
+        return Err(format!["Empty range: {}..={}", min, max]);
+    }
+    runtime_decider(format!["<i32> ... {}..={}", min, max], move |input, out| {
+        let nums = input
+            .iter()
+            .map_while(|arg| arg.parse::<i32>().ok())
+            .collect::<Vec<_>>();
+        check_count(nums.len(), (min, max))?;
+        let count = nums.len();
+        out.extend(nums.into_iter().map(Type::I32));
+        Decision::Accept(count)
+    })
+}
+
+/// Create a decider that accepts between `min` and `max` strings inclusive, see [MANY_STRING]
+/// and [many_i32_bounded]
+pub fn many_string_bounded(min: usize, max: usize) -> Result<RuntimeDecider, String> {
+    if min > max {
+        return Err(format!["Empty range: {}..={}", min, max]);
+    }
+    runtime_decider(
+        format!["<string> ... {}..={}", min, max],
+        move |input, out| {
+            check_count(input.len(), (min, max))?;
+            out.extend(input.iter().map(|arg| Type::String(arg.to_string())));
+            Decision::Accept(input.len())
+        },
+    )
+}
+
+fn check_count(count: usize, (min, max): (usize, usize)) -> Result<(), String> {
+    if count < min {
+        Err(format![
+            "expected at least {} arguments, got {}",
+            min, count
+        ])
+    } else if count > max {
+        Err(format!["expected at most {} arguments, got {}", max, count])
+    } else {
+        Ok(())
+    }
+}
+
+type DeciderFunction = fn(&[&str], &mut SVec<Type>) -> Decision<String>;
+
+type DeciderClosure = Arc<dyn Fn(&[&str], &mut SVec<Type>) -> Decision<String> + Send + Sync>;
+
+// Create a decider whose function calls `decider`, keeping it in a free slot
+fn runtime_decider(
+    description: String,
+    decider: impl Fn(&[&str], &mut SVec<Type>) -> Decision<String> + Send + Sync + 'static,
+) -> Result<RuntimeDecider, String> {
+    let slot = claim_slot(&mut RUNTIME_DECIDERS.write().unwrap(), Arc::new(decider))?;
+    let inner = Box::leak(Box::new(Decider {
+        description: Box::leak(description.into_boxed_str()),
+        decider: RUNTIME_FUNCTIONS[slot / 16][slot % 16],
+    }));
+    Ok(RuntimeDecider {
+        inner,
+        slot: Some(slot),
+    })
+}
+
+// Put `decider` in the first free slot, failing if every slot is taken
+fn claim_slot(
+    slots: &mut Vec<Option<DeciderClosure>>,
+    decider: DeciderClosure,
+) -> Result<usize, String> {
+    if let Some(slot) = slots.iter().position(Option::is_none) {
+        slots[slot] = Some(decider);
+        return Ok(slot);
+    }
+    if slots.len() == RUNTIME_DECIDER_LIMIT {
+        return Err(format![
+            "Limit of {} runtime deciders reached",
+            RUNTIME_DECIDER_LIMIT
+        ]);
+    }
+    slots.push(Some(decider));
+    Ok(slots.len() - 1)
+}
+
+// State of the deciders created by `runtime_decider`, the closure at index `I` is called by
+// `runtime_function::<I>`. Slots of dropped deciders are empty until they are claimed again
+static RUNTIME_DECIDERS: RwLock<Vec<Option<DeciderClosure>>> = RwLock::new(Vec::new());
+
+// Instantiate `runtime_function` for every slot, in rows of 16 slots
+macro_rules! runtime_functions {
+    ($($row:literal)*) => {
+        [$(runtime_functions![@row $row; 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15]),*]
+    };
+    (@row $row:literal; $($column:literal)*) => {
+        [$(runtime_function::<{ 16 * $row + $column }>),*]
+    };
+}
+
+const RUNTIME_FUNCTIONS: [[DeciderFunction; 16]; RUNTIME_DECIDER_LIMIT / 16] =
+    runtime_functions![0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15];
+
+fn runtime_function<const I: usize>(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    // Release the lock before calling, the closure may call other runtime deciders
+    let decider = RUNTIME_DECIDERS.read().unwrap()[I].clone();
+    match decider {
+        Some(decider) => decider(input, out),
+        None => Decision::Deny("decider was dropped".into()),
+    }
+}
+
 // ---
 
+fn any_angle_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    let value = input[0].trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let to_radians = match &input[0][value.len()..] {
+        "deg" => std::f32::consts::PI / 180.0,
+        "rad" => 1.0,
+        "" => {
+            return Decision::Deny("missing unit, expected deg or rad: ".to_string() + input[0]);
+        }
+        _ => {
+            return Decision::Deny("unknown unit, expected deg or rad: ".to_string() + input[0]);
+        }
+    };
+    match value.parse::<f32>() {
+        Ok(value) => {
+            out.push(Type::F32(value * to_radians));
+        }
+        Err(_) => {
+            return Decision::Deny("got string: ".to_string() + input[0]);
+        }
+    }
+    Decision::Accept(1)
+}
+
 fn any_atom_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
     aslen(input, 1)?;
     for i in input[0].chars() {
@@ -175,6 +1172,90 @@ fn any_bool_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
     Decision::Accept(1)
 }
 
+fn any_bool_loose_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    let value = match input[0].to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => true,
+        "false" | "no" | "off" | "0" => false,
+        _ => return Decision::Deny("expected a boolean-like value: ".to_string() + input[0]),
+    };
+    out.push(Type::Bool(value));
+    Decision::Accept(1)
+}
+
+fn any_char_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    let mut chars = input[0].chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => {
+            out.push(Type::Char(ch));
+            Decision::Accept(1)
+        }
+        (None, _) => Decision::Deny("empty, expected a single character".into()),
+        (Some(_), Some(_)) => Decision::Deny(format!["more than one character: {}", input[0]]),
+    }
+}
+
+fn any_cidr_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    let (addr, prefix) = match input[0].split_once('/') {
+        Some(parts) => parts,
+        None => return Decision::Deny("expected address/prefix: ".to_string() + input[0]),
+    };
+    let addr = match addr.parse::<IpAddr>() {
+        Ok(addr) => addr,
+        Err(err) => return Decision::Deny(format!["{}: {}", err, addr]),
+    };
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    match prefix.parse::<u8>() {
+        Ok(prefix) if prefix <= max => {
+            out.push(Type::Cidr { addr, prefix });
+            Decision::Accept(1)
+        }
+        _ => Decision::Deny(format!["prefix out of range 0..={}: {}", max, prefix]),
+    }
+}
+
+fn any_duration_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    let split = input[0]
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or_else(|| input[0].len());
+    let (number, unit) = input[0].split_at(split);
+    if number.is_empty() {
+        return Decision::Deny(
+            "expected a number followed by ms, s, m, or h: ".to_string() + input[0],
+        );
+    }
+    let number = match number.parse::<u64>() {
+        Ok(number) => number,
+        Err(_) => return Decision::Deny("duration too long: ".to_string() + input[0]),
+    };
+    let duration = match unit {
+        "ms" => Some(Duration::from_millis(number)),
+        "s" => Some(Duration::from_secs(number)),
+        "m" => number.checked_mul(60).map(Duration::from_secs),
+        "h" => number.checked_mul(60 * 60).map(Duration::from_secs),
+        "" => {
+            return Decision::Deny(
+                "missing unit, expected ms, s, m, or h: ".to_string() + input[0],
+            );
+        }
+        _ => {
+            return Decision::Deny(
+                "unknown unit, expected ms, s, m, or h: ".to_string() + input[0],
+            );
+        }
+    };
+    match duration {
+        Some(duration) => {
+            out.push(Type::Duration(duration));
+            Decision::Accept(1)
+        }
+        None => Decision::Deny("duration too long: ".to_string() + input[0]),
+    }
+}
+
 fn any_f32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
     aslen(input, 1)?;
     match input[0].parse::<f32>().ok().map(Type::F32) {
@@ -188,9 +1269,9 @@ fn any_f32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
     Decision::Accept(1)
 }
 
-fn any_i32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+fn any_f64_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
     aslen(input, 1)?;
-    match input[0].parse::<i32>().ok().map(Type::I32) {
+    match input[0].parse::<f64>().ok().map(Type::F64) {
         Some(num) => {
             out.push(num);
         }
@@ -201,15 +1282,14 @@ fn any_i32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
     Decision::Accept(1)
 }
 
-fn any_string_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
-    aslen(input, 1)?;
-    out.push(Type::String(input[0].to_string()));
-    Decision::Accept(1)
-}
-
-fn any_u8_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+fn any_f32_locale_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
     aslen(input, 1)?;
-    match input[0].parse::<u8>().ok().map(Type::U8) {
+    let number = if input[0].matches(',').count() == 1 && !input[0].contains('.') {
+        input[0].replace(',', ".").parse::<f32>()
+    } else {
+        input[0].parse::<f32>()
+    };
+    match number.ok().map(Type::F32) {
         Some(num) => {
             out.push(num);
         }
@@ -220,64 +1300,461 @@ fn any_u8_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
     Decision::Accept(1)
 }
 
-fn any_usize_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+fn any_hex_bytes_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
     aslen(input, 1)?;
-    match input[0].parse::<usize>().ok().map(Type::Usize) {
-        Some(num) => {
-            out.push(num);
-        }
-        None => {
-            return Decision::Deny("got string: ".to_string() + input[0]);
+    let digits = input[0].as_bytes();
+    if digits.len() % 2 == 1 {
+        return Decision::Deny("odd number of hex digits: ".to_string() + input[0]);
+    }
+    let mut bytes = Vec::with_capacity(digits.len() / 2);
+    for (index, pair) in digits.chunks(2).enumerate() {
+        let mut byte = 0;
+        for (offset, digit) in pair.iter().enumerate() {
+            match (*digit as char).to_digit(16) {
+                Some(value) => byte = byte << 4 | value as u8,
+                None => {
+                    return Decision::Deny(format![
+                        "not a hex digit at index {}: {}",
+                        index * 2 + offset,
+                        input[0]
+                    ]);
+                }
+            }
         }
+        bytes.push(byte);
     }
+    out.push(Type::Raw(bytes));
     Decision::Accept(1)
 }
 
-fn ignore_all_function(input: &[&str], _: &mut SVec<Type>) -> Decision<String> {
-    Decision::Accept(input.len())
+fn any_hex_u32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    let digits = input[0]
+        .strip_prefix("0x")
+        .or_else(|| input[0].strip_prefix("0X"))
+        .unwrap_or(input[0]);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Decision::Deny("got string: ".to_string() + input[0]);
+    }
+    match u32::from_str_radix(digits, 16) {
+        Ok(num) => {
+            out.push(Type::U32(num));
+        }
+        Err(_) => {
+            return Decision::Deny("value out of range for u32: ".to_string() + input[0]);
+        }
+    }
+    Decision::Accept(1)
 }
 
-fn many_i32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
-    let mut cnt = 0;
-    for i in input.iter() {
-        if let Some(num) = i.parse::<i32>().ok().map(Type::I32) {
-            aslen(input, cnt + 1)?;
-            out.push(num);
-            cnt += 1;
-        } else {
-            break;
+fn any_i8_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<i8>() {
+        Ok(num) => {
+            out.push(Type::I8(num));
+        }
+        Err(_) => {
+            return Decision::Deny(format!["not a valid i8: {}", input[0]]);
         }
     }
-    Decision::Accept(cnt)
+    Decision::Accept(1)
 }
 
-fn many_string_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
-    aslen(input, input.len())?;
-    let mut cnt = 0;
-    for (idx, i) in input.iter().enumerate() {
-        out.push(Type::String((*i).into()));
-        cnt = idx + 1;
+fn any_i16_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<i16>() {
+        Ok(num) => {
+            out.push(Type::I16(num));
+        }
+        Err(_) => {
+            return Decision::Deny(format!["not a valid i16: {}", input[0]]);
+        }
     }
-    Decision::Accept(cnt)
+    Decision::Accept(1)
 }
 
-fn positive_f32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+fn any_i32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
     aslen(input, 1)?;
-    match input[0].parse::<f32>().ok().map(Type::F32) {
-        Some(Type::F32(val)) if val >= 0.0f32 => {
-            out.push(Type::F32(val));
+    match input[0].parse::<i32>().ok().map(Type::I32) {
+        Some(num) => {
+            out.push(num);
         }
-        _ => {
+        None => {
             return Decision::Deny("got string: ".to_string() + input[0]);
         }
     }
     Decision::Accept(1)
 }
 
-fn two_string_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
-    if input.len() == 1 {
-        return Decision::Deny("expected 1 more string".into());
-    }
+fn any_i64_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<i64>() {
+        Ok(num) => {
+            out.push(Type::I64(num));
+        }
+        Err(err) => {
+            return Decision::Deny(parse_int_denial(&err, "i64", input[0]));
+        }
+    }
+    Decision::Accept(1)
+}
+
+fn any_ipaddr_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<IpAddr>() {
+        Ok(addr) => {
+            out.push(Type::IpAddr(addr));
+            Decision::Accept(1)
+        }
+        Err(err) => Decision::Deny(format!["{}", err]),
+    }
+}
+
+fn any_path_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    out.push(Type::Path(input[0].into()));
+    Decision::Accept(1)
+}
+
+fn any_quoted_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    let mut chars = match input[0].strip_prefix('"') {
+        Some(rest) => rest.chars(),
+        None => return Decision::Deny("expected a quoted string: ".to_string() + input[0]),
+    };
+    let mut string = String::new();
+    loop {
+        match chars.next() {
+            Some('"') if chars.as_str().is_empty() => break,
+            Some('"') => return Decision::Deny("unescaped quote in: ".to_string() + input[0]),
+            Some('\\') => match chars.next() {
+                Some('n') => string.push('\n'),
+                Some('t') => string.push('\t'),
+                Some('"') => string.push('"'),
+                Some('\\') => string.push('\\'),
+                Some(other) => {
+                    return Decision::Deny(format!["invalid escape \\{} in: {}", other, input[0]]);
+                }
+                None => return Decision::Deny("unterminated string: ".to_string() + input[0]),
+            },
+            Some(other) => string.push(other),
+            None => return Decision::Deny("unterminated string: ".to_string() + input[0]),
+        }
+    }
+    out.push(Type::String(string));
+    Decision::Accept(1)
+}
+
+fn any_regex_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match Regex::new(input[0]) {
+        Ok(_) => {
+            out.push(Type::Regex(input[0].into()));
+            Decision::Accept(1)
+        }
+        Err(err) => Decision::Deny(err.to_string()),
+    }
+}
+
+#[cfg(feature = "with-semver")]
+fn any_semver_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match semver::Version::parse(input[0]) {
+        Ok(version) => {
+            out.push(Type::SemVer(version));
+            Decision::Accept(1)
+        }
+        Err(err) => Decision::Deny(format!["{}", err]),
+    }
+}
+
+fn any_socketaddr_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<SocketAddr>() {
+        Ok(addr) => {
+            out.push(Type::SocketAddr(addr));
+            Decision::Accept(1)
+        }
+        Err(err) => Decision::Deny(format!["{}", err]),
+    }
+}
+
+fn any_string_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    out.push(Type::String(input[0].to_string()));
+    Decision::Accept(1)
+}
+
+fn any_u8_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<u8>().ok().map(Type::U8) {
+        Some(num) => {
+            out.push(num);
+        }
+        None => {
+            return Decision::Deny("got string: ".to_string() + input[0]);
+        }
+    }
+    Decision::Accept(1)
+}
+
+fn any_u16_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<u16>() {
+        Ok(num) => {
+            out.push(Type::U16(num));
+        }
+        Err(_) => {
+            return Decision::Deny(format!["not a valid u16: {}", input[0]]);
+        }
+    }
+    Decision::Accept(1)
+}
+
+fn any_u32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<u32>() {
+        Ok(num) => {
+            out.push(Type::U32(num));
+        }
+        Err(_) => {
+            return Decision::Deny(format!["not a valid u32: {}", input[0]]);
+        }
+    }
+    Decision::Accept(1)
+}
+
+fn any_u64_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<u64>() {
+        Ok(num) => {
+            out.push(Type::U64(num));
+        }
+        Err(err) => {
+            return Decision::Deny(parse_int_denial(&err, "u64", input[0]));
+        }
+    }
+    Decision::Accept(1)
+}
+
+fn any_usize_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<usize>().ok().map(Type::Usize) {
+        Some(num) => {
+            out.push(num);
+        }
+        None => {
+            return Decision::Deny("got string: ".to_string() + input[0]);
+        }
+    }
+    Decision::Accept(1)
+}
+
+fn csv_i32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    let mut list = Vec::new();
+    for (index, piece) in input[0].split(',').enumerate() {
+        if piece.is_empty() {
+            return Decision::Deny(format!["empty element at index {}: {}", index, input[0]]);
+        }
+        match piece.parse::<i32>() {
+            Ok(num) => list.push(num),
+            Err(err) => {
+                return Decision::Deny(format![
+                    "element at index {}: {}",
+                    index,
+                    parse_int_denial(&err, "i32", piece)
+                ]);
+            }
+        }
+    }
+    out.push(Type::I32List(list));
+    Decision::Accept(1)
+}
+
+fn existing_path_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    let path = PathBuf::from(input[0]);
+    if !path.exists() {
+        return Decision::Deny("path does not exist: ".to_string() + input[0]);
+    }
+    out.push(Type::Path(path));
+    Decision::Accept(1)
+}
+
+fn finite_f32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<f32>() {
+        Ok(num) if num.is_finite() => {
+            out.push(Type::F32(num));
+            Decision::Accept(1)
+        }
+        Ok(_) => Decision::Deny("value must be finite".into()),
+        Err(_) => Decision::Deny("got string: ".to_string() + input[0]),
+    }
+}
+
+#[cfg(feature = "with-glob")]
+fn glob_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match glob::Pattern::new(input[0]) {
+        Ok(_) => {
+            out.push(Type::Glob(input[0].to_string()));
+            Decision::Accept(1)
+        }
+        Err(err) => Decision::Deny(format!["{}", err]),
+    }
+}
+
+fn ignore_all_function(input: &[&str], _: &mut SVec<Type>) -> Decision<String> {
+    Decision::Accept(input.len())
+}
+
+fn keychord_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    let mut parts = input[0].split('+').collect::<Vec<_>>();
+    let key = parts.pop().unwrap_or_default();
+    if key.is_empty() {
+        return Decision::Deny("missing key: ".to_string() + input[0]);
+    }
+    let mut modifiers = Vec::with_capacity(parts.len());
+    for part in parts {
+        let modifier = match part.to_lowercase().as_str() {
+            "ctrl" | "control" => "Ctrl",
+            "shift" => "Shift",
+            "alt" | "option" => "Alt",
+            "super" | "meta" | "cmd" | "win" => "Super",
+            _ => return Decision::Deny(format!["unknown modifier {:?} in: {}", part, input[0]]),
+        };
+        if modifiers.contains(&modifier) {
+            return Decision::Deny(format!["repeated modifier {} in: {}", modifier, input[0]]);
+        }
+        modifiers.push(modifier);
+    }
+    let mut chars = key.chars();
+    let key = match (chars.next(), chars.next()) {
+        (Some(ch), None) => ch.to_uppercase().collect(),
+        _ if key.chars().all(char::is_alphanumeric) => key.to_string(),
+        _ => return Decision::Deny(format!["invalid key {:?} in: {}", key, input[0]]),
+    };
+    out.push(Type::KeyChord {
+        modifiers: modifiers.into_iter().map(String::from).collect(),
+        key,
+    });
+    Decision::Accept(1)
+}
+
+fn many_i32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    let mut cnt = 0;
+    for i in input.iter() {
+        if let Some(num) = i.parse::<i32>().ok().map(Type::I32) {
+            aslen(input, cnt + 1)?;
+            out.push(num);
+            cnt += 1;
+        } else {
+            break;
+        }
+    }
+    Decision::Accept(cnt)
+}
+
+fn many_f32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    let mut cnt = 0;
+    for i in input.iter() {
+        if let Ok(num) = i.parse::<f32>() {
+            out.push(Type::F32(num));
+            cnt += 1;
+        } else {
+            break;
+        }
+    }
+    Decision::Accept(cnt)
+}
+
+fn many_u8_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    let bytes = input
+        .iter()
+        .map_while(|i| i.parse::<u8>().ok())
+        .collect::<Vec<_>>();
+    let cnt = bytes.len();
+    out.push(Type::Raw(bytes));
+    Decision::Accept(cnt)
+}
+
+fn many_string_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, input.len())?;
+    let mut cnt = 0;
+    for (idx, i) in input.iter().enumerate() {
+        out.push(Type::String((*i).into()));
+        cnt = idx + 1;
+    }
+    Decision::Accept(cnt)
+}
+
+fn nonzero_i32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<i32>() {
+        Ok(0) => Decision::Deny("value must be non-zero".into()),
+        Ok(num) => {
+            out.push(Type::I32(num));
+            Decision::Accept(1)
+        }
+        Err(err) => Decision::Deny(parse_int_denial(&err, "i32", input[0])),
+    }
+}
+
+#[cfg(feature = "with-unicode-normalization")]
+fn normalized_atom_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    use unicode_normalization::UnicodeNormalization;
+    aslen(input, 1)?;
+    if input[0].chars().any(char::is_control) {
+        return Decision::Deny(format!["control character in: {:?}", input[0]]);
+    }
+    out.push(Type::Atom(input[0].chars().nfc().collect()));
+    Decision::Accept(1)
+}
+
+fn positive_f32_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    match input[0].parse::<f32>().ok().map(Type::F32) {
+        Some(Type::F32(val)) if val >= 0.0f32 => {
+            out.push(Type::F32(val));
+        }
+        _ => {
+            return Decision::Deny("got string: ".to_string() + input[0]);
+        }
+    }
+    Decision::Accept(1)
+}
+
+fn quoted_string_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    aslen(input, 1)?;
+    let first = match input[0].strip_prefix('"') {
+        Some(first) => first,
+        None => return Decision::Deny("expected a leading quote, got: ".to_string() + input[0]),
+    };
+    let mut string = String::new();
+    for (idx, token) in input.iter().enumerate() {
+        let token = if idx == 0 {
+            first
+        } else {
+            string.push(' ');
+            token
+        };
+        if let Some(last) = token.strip_suffix('"') {
+            string.push_str(last);
+            out.push(Type::String(string));
+            return Decision::Accept(idx + 1);
+        }
+        string.push_str(token);
+    }
+    Decision::Deny("unterminated quote".into())
+}
+
+fn two_string_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+    if input.len() == 1 {
+        return Decision::Deny("expected 1 more string".into());
+    }
     aslen(input, 2)?;
     out.push(Type::String(input[0].to_string()));
     out.push(Type::String(input[1].to_string()));
@@ -286,6 +1763,15 @@ fn two_string_function(input: &[&str], out: &mut SVec<Type>) -> Decision<String>
 
 // ---
 
+fn parse_int_denial(err: &ParseIntError, type_name: &str, input: &str) -> String {
+    match err.kind() {
+        IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+            format!["value out of range for {}", type_name]
+        }
+        _ => "got string: ".to_string() + input,
+    }
+}
+
 fn aslen(input: &[&str], input_l: usize) -> Result<(), String> {
     if input.len() < input_l {
         Err(format![
@@ -308,15 +1794,1184 @@ mod tests {
         let input = &input.iter().map(|string| &string[..]).collect::<Vec<_>>()[..];
         let out = &mut SVec::new();
 
+        any_angle_function(input, out);
         any_atom_function(input, out);
         any_base64_function(input, out);
         any_bool_function(input, out);
+        any_bool_loose_function(input, out);
+        any_char_function(input, out);
+        any_cidr_function(input, out);
+        any_duration_function(input, out);
         any_f32_function(input, out);
+        any_f32_locale_function(input, out);
+        any_f64_function(input, out);
+        any_hex_bytes_function(input, out);
+        any_hex_u32_function(input, out);
+        any_i8_function(input, out);
+        any_i16_function(input, out);
+        any_i64_function(input, out);
+        any_ipaddr_function(input, out);
+        any_path_function(input, out);
+        any_quoted_function(input, out);
+        any_regex_function(input, out);
+        #[cfg(feature = "with-semver")]
+        any_semver_function(input, out);
+        any_socketaddr_function(input, out);
         any_string_function(input, out);
         any_u8_function(input, out);
+        any_u16_function(input, out);
+        any_u32_function(input, out);
+        any_u64_function(input, out);
+        #[cfg(feature = "with-glob")]
+        glob_function(input, out);
+        csv_i32_function(input, out);
+        existing_path_function(input, out);
+        finite_f32_function(input, out);
         ignore_all_function(input, out);
+        keychord_function(input, out);
+        many_f32_function(input, out);
+        many_u8_function(input, out);
         many_string_function(input, out);
+        nonzero_i32_function(input, out);
+        #[cfg(feature = "with-unicode-normalization")]
+        normalized_atom_function(input, out);
         positive_f32_function(input, out);
+        quoted_string_function(input, out);
         two_string_function(input, out);
     }
+
+    #[quickcheck_macros::quickcheck]
+    fn builtin_invariants(input: Vec<String>) {
+        let input = &input.iter().map(|string| &string[..]).collect::<Vec<_>>()[..];
+        let deciders = [
+            ANY_ANGLE,
+            ANY_ATOM,
+            ANY_BASE64,
+            ANY_BOOL,
+            ANY_BOOL_LOOSE,
+            ANY_CHAR,
+            ANY_CIDR,
+            ANY_DURATION,
+            ANY_F32,
+            ANY_F64,
+            ANY_F32_LOCALE,
+            ANY_HEX_BYTES,
+            ANY_HEX_U32,
+            ANY_I8,
+            ANY_I16,
+            ANY_I32,
+            ANY_I64,
+            ANY_IPADDR,
+            ANY_PATH,
+            ANY_QUOTED,
+            ANY_REGEX,
+            #[cfg(feature = "with-semver")]
+            ANY_SEMVER,
+            ANY_SOCKETADDR,
+            ANY_STRING,
+            ANY_U8,
+            ANY_U16,
+            ANY_U32,
+            ANY_U64,
+            ANY_USIZE,
+            CSV_I32,
+            EXISTING_PATH,
+            FINITE_F32,
+            #[cfg(feature = "with-glob")]
+            GLOB,
+            IGNORE_ALL,
+            KEYCHORD,
+            MANY_F32,
+            MANY_I32,
+            MANY_STRING,
+            MANY_U8,
+            NONZERO_I32,
+            #[cfg(feature = "with-unicode-normalization")]
+            NORMALIZED_ATOM,
+            POSITIVE_F32,
+            QUOTED_STRING,
+            TWO_STRINGS,
+        ];
+        for decider in deciders.iter() {
+            if let Err(violation) = check_decider(decider.unwrap(), input) {
+                panic!("{} on input {:?}", violation, input);
+            }
+        }
+    }
+
+    #[test]
+    fn check_decider_reports_violations() {
+        fn greedy(_: &[&str], _: &mut SVec<Type>) -> Decision<String> {
+            Decision::Accept(3)
+        }
+        fn noisy(input: &[&str], out: &mut SVec<Type>) -> Decision<String> {
+            out.push(Type::Bool(true));
+            out.push(Type::Bool(true));
+            if input.is_empty() {
+                Decision::Deny("empty".into())
+            } else {
+                Decision::Accept(1)
+            }
+        }
+        let greedy = Decider {
+            description: "<greedy>",
+            decider: greedy,
+        };
+        let noisy = Decider {
+            description: "<noisy>",
+            decider: noisy,
+        };
+        assert_eq!(
+            Err("<greedy> accepted 3 of 2 arguments".into()),
+            check_decider(&greedy, &["a", "b"])
+        );
+        assert_eq!(Ok(()), check_decider(&greedy, &["a", "b", "c"]));
+        assert_eq!(
+            Err("<noisy> pushed 2 outputs for 1 arguments".into()),
+            check_decider(&noisy, &["a"])
+        );
+        assert_eq!(
+            Err("<noisy> pushed 2 outputs and denied".into()),
+            check_decider(&noisy, &[])
+        );
+    }
+
+    #[test]
+    fn f64_keeps_precision() {
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Accept(1),
+            any_f64_function(&["51.4769444444"], out)
+        );
+        assert_eq!(Decision::Accept(1), any_f64_function(&["NaN"], out));
+        assert_eq!(Decision::Accept(1), any_f64_function(&["-inf"], out));
+        match &out[..] {
+            [Type::F64(a), Type::F64(b), Type::F64(c)] => {
+                assert_eq!(51.4769444444, *a);
+                assert!(b.is_nan());
+                assert_eq!(f64::NEG_INFINITY, *c);
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("got string: 1,5".into()),
+            any_f64_function(&["1,5"], out)
+        );
+        assert_eq!(3, out.len());
+    }
+
+    #[test]
+    fn angles_in_radians() {
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), any_angle_function(&["90deg"], out));
+        assert_eq!(Decision::Accept(1), any_angle_function(&["1.57rad"], out));
+        assert_eq!(Decision::Accept(1), any_angle_function(&["-180deg"], out));
+        match &out[..] {
+            [Type::F32(a), Type::F32(b), Type::F32(c)] => {
+                assert_eq!(std::f32::consts::FRAC_PI_2, *a);
+                assert_eq!(1.57, *b);
+                assert_eq!(-std::f32::consts::PI, *c);
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("missing unit, expected deg or rad: 90".into()),
+            any_angle_function(&["90"], out)
+        );
+        assert_eq!(
+            Decision::Deny("unknown unit, expected deg or rad: 90grad".into()),
+            any_angle_function(&["90grad"], out)
+        );
+        assert_eq!(
+            Decision::Deny("got string: deg".into()),
+            any_angle_function(&["deg"], out)
+        );
+        assert_eq!(3, out.len());
+    }
+
+    #[test]
+    fn loose_bools() {
+        let out = &mut SVec::new();
+        let matrix = [
+            ("true", true),
+            ("TRUE", true),
+            ("yes", true),
+            ("YeS", true),
+            ("on", true),
+            ("On", true),
+            ("1", true),
+            ("false", false),
+            ("False", false),
+            ("no", false),
+            ("NO", false),
+            ("off", false),
+            ("Off", false),
+            ("0", false),
+        ];
+        for (input, expected) in matrix.iter() {
+            out.clear();
+            assert_eq!(Decision::Accept(1), any_bool_loose_function(&[input], out));
+            assert_eq!(&[Type::Bool(*expected)][..], &out[..], "{}", input);
+        }
+        for input in ["y", "2", "enabled", ""].iter() {
+            assert_eq!(
+                Decision::Deny("expected a boolean-like value: ".to_string() + input),
+                any_bool_loose_function(&[input], out)
+            );
+        }
+        assert_eq!(
+            Decision::Deny("got string: yes".into()),
+            any_bool_function(&["yes"], out)
+        );
+    }
+
+    #[test]
+    fn durations() {
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), any_duration_function(&["1500ms"], out));
+        assert_eq!(Decision::Accept(1), any_duration_function(&["2s"], out));
+        assert_eq!(Decision::Accept(1), any_duration_function(&["3m"], out));
+        assert_eq!(Decision::Accept(1), any_duration_function(&["1h"], out));
+        assert_eq!(
+            &[
+                Type::Duration(Duration::from_millis(1500)),
+                Type::Duration(Duration::from_secs(2)),
+                Type::Duration(Duration::from_secs(180)),
+                Type::Duration(Duration::from_secs(3600)),
+            ][..],
+            &out[..]
+        );
+        assert_eq!(
+            Decision::Deny("unknown unit, expected ms, s, m, or h: 5d".into()),
+            any_duration_function(&["5d"], out)
+        );
+        assert_eq!(
+            Decision::Deny("missing unit, expected ms, s, m, or h: 5".into()),
+            any_duration_function(&["5"], out)
+        );
+        assert_eq!(
+            Decision::Deny("expected a number followed by ms, s, m, or h: ms".into()),
+            any_duration_function(&["ms"], out)
+        );
+        assert_eq!(
+            Decision::Deny("expected a number followed by ms, s, m, or h: -5s".into()),
+            any_duration_function(&["-5s"], out)
+        );
+        assert_eq!(
+            Decision::Deny("duration too long: 18446744073709551615h".into()),
+            any_duration_function(&["18446744073709551615h"], out)
+        );
+        assert_eq!(
+            Decision::Deny("duration too long: 99999999999999999999s".into()),
+            any_duration_function(&["99999999999999999999s"], out)
+        );
+        assert_eq!(4, out.len());
+    }
+
+    #[test]
+    fn single_chars() {
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), any_char_function(&["a"], out));
+        assert_eq!(Decision::Accept(1), any_char_function(&["é"], out));
+        match &out[..] {
+            [Type::Char('a'), Type::Char('é')] => {}
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("empty, expected a single character".into()),
+            any_char_function(&[""], out)
+        );
+        assert_eq!(
+            Decision::Deny("more than one character: ab".into()),
+            any_char_function(&["ab"], out)
+        );
+        assert_eq!(
+            Decision::Deny("more than one character: e\u{301}".into()),
+            any_char_function(&["e\u{301}"], out)
+        );
+        assert_eq!(2, out.len());
+    }
+
+    #[test]
+    fn network_addresses() {
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Accept(1),
+            any_ipaddr_function(&["127.0.0.1"], out)
+        );
+        assert_eq!(Decision::Accept(1), any_ipaddr_function(&["::1"], out));
+        assert_eq!(
+            Decision::Accept(1),
+            any_socketaddr_function(&["127.0.0.1:8080"], out)
+        );
+        assert_eq!(
+            Decision::Accept(1),
+            any_socketaddr_function(&["[::1]:8080"], out)
+        );
+        match &out[..] {
+            [Type::IpAddr(a), Type::IpAddr(b), Type::SocketAddr(c), Type::SocketAddr(d)] => {
+                assert_eq!(IpAddr::from([127, 0, 0, 1]), *a);
+                assert!(b.is_loopback());
+                assert_eq!(8080, c.port());
+                assert!(d.is_ipv6());
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        let error = "127.0.0.256".parse::<IpAddr>().unwrap_err().to_string();
+        assert_eq!(
+            Decision::Deny(error),
+            any_ipaddr_function(&["127.0.0.256"], out)
+        );
+        let error = "127.0.0.1".parse::<SocketAddr>().unwrap_err().to_string();
+        assert_eq!(
+            Decision::Deny(error),
+            any_socketaddr_function(&["127.0.0.1"], out)
+        );
+        assert_eq!(4, out.len());
+    }
+
+    #[test]
+    fn cidr_blocks() {
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), any_cidr_function(&["10.0.0.0/8"], out));
+        assert_eq!(Decision::Accept(1), any_cidr_function(&["fe80::/10"], out));
+        assert_eq!(
+            &[
+                Type::Cidr {
+                    addr: [10, 0, 0, 0].into(),
+                    prefix: 8
+                },
+                Type::Cidr {
+                    addr: [0xfe80, 0, 0, 0, 0, 0, 0, 0].into(),
+                    prefix: 10
+                },
+            ][..],
+            &out[..]
+        );
+        assert_eq!(
+            Decision::Deny("prefix out of range 0..=32: 33".into()),
+            any_cidr_function(&["10.0.0.0/33"], out)
+        );
+        assert_eq!(Decision::Accept(1), any_cidr_function(&["::/128"], out));
+        assert_eq!(
+            Decision::Deny("prefix out of range 0..=128: -1".into()),
+            any_cidr_function(&["::/-1"], out)
+        );
+        let error = "10.0.0".parse::<IpAddr>().unwrap_err().to_string();
+        assert_eq!(
+            Decision::Deny(format!["{}: 10.0.0", error]),
+            any_cidr_function(&["10.0.0/8"], out)
+        );
+        assert_eq!(
+            Decision::Deny("expected address/prefix: 10.0.0.0".into()),
+            any_cidr_function(&["10.0.0.0"], out)
+        );
+        assert_eq!(3, out.len());
+    }
+
+    #[test]
+    fn regex_patterns() {
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), any_regex_function(&["^[a-z]+$"], out));
+        assert_eq!(&[Type::Regex("^[a-z]+$".into())][..], &out[..]);
+        let unclosed = "[a-z";
+        let error = Regex::new(unclosed).unwrap_err().to_string();
+        assert_eq!(Decision::Deny(error), any_regex_function(&[unclosed], out));
+        assert_eq!(1, out.len());
+    }
+
+    #[test]
+    fn keychords() {
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), keychord_function(&["Ctrl+K"], out));
+        assert_eq!(
+            Decision::Accept(1),
+            keychord_function(&["ctrl+shift+q"], out)
+        );
+        assert_eq!(Decision::Accept(1), keychord_function(&["F5"], out));
+        assert_eq!(
+            &[
+                Type::KeyChord {
+                    modifiers: vec!["Ctrl".into()],
+                    key: "K".into()
+                },
+                Type::KeyChord {
+                    modifiers: vec!["Ctrl".into(), "Shift".into()],
+                    key: "Q".into()
+                },
+                Type::KeyChord {
+                    modifiers: vec![],
+                    key: "F5".into()
+                },
+            ][..],
+            &out[..]
+        );
+        assert_eq!(
+            Decision::Deny("missing key: Ctrl+".into()),
+            keychord_function(&["Ctrl+"], out)
+        );
+        assert_eq!(
+            Decision::Deny("unknown modifier \"Hyper\" in: Hyper+K".into()),
+            keychord_function(&["Hyper+K"], out)
+        );
+        assert_eq!(
+            Decision::Deny("repeated modifier Ctrl in: Ctrl+Control+K".into()),
+            keychord_function(&["Ctrl+Control+K"], out)
+        );
+        assert_eq!(
+            Decision::Deny("invalid key \"Page-Up\" in: Shift+Page-Up".into()),
+            keychord_function(&["Shift+Page-Up"], out)
+        );
+        assert_eq!(3, out.len());
+    }
+
+    #[test]
+    fn paths() {
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Accept(1),
+            any_path_function(&["does/not/exist"], out)
+        );
+        assert_eq!(Decision::Accept(1), existing_path_function(&["src"], out));
+        match &out[..] {
+            [Type::Path(a), Type::Path(b)] => {
+                assert_eq!(PathBuf::from("does/not/exist"), *a);
+                assert!(b.is_dir());
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("path does not exist: does/not/exist".into()),
+            existing_path_function(&["does/not/exist"], out)
+        );
+        assert_eq!(2, out.len());
+    }
+
+    #[test]
+    fn quoted_with_escapes() {
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Accept(1),
+            any_quoted_function(&[r#""a\tb\n\"c\"\\""#, "rest"], out)
+        );
+        assert_eq!(Decision::Accept(1), any_quoted_function(&[r#""""#], out));
+        match &out[..] {
+            [Type::String(a), Type::String(b)] => {
+                assert_eq!("a\tb\n\"c\"\\", a);
+                assert_eq!("", b);
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        let denials = [
+            ("abc", "expected a quoted string: abc"),
+            (r#"""#, r#"unterminated string: ""#),
+            (r#""abc"#, r#"unterminated string: "abc"#),
+            (r#""abc\""#, r#"unterminated string: "abc\""#),
+            (r#""a"b""#, r#"unescaped quote in: "a"b""#),
+            (r#""a\xb""#, r#"invalid escape \x in: "a\xb""#),
+        ];
+        for (token, reason) in &denials {
+            assert_eq!(
+                Decision::Deny((*reason).into()),
+                any_quoted_function(&[token], out)
+            );
+        }
+        assert_eq!(2, out.len());
+    }
+
+    #[test]
+    fn hex_bytes() {
+        let out = &mut SVec::new();
+        let bytes = vec![0x00, 0x7f, 0x80, 0xde, 0xad, 0xbe, 0xef, 0xff];
+        let lower = bytes
+            .iter()
+            .map(|byte| format!["{:02x}", byte])
+            .collect::<String>();
+        assert_eq!("007f80deadbeefff", lower);
+        assert_eq!(Decision::Accept(1), any_hex_bytes_function(&[&lower], out));
+        assert_eq!(
+            Decision::Accept(1),
+            any_hex_bytes_function(&[&lower.to_uppercase()], out)
+        );
+        assert_eq!(Decision::Accept(1), any_hex_bytes_function(&[""], out));
+        assert_eq!(
+            &[
+                Type::Raw(bytes.clone()),
+                Type::Raw(bytes),
+                Type::Raw(vec![])
+            ][..],
+            &out[..]
+        );
+        assert_eq!(
+            Decision::Deny("odd number of hex digits: abc".into()),
+            any_hex_bytes_function(&["abc"], out)
+        );
+        assert_eq!(
+            Decision::Deny("not a hex digit at index 3: 00fg".into()),
+            any_hex_bytes_function(&["00fg"], out)
+        );
+        assert_eq!(
+            Decision::Deny("not a hex digit at index 0: é00".into()),
+            any_hex_bytes_function(&["é00"], out)
+        );
+        assert_eq!(3, out.len());
+    }
+
+    #[test]
+    fn hex_u32() {
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Accept(1),
+            any_hex_u32_function(&["0xDEADBEEF"], out)
+        );
+        assert_eq!(Decision::Accept(1), any_hex_u32_function(&["0X10"], out));
+        assert_eq!(Decision::Accept(1), any_hex_u32_function(&["ff"], out));
+        match &out[..] {
+            [Type::U32(a), Type::U32(b), Type::U32(c)] => {
+                assert_eq!(0xDEAD_BEEF, *a);
+                assert_eq!(16, *b);
+                assert_eq!(255, *c);
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        for token in &["", "0x", "0xfg", "+ff", "0x-1"] {
+            assert_eq!(
+                Decision::Deny("got string: ".to_string() + token),
+                any_hex_u32_function(&[token], out)
+            );
+        }
+        assert_eq!(
+            Decision::Deny("value out of range for u32: 0x100000000".into()),
+            any_hex_u32_function(&["0x100000000"], out)
+        );
+        assert_eq!(3, out.len());
+    }
+
+    #[test]
+    fn small_width_integers() {
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), any_u16_function(&["65535"], out));
+        assert_eq!(Decision::Accept(1), any_u32_function(&["4294967295"], out));
+        assert_eq!(Decision::Accept(1), any_i16_function(&["-32768"], out));
+        assert_eq!(Decision::Accept(1), any_i8_function(&["127"], out));
+        match &out[..] {
+            [Type::U16(a), Type::U32(b), Type::I16(c), Type::I8(d)] => {
+                assert_eq!(u16::MAX, *a);
+                assert_eq!(u32::MAX, *b);
+                assert_eq!(i16::MIN, *c);
+                assert_eq!(i8::MAX, *d);
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("not a valid u16: 65536".into()),
+            any_u16_function(&["65536"], out)
+        );
+        assert_eq!(
+            Decision::Deny("not a valid u32: -1".into()),
+            any_u32_function(&["-1"], out)
+        );
+        assert_eq!(
+            Decision::Deny("not a valid i16: 32768".into()),
+            any_i16_function(&["32768"], out)
+        );
+        assert_eq!(
+            Decision::Deny("not a valid i8: x".into()),
+            any_i8_function(&["x"], out)
+        );
+        assert_eq!(4, out.len());
+    }
+
+    #[test]
+    fn csv_i32() {
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), csv_i32_function(&["1,2,3"], out));
+        assert_eq!(Decision::Accept(1), csv_i32_function(&["-7"], out));
+        assert_eq!(
+            &[Type::I32List(vec![1, 2, 3]), Type::I32List(vec![-7])][..],
+            &out[..]
+        );
+        assert_eq!(
+            Decision::Deny("empty element at index 2: 1,2,,4".into()),
+            csv_i32_function(&["1,2,,4"], out)
+        );
+        assert_eq!(
+            Decision::Deny("empty element at index 2: 1,2,".into()),
+            csv_i32_function(&["1,2,"], out)
+        );
+        assert_eq!(
+            Decision::Deny("element at index 1: got string: x".into()),
+            csv_i32_function(&["1,x"], out)
+        );
+        assert_eq!(
+            Decision::Deny("element at index 0: value out of range for i32".into()),
+            csv_i32_function(&["3000000000"], out)
+        );
+        assert_eq!(2, out.len());
+    }
+
+    #[test]
+    fn nonzero_i32() {
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Deny("value must be non-zero".into()),
+            nonzero_i32_function(&["0"], out)
+        );
+        assert_eq!(
+            Decision::Deny("value must be non-zero".into()),
+            nonzero_i32_function(&["-0"], out)
+        );
+        assert_eq!(Decision::Accept(1), nonzero_i32_function(&["-3"], out));
+        assert_eq!(Decision::Accept(1), nonzero_i32_function(&["7"], out));
+        assert_eq!(&[Type::I32(-3), Type::I32(7)][..], &out[..]);
+        assert_eq!(
+            Decision::Deny("got string: x".into()),
+            nonzero_i32_function(&["x"], out)
+        );
+        assert_eq!(2, out.len());
+    }
+
+    #[test]
+    fn i64_and_u64() {
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Accept(1),
+            any_i64_function(&["-1581589852000"], out)
+        );
+        assert_eq!(
+            Decision::Accept(1),
+            any_u64_function(&["18446744073709551615"], out)
+        );
+        match &out[..] {
+            [Type::I64(a), Type::U64(b)] => {
+                assert_eq!(-1_581_589_852_000, *a);
+                assert_eq!(u64::MAX, *b);
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("value out of range for i64".into()),
+            any_i64_function(&["9223372036854775808"], out)
+        );
+        assert_eq!(
+            Decision::Deny("value out of range for i64".into()),
+            any_i64_function(&["-9223372036854775809"], out)
+        );
+        assert_eq!(
+            Decision::Deny("value out of range for u64".into()),
+            any_u64_function(&["18446744073709551616"], out)
+        );
+        assert_eq!(
+            Decision::Deny("got string: -1".into()),
+            any_u64_function(&["-1"], out)
+        );
+        assert_eq!(
+            Decision::Deny("got string: x".into()),
+            any_i64_function(&["x"], out)
+        );
+        assert_eq!(2, out.len());
+    }
+
+    #[test]
+    fn finite_f32() {
+        let out = &mut SVec::new();
+        for input in ["inf", "-inf", "NaN", "1e39"].iter() {
+            assert_eq!(
+                Decision::Deny("value must be finite".into()),
+                finite_f32_function(&[input], out)
+            );
+        }
+        assert_eq!(Decision::Accept(1), finite_f32_function(&["-2.5"], out));
+        assert_eq!(&[Type::F32(-2.5)][..], &out[..]);
+        assert_eq!(
+            Decision::Deny("got string: x".into()),
+            finite_f32_function(&["x"], out)
+        );
+        assert_eq!(Decision::Accept(1), any_f32_function(&["inf"], out));
+    }
+
+    #[test]
+    fn f32_with_comma_separator() {
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), any_f32_locale_function(&["1,5"], out));
+        assert_eq!(Decision::Accept(1), any_f32_locale_function(&["1.5"], out));
+        assert_eq!(
+            Decision::Accept(1),
+            any_f32_locale_function(&["-2,25"], out)
+        );
+        match &out[..] {
+            [Type::F32(a), Type::F32(b), Type::F32(c)] => {
+                assert_eq!(1.5, *a);
+                assert_eq!(1.5, *b);
+                assert_eq!(-2.25, *c);
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("got string: 1,5,0".into()),
+            any_f32_locale_function(&["1,5,0"], out)
+        );
+        assert_eq!(
+            Decision::Deny("got string: 1,5.0".into()),
+            any_f32_locale_function(&["1,5.0"], out)
+        );
+        assert_eq!(
+            Decision::Deny("got string: 1,5".into()),
+            any_f32_function(&["1,5"], out)
+        );
+    }
+
+    #[test]
+    fn from_str_custom_type() {
+        #[derive(Debug, PartialEq)]
+        enum Difficulty {
+            Easy,
+            Normal,
+        }
+
+        impl FromStr for Difficulty {
+            type Err = ();
+            fn from_str(input: &str) -> Result<Self, Self::Err> {
+                match input {
+                    "easy" => Ok(Difficulty::Easy),
+                    "normal" => Ok(Difficulty::Normal),
+                    _ => Err(()),
+                }
+            }
+        }
+
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Accept(1),
+            from_str_function::<Difficulty>(&["normal"], out)
+        );
+        match &out[..] {
+            [Type::Custom(any)] => {
+                assert_eq!(Some(&Difficulty::Normal), any.downcast_ref::<Difficulty>());
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("got string: hard".into()),
+            from_str_function::<Difficulty>(&["hard"], out)
+        );
+    }
+
+    #[test]
+    fn one_of_reports_all_denials() {
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Accept(1),
+            one_of(&[ANY_I32, ANY_BOOL], &["false"], out)
+        );
+        assert_eq!(
+            Decision::Accept(1),
+            one_of(&[ANY_I32, ANY_BOOL], &["12"], out)
+        );
+        assert_eq!(
+            Decision::Deny(
+                "tried <i32>: got string: alpha; tried <true/false>: got string: alpha".into()
+            ),
+            one_of(&[ANY_I32, ANY_BOOL], &["alpha"], out)
+        );
+        match &out[..] {
+            [Type::Bool(false), Type::I32(12)] => {}
+            _ => panic!("Wrong output: {:?}", out),
+        }
+    }
+
+    #[test]
+    fn newtype_decider() {
+        #[derive(Debug, PartialEq)]
+        struct EntityId(u32);
+
+        decider_for_newtype!(EntityId, u32, "<entity-id>");
+
+        let decider = EntityId::DECIDER.unwrap();
+        assert_eq!("<entity-id>", decider.description);
+
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), (decider.decider)(&["42", "x"], out));
+        assert_eq!(Some(&EntityId(42)), EntityId::from_type(&out[0]));
+        assert_eq!(None, EntityId::from_type(&Type::U8(42)));
+        assert_eq!(
+            Decision::Deny("got string: -1".into()),
+            (decider.decider)(&["-1"], out)
+        );
+        assert_eq!(
+            Decision::Deny("Too few elements: [], length: 0, expected: 1".into()),
+            (decider.decider)(&[], out)
+        );
+    }
+
+    #[test]
+    fn regex_match_decider() {
+        let decider = regex_match("[a-z]+[0-9]?").unwrap();
+        assert_eq!("<[a-z]+[0-9]?>", decider.description);
+
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), (decider.decider)(&["alice2"], out));
+        match &out[..] {
+            [Type::String(nick)] => assert_eq!("alice2", nick),
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("does not match [a-z]+[0-9]?".into()),
+            (decider.decider)(&["alice22"], out)
+        );
+        assert_eq!(
+            Decision::Deny("does not match [a-z]+[0-9]?".into()),
+            (decider.decider)(&["Alice"], out)
+        );
+        assert_eq!(1, out.len());
+
+        let other = regex_match("[0-9]+").unwrap();
+        assert_eq!(Decision::Accept(1), (other.decider)(&["123"], out));
+        assert_eq!(Decision::Accept(1), (decider.decider)(&["bob"], out));
+
+        assert!(regex_match("[a-z").is_err());
+    }
+
+    #[test]
+    fn matching_decider() {
+        let square = matching("[A-H][1-8]").unwrap();
+        assert_eq!("<[A-H][1-8]>", square.description);
+
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), (square.decider)(&["E4", "E5"], out));
+        match &out[..] {
+            [Type::Atom(square)] => assert_eq!("E4", square),
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("does not match [A-H][1-8]".into()),
+            (square.decider)(&["E45"], out)
+        );
+        assert_eq!(
+            Decision::Deny("does not match [A-H][1-8]".into()),
+            (square.decider)(&["xE4"], out)
+        );
+        assert_eq!(1, out.len());
+
+        assert!(matching("[A-H").is_err());
+    }
+
+    #[test]
+    fn bounded_deciders() {
+        let die = bounded_i32(1, 6).unwrap();
+        assert_eq!("<i32 1..=6>", die.description);
+        let unit = bounded_f32(0.0, 1.0).unwrap();
+        assert_eq!("<f32 0..=1>", unit.description);
+
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), (die.decider)(&["1"], out));
+        assert_eq!(Decision::Accept(1), (die.decider)(&["6"], out));
+        assert_eq!(Decision::Accept(1), (unit.decider)(&["0.5"], out));
+        match &out[..] {
+            [Type::I32(1), Type::I32(6), Type::F32(half)] => assert_eq!(0.5, *half),
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("value out of range 1..=6: 7".into()),
+            (die.decider)(&["7"], out)
+        );
+        assert_eq!(
+            Decision::Deny("got string: x".into()),
+            (die.decider)(&["x"], out)
+        );
+        assert_eq!(
+            Decision::Deny("value out of range 0..=1: NaN".into()),
+            (unit.decider)(&["NaN"], out)
+        );
+        assert_eq!(3, out.len());
+
+        let other = bounded_i32(-10, -5).unwrap();
+        assert_eq!(Decision::Accept(1), (other.decider)(&["-7"], out));
+        assert_eq!(Decision::Accept(1), (die.decider)(&["3"], out));
+
+        assert!(bounded_i32(6, 1).is_err());
+        assert!(bounded_f32(f32::NAN, 1.0).is_err());
+    }
+
+    #[test]
+    fn many_bounded_deciders() {
+        let point = many_i32_bounded(2, 3).unwrap();
+        assert_eq!("<i32> ... 2..=3", point.description);
+        let names = many_string_bounded(1, 2).unwrap();
+        assert_eq!("<string> ... 1..=2", names.description);
+
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Deny("expected at least 2 arguments, got 1".into()),
+            (point.decider)(&["1", "x"], out)
+        );
+        assert_eq!(Decision::Accept(2), (point.decider)(&["1", "2", "x"], out));
+        assert_eq!(Decision::Accept(3), (point.decider)(&["1", "2", "3"], out));
+        assert_eq!(
+            Decision::Deny("expected at most 3 arguments, got 4".into()),
+            (point.decider)(&["1", "2", "3", "4"], out)
+        );
+        assert_eq!(5, out.len());
+
+        out.clear();
+        assert_eq!(
+            Decision::Deny("expected at least 1 arguments, got 0".into()),
+            (names.decider)(&[], out)
+        );
+        assert_eq!(Decision::Accept(2), (names.decider)(&["a", "b"], out));
+        assert_eq!(
+            Decision::Deny("expected at most 2 arguments, got 3".into()),
+            (names.decider)(&["a", "b", "c"], out)
+        );
+        assert_eq!(
+            &[Type::String("a".into()), Type::String("b".into())][..],
+            &out[..]
+        );
+
+        assert!(many_i32_bounded(3, 2).is_err());
+    }
+
+    #[test]
+    fn builtin_predicates_are_sorted_and_complete() {
+        let predicates = builtin_predicates();
+        assert!(predicates.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(predicates.iter().all(|(_, decider)| decider.is_some()));
+
+        let names: Vec<_> = predicates.iter().map(|(name, _)| *name).collect();
+        for name in &[
+            "ANY_ANGLE",
+            "ANY_ATOM",
+            "ANY_BASE64",
+            "ANY_BOOL",
+            "ANY_BOOL_LOOSE",
+            "ANY_CHAR",
+            "ANY_CIDR",
+            "ANY_DURATION",
+            "ANY_F32",
+            "ANY_F32_LOCALE",
+            "ANY_F64",
+            "ANY_HEX_BYTES",
+            "ANY_HEX_U32",
+            "ANY_I16",
+            "ANY_I32",
+            "ANY_I64",
+            "ANY_I8",
+            "ANY_IPADDR",
+            "ANY_PATH",
+            "ANY_QUOTED",
+            "ANY_REGEX",
+            "ANY_SOCKETADDR",
+            "ANY_STRING",
+            "ANY_U16",
+            "ANY_U32",
+            "ANY_U64",
+            "ANY_U8",
+            "ANY_USIZE",
+            "CSV_I32",
+            "EXISTING_PATH",
+            "FINITE_F32",
+            "IGNORE_ALL",
+            "KEYCHORD",
+            "MANY_F32",
+            "MANY_I32",
+            "MANY_STRING",
+            "MANY_U8",
+            "NONZERO_I32",
+            "POSITIVE_F32",
+            "QUOTED_STRING",
+            "TWO_STRINGS",
+        ] {
+            assert!(names.contains(name), "missing {}", name);
+        }
+        #[cfg(feature = "with-glob")]
+        assert!(names.contains(&"GLOB"));
+
+        let i32_description = predicates
+            .iter()
+            .find(|(name, _)| *name == "ANY_I32")
+            .and_then(|(_, decider)| *decider)
+            .map(|decider| decider.description);
+        assert_eq!(Some("<i32>"), i32_description);
+    }
+
+    #[test]
+    fn exactly_decider() {
+        let pair = exactly(2, ANY_I32.unwrap()).unwrap();
+        assert_eq!("<i32> <i32>", pair.description);
+
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(2), (pair.decider)(&["5", "6", "7"], out));
+        assert_eq!(&[Type::I32(5), Type::I32(6)][..], &out[..]);
+        assert_eq!(
+            Decision::Deny("<i32> at index 1: got string: abc".into()),
+            (pair.decider)(&["5", "abc"], out)
+        );
+        assert_eq!(
+            Decision::Deny("<i32> at index 1: Too few elements: [], length: 0, expected: 1".into()),
+            (pair.decider)(&["5"], out)
+        );
+        assert_eq!(2, out.len());
+
+        let words = exactly(2, QUOTED_STRING.unwrap()).unwrap();
+        assert_eq!(
+            Decision::Accept(3),
+            (words.decider)(&["\"a", "b\"", "\"c\""], out)
+        );
+
+        assert!(exactly(0, ANY_I32.unwrap()).is_err());
+    }
+
+    #[test]
+    fn optional_decider() {
+        let level = optional(ANY_I32.unwrap()).unwrap();
+        assert_eq!("[<i32>]", level.description);
+
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), (level.decider)(&["5", "6"], out));
+        assert_eq!(&[Type::I32(5)][..], &out[..]);
+        assert_eq!(Decision::Accept(0), (level.decider)(&["abc"], out));
+        assert_eq!(Decision::Accept(0), (level.decider)(&[], out));
+        assert_eq!(1, out.len());
+
+        let pair = optional(Box::leak(Box::new(exactly(2, ANY_I32.unwrap()).unwrap()))).unwrap();
+        assert_eq!(Decision::Accept(0), (pair.decider)(&["5", "abc"], out));
+        assert_eq!(1, out.len());
+    }
+
+    #[test]
+    fn one_of_literals_decider() {
+        let mode = one_of_literals(&["fast", "slow", "off"]).unwrap();
+        assert_eq!("(fast|slow|off)", mode.description);
+
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), (mode.decider)(&["off", "fast"], out));
+        match &out[..] {
+            [Type::Atom(mode)] => assert_eq!("off", mode),
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("expected one of: fast, slow, off".into()),
+            (mode.decider)(&["Fast"], out)
+        );
+        assert_eq!(1, out.len());
+
+        let other = one_of_literals(&["on"]).unwrap();
+        assert_eq!(Decision::Accept(1), (other.decider)(&["on"], out));
+        assert_eq!(Decision::Accept(1), (mode.decider)(&["slow"], out));
+
+        assert!(one_of_literals(&[]).is_err());
+    }
+
+    #[test]
+    fn runtime_decider_slots_are_reused() {
+        fn decider() -> DeciderClosure {
+            Arc::new(|_, _| Decision::Accept(0))
+        }
+
+        let mut slots = vec![];
+        for slot in 0..RUNTIME_DECIDER_LIMIT {
+            assert_eq!(Ok(slot), claim_slot(&mut slots, decider()));
+        }
+        assert_eq!(
+            Err("Limit of 256 runtime deciders reached".into()),
+            claim_slot(&mut slots, decider())
+        );
+
+        slots[3] = None;
+        assert_eq!(Ok(3), claim_slot(&mut slots, decider()));
+        assert!(claim_slot(&mut slots, decider()).is_err());
+    }
+
+    #[cfg(feature = "with-glob")]
+    #[test]
+    fn glob() {
+        let out = &mut SVec::new();
+        assert_eq!(Decision::Accept(1), glob_function(&["*.png"], out));
+        match &out[..] {
+            [Type::Glob(pattern)] => assert_eq!("*.png", pattern),
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        if let Decision::Accept(_) = glob_function(&["[a-z.png"], out) {
+            panic!("Accepted a malformed pattern");
+        }
+        assert_eq!(1, out.len());
+    }
+
+    #[cfg(feature = "with-semver")]
+    #[test]
+    fn semver() {
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Accept(1),
+            any_semver_function(&["1.2.3-beta.1"], out)
+        );
+        match &out[..] {
+            [Type::SemVer(version)] => {
+                assert_eq!((1, 2, 3), (version.major, version.minor, version.patch));
+                assert!(version.is_prerelease());
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        if let Decision::Accept(_) = any_semver_function(&["1.2"], out) {
+            panic!("Accepted a malformed version");
+        }
+        assert_eq!(1, out.len());
+    }
+
+    #[cfg(feature = "with-unicode-normalization")]
+    #[test]
+    fn normalized_atom() {
+        let out = &mut SVec::new();
+        let decomposed = "cafe\u{301}";
+        let composed = "caf\u{e9}";
+        assert_ne!(decomposed, composed);
+        assert_eq!(
+            Decision::Accept(1),
+            normalized_atom_function(&[decomposed], out)
+        );
+        assert_eq!(
+            Decision::Accept(1),
+            normalized_atom_function(&[composed], out)
+        );
+        assert_eq!(out[0], out[1]);
+        assert_eq!(Type::Atom(composed.into()), out[0]);
+        assert_eq!(
+            Decision::Deny("control character in: \"a\\u{7}b\"".into()),
+            normalized_atom_function(&["a\u{7}b"], out)
+        );
+        assert_eq!(2, out.len());
+    }
+
+    #[test]
+    fn quoted_string() {
+        let out = &mut SVec::new();
+        assert_eq!(
+            Decision::Accept(2),
+            quoted_string_function(&["\"hello", "world\"", "rest"], out)
+        );
+        assert_eq!(
+            Decision::Accept(1),
+            quoted_string_function(&["\"hello\""], out)
+        );
+        assert_eq!(
+            Decision::Accept(3),
+            quoted_string_function(&["\"", "a", "\""], out)
+        );
+        match &out[..] {
+            [Type::String(a), Type::String(b), Type::String(c)] => {
+                assert_eq!("hello world", a);
+                assert_eq!("hello", b);
+                assert_eq!(" a ", c);
+            }
+            _ => panic!("Wrong output: {:?}", out),
+        }
+        assert_eq!(
+            Decision::Deny("unterminated quote".into()),
+            quoted_string_function(&["\"hello", "world"], out)
+        );
+        assert_eq!(
+            Decision::Deny("expected a leading quote, got: hello".into()),
+            quoted_string_function(&["hello", "world\""], out)
+        );
+    }
 }
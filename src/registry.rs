@@ -0,0 +1,111 @@
+//! Atomically reloadable command registry
+//!
+//! A [SharedRegistry] holds a [Mapping] that can be replaced while other threads are in the
+//! middle of a lookup. Readers take a snapshot of the current mapping, which stays valid for as
+//! long as they hold on to it, even if a writer publishes a new mapping in the meantime.
+use crate::types::Type;
+use arc_swap::ArcSwap;
+use cmdmat::{Mapping, RegError, Spec};
+use std::sync::Arc;
+
+/// A command registry that can be swapped atomically under concurrent readers
+pub struct SharedRegistry<C> {
+    current: ArcSwap<Mapping<'static, Type, String, C>>,
+}
+
+impl<C> SharedRegistry<C> {
+    /// Create a new shared registry from an existing mapping.
+    pub fn new(mapping: Mapping<'static, Type, String, C>) -> Self {
+        Self {
+            current: ArcSwap::from(Arc::new(mapping)),
+        }
+    }
+
+    /// Create a new shared registry containing the given command specifications.
+    pub fn from_specs(specs: &[Spec<'_, 'static, Type, String, C>]) -> Result<Self, RegError> {
+        let mut mapping = Mapping::default();
+        mapping.register_many(specs)?;
+        Ok(Self::new(mapping))
+    }
+
+    /// Get a snapshot of the current mapping. The snapshot is unaffected by later calls to
+    /// [SharedRegistry::publish].
+    pub fn snapshot(&self) -> Arc<Mapping<'static, Type, String, C>> {
+        self.current.load_full()
+    }
+
+    /// Atomically replace the current mapping.
+    pub fn publish(&self, mapping: Mapping<'static, Type, String, C>) {
+        self.current.store(Arc::new(mapping));
+    }
+
+    /// Atomically replace the current mapping with one containing the given command
+    /// specifications. The current mapping is left untouched if registration fails.
+    pub fn publish_specs(
+        &self,
+        specs: &[Spec<'_, 'static, Type, String, C>],
+    ) -> Result<(), RegError> {
+        let mut mapping = Mapping::default();
+        mapping.register_many(specs)?;
+        self.publish(mapping);
+        Ok(())
+    }
+}
+
+impl<C> Default for SharedRegistry<C> {
+    fn default() -> Self {
+        Self::new(Mapping::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::predicates::*;
+    use std::thread;
+
+    fn old(_: &mut u32, _: &[Type]) -> Result<String, String> {
+        Ok("old".into())
+    }
+
+    fn new(_: &mut u32, _: &[Type]) -> Result<String, String> {
+        Ok("new".into())
+    }
+
+    #[test]
+    fn reload_while_reader_holds_snapshot() {
+        let registry = Arc::new(SharedRegistry::from_specs(&[(&[("call", None)], old)]).unwrap());
+
+        let snapshot = registry.snapshot();
+
+        let writer = {
+            let registry = registry.clone();
+            thread::spawn(move || {
+                registry
+                    .publish_specs(&[(&[("call", None)], new), (&[("plugin", ANY_I32)], new)])
+                    .unwrap();
+            })
+        };
+        writer.join().unwrap();
+
+        let (handler, args) = snapshot.lookup(&["call"]).unwrap();
+        assert_eq!(Ok("old".into()), handler(&mut 0, &args));
+        assert!(snapshot.lookup(&["plugin", "1"]).is_err());
+
+        let current = registry.snapshot();
+        let (handler, args) = current.lookup(&["call"]).unwrap();
+        assert_eq!(Ok("new".into()), handler(&mut 0, &args));
+        assert!(current.lookup(&["plugin", "1"]).is_ok());
+    }
+
+    #[test]
+    fn failed_publish_keeps_current() {
+        let registry = SharedRegistry::from_specs(&[(&[("call", None)], old)]).unwrap();
+        assert_eq!(
+            Err(RegError::FinalizerAlreadyExists),
+            registry.publish_specs(&[(&[("call", None)], new), (&[("call", None)], new)])
+        );
+        let (handler, args) = registry.snapshot().lookup(&["call"]).unwrap();
+        assert_eq!(Ok("old".into()), handler(&mut 0, &args));
+    }
+}
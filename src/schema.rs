@@ -0,0 +1,160 @@
+//! Structured description of a command registry
+//!
+//! A [Schema] describes every command path registered in an [Evaluator](crate::Evaluator),
+//! along with the descriptions of the deciders along that path and whether the path can be run.
+//! This is useful for documentation pipelines and for generating client code or forms.
+//!
+//! With the `with-serde` feature enabled the schema implements `serde::Serialize` and
+//! `serde::Deserialize`.
+use crate::{evaluator::is_hidden, types::Type};
+use cmdmat::Mapping;
+use std::collections::BTreeMap;
+
+/// Description of an entire command registry
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Schema {
+    /// All command paths in the registry, sorted by their literals
+    pub commands: Vec<SchemaCommand>,
+}
+
+/// A single path through the command tree
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaCommand {
+    /// The nodes from the root to this command
+    pub path: Vec<SchemaNode>,
+    /// Whether a handler exists at the end of this path
+    pub finalizer: bool,
+    /// Metadata of this command, see [crate::Evaluator::register_with_meta]
+    pub meta: BTreeMap<String, String>,
+}
+
+/// A single node in a command path
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaNode {
+    /// The literal that matches this node
+    pub literal: String,
+    /// Description of the decider for this node, if any
+    pub decider: Option<String>,
+}
+
+impl Schema {
+    pub(crate) fn from_mapping<C>(
+        mapping: &Mapping<'_, Type, String, C>,
+        hidden: &[&Mapping<'_, Type, String, C>],
+    ) -> Self {
+        let mut commands = vec![];
+        collect(mapping, hidden, &mut vec![], &mut commands);
+        commands.sort_by(|a, b| {
+            let a = a.path.iter().map(|node| &node.literal[..]);
+            let b = b.path.iter().map(|node| &node.literal[..]);
+            a.cmp(b)
+        });
+        Self { commands }
+    }
+
+    /// Find the command matching the given sequence of literals.
+    pub fn get(&self, literals: &[&str]) -> Option<&SchemaCommand> {
+        self.commands.iter().find(|command| {
+            command.path.len() == literals.len()
+                && command
+                    .path
+                    .iter()
+                    .zip(literals)
+                    .all(|(node, literal)| node.literal == *literal)
+        })
+    }
+}
+
+fn collect<C>(
+    mapping: &Mapping<'_, Type, String, C>,
+    hidden: &[&Mapping<'_, Type, String, C>],
+    path: &mut Vec<SchemaNode>,
+    commands: &mut Vec<SchemaCommand>,
+) {
+    for (key, entry) in mapping.iter() {
+        if is_hidden(entry, hidden) {
+            continue;
+        }
+        path.push(SchemaNode {
+            literal: (*key).into(),
+            decider: entry.decider().map(|decider| decider.description.into()),
+        });
+        commands.push(SchemaCommand {
+            path: path.clone(),
+            finalizer: entry.finalizer().is_some(),
+            meta: BTreeMap::new(),
+        });
+        collect(entry, hidden, path, commands);
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{predicates::*, types::Type, Evaluator};
+
+    #[test]
+    fn schema_contains_registered_commands() {
+        let mut eval = Evaluator::new(());
+
+        fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        eval.register((&[("call", ANY_F32), ("something", None)], handler))
+            .unwrap();
+        eval.register((&[("log", None), ("level", ANY_ATOM)], handler))
+            .unwrap();
+
+        let schema = eval.export_schema();
+        assert_eq!(4, schema.commands.len());
+
+        let call = schema.get(&["call"]).unwrap();
+        assert_eq!(Some("<f32>".into()), call.path[0].decider);
+        assert!(!call.finalizer);
+
+        let something = schema.get(&["call", "something"]).unwrap();
+        assert_eq!(None, something.path[1].decider);
+        assert!(something.finalizer);
+
+        let level = schema.get(&["log", "level"]).unwrap();
+        assert_eq!(Some("<atom>".into()), level.path[1].decider);
+        assert!(level.finalizer);
+
+        assert!(schema.get(&["level"]).is_none());
+    }
+
+    #[test]
+    fn metadata_round_trips() {
+        let mut eval = Evaluator::new(());
+
+        fn handler(_: &mut (), _: &[Type]) -> Result<String, String> {
+            Ok("".into())
+        }
+
+        let meta = [("category", "world"), ("since", "0.4.0")];
+        eval.register_with_meta(
+            (&[("spawn", ANY_ATOM)], handler),
+            meta.iter().cloned().collect(),
+        )
+        .unwrap();
+        eval.register((&[("log", None)], handler)).unwrap();
+
+        assert_eq!(
+            Some(&"world"),
+            eval.meta(&["spawn"]).unwrap().get("category")
+        );
+        assert!(eval.meta(&["log"]).is_none());
+
+        let schema = eval.export_schema();
+        let spawn = schema.get(&["spawn"]).unwrap();
+        assert_eq!(2, spawn.meta.len());
+        assert_eq!("world", spawn.meta["category"]);
+        assert_eq!("0.4.0", spawn.meta["since"]);
+        assert!(schema.get(&["log"]).unwrap().meta.is_empty());
+        assert_eq!(Ok(()), eval.check_consistency());
+    }
+}
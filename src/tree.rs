@@ -0,0 +1,100 @@
+//! Recursively parsed statements, for tools such as syntax highlighters
+use metac::{Data, Evaluate, ParseError};
+
+/// A fully parsed statement or part of one, see [parse_tree]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tree {
+    /// A string that contains no whitespace
+    Atom(String),
+    /// A string created using `(#...)`, which is not parsed any further
+    String(String),
+    /// A statement or a nested command, containing its parts in order
+    Command(Vec<Tree>),
+}
+
+/// Parse a statement and all commands nested within it
+///
+/// The result is always a [Tree::Command] containing the parts of the statement. Unlike
+/// evaluating, which parses nested commands only once they are needed, this fails if any
+/// nested command fails to parse.
+///
+/// ```
+/// use gameshell::{parse_tree, Tree};
+///
+/// assert_eq!(
+///     Ok(Tree::Command(vec![
+///         Tree::Atom("say".into()),
+///         Tree::Command(vec![Tree::Atom("get".into()), Tree::Atom("motd".into())]),
+///     ])),
+///     parse_tree("say (get motd)")
+/// );
+/// ```
+pub fn parse_tree(statement: &str) -> Result<Tree, ParseError> {
+    Ok(Tree::Command(TreeBuilder.interpret_single(statement)??))
+}
+
+struct TreeBuilder;
+
+impl Evaluate<Result<Vec<Tree>, ParseError>> for TreeBuilder {
+    fn evaluate(&mut self, statement: &[Data]) -> Result<Vec<Tree>, ParseError> {
+        statement
+            .iter()
+            .map(|data| match data {
+                Data::Atom(string) => Ok(Tree::Atom((*string).into())),
+                Data::Command(string) => match string.strip_prefix('#') {
+                    Some(string) => Ok(Tree::String(string.into())),
+                    None => parse_tree(string),
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atom(string: &str) -> Tree {
+        Tree::Atom(string.into())
+    }
+
+    #[test]
+    fn nested_statement() {
+        assert_eq!(
+            Ok(Tree::Command(vec![
+                atom("a"),
+                Tree::Command(vec![
+                    atom("b"),
+                    Tree::Command(vec![atom("c"), atom("d")]),
+                    Tree::String("raw (x)".into()),
+                    atom("e"),
+                ]),
+                atom("f"),
+            ])),
+            parse_tree("a (b (c d) (#raw (x)) e) f")
+        );
+    }
+
+    #[test]
+    fn touching_and_multiline() {
+        assert_eq!(
+            Ok(Tree::Command(vec![
+                atom("a"),
+                Tree::Command(vec![atom("b")]),
+                Tree::Command(vec![atom("c"), atom("d")]),
+            ])),
+            parse_tree("a(b)(c\nd)")
+        );
+    }
+
+    #[test]
+    fn errors_in_nested_commands() {
+        assert_eq!(Err(ParseError::NothingToParse), parse_tree(""));
+        assert_eq!(Err(ParseError::NothingToParse), parse_tree("a ()"));
+        assert_eq!(Err(ParseError::DanglingLeftParenthesis), parse_tree("a (b"));
+        assert_eq!(
+            Err(ParseError::PrematureRightParenthesis),
+            parse_tree("a b)")
+        );
+    }
+}
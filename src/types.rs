@@ -1,32 +1,345 @@
 //! Basic types used by the gameshell for input to handlers
+use std::{
+    any::Any,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
 /// Basic types used by the gameshell for input to handlers
+///
+/// Equality follows the contained values, so `F32` and `F64` use float comparison: `NaN` is never
+/// equal to anything, including itself, and `0.0 == -0.0`. `Custom` values are equal only when they
+/// share the same allocation.
+///
+/// With the `with-serde` feature enabled, `Type` implements `serde::Serialize` and
+/// `serde::Deserialize`. `Raw` is serialized as base64 and `SemVer` as its textual form. `Custom`
+/// values cannot be serialized.
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug)]
 pub enum Type {
     /// A string that contains no whitespace
     Atom(String),
     /// A `true` or `false` value
     Bool(bool),
+    /// A single unicode scalar value
+    Char(char),
+    /// An IP network, see [crate::predicates::ANY_CIDR]
+    Cidr {
+        /// The network address
+        addr: IpAddr,
+        /// The number of leading bits in the network mask
+        prefix: u8,
+    },
     /// A string which was enclosed by parentheses, may contain parentheses itself
     Command(String),
+    /// A user-defined value, see [crate::predicates::from_str_decider]
+    #[cfg_attr(feature = "with-serde", serde(skip))]
+    Custom(Arc<dyn Any + Send + Sync>),
+    /// A span of time, see [crate::predicates::ANY_DURATION]
+    Duration(Duration),
     /// A 32-bit floating point value
     F32(f32),
+    /// A 64-bit floating point value
+    F64(f64),
+    /// A file glob pattern, not expanded, see [crate::predicates::GLOB]
+    #[cfg(feature = "with-glob")]
+    Glob(String),
+    /// An 8-bit signed integer value
+    I8(i8),
+    /// A 16-bit signed integer value
+    I16(i16),
     /// A 32-bit signed integer value
     I32(i32),
+    /// A list of 32-bit signed integer values, see [crate::predicates::CSV_I32]
+    I32List(Vec<i32>),
+    /// A 64-bit signed integer value
+    I64(i64),
+    /// An IPv4 or IPv6 address
+    IpAddr(IpAddr),
+    /// A key with modifiers held down, see [crate::predicates::KEYCHORD]
+    KeyChord {
+        /// The modifiers in the order given, such as `Ctrl` and `Shift`
+        modifiers: Vec<String>,
+        /// The key pressed while holding the modifiers
+        key: String,
+    },
+    /// A filesystem path, see [crate::predicates::ANY_PATH]
+    Path(PathBuf),
     /// Raw binary data
-    Raw(Vec<u8>),
+    Raw(#[cfg_attr(feature = "with-serde", serde(with = "base64_bytes"))] Vec<u8>),
+    /// A regular expression that is known to compile, see [crate::predicates::ANY_REGEX]
+    Regex(String),
+    /// A semantic version
+    #[cfg(feature = "with-semver")]
+    SemVer(#[cfg_attr(feature = "with-serde", serde(with = "semver_text"))] semver::Version),
+    /// An IP address with a port
+    SocketAddr(SocketAddr),
     /// A string, can be created using (#)
     String(String),
     /// An unsigned 8-bit value
     U8(u8),
+    /// An unsigned 16-bit value
+    U16(u16),
+    /// An unsigned 32-bit value
+    U32(u32),
+    /// An unsigned 64-bit value
+    U64(u64),
     /// An unsigned size type
     Usize(usize),
 }
 
+/// The kind of a [Type], without its value
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TypeKind {
+    /// See [Type::Atom]
+    Atom,
+    /// See [Type::Bool]
+    Bool,
+    /// See [Type::Char]
+    Char,
+    /// See [Type::Cidr]
+    Cidr,
+    /// See [Type::Command]
+    Command,
+    /// See [Type::Custom]
+    Custom,
+    /// See [Type::Duration]
+    Duration,
+    /// See [Type::F32]
+    F32,
+    /// See [Type::F64]
+    F64,
+    /// See [Type::Glob]
+    #[cfg(feature = "with-glob")]
+    Glob,
+    /// See [Type::I8]
+    I8,
+    /// See [Type::I16]
+    I16,
+    /// See [Type::I32]
+    I32,
+    /// See [Type::I32List]
+    I32List,
+    /// See [Type::I64]
+    I64,
+    /// See [Type::IpAddr]
+    IpAddr,
+    /// See [Type::KeyChord]
+    KeyChord,
+    /// See [Type::Path]
+    Path,
+    /// See [Type::Raw]
+    Raw,
+    /// See [Type::Regex]
+    Regex,
+    /// See [Type::SemVer]
+    #[cfg(feature = "with-semver")]
+    SemVer,
+    /// See [Type::SocketAddr]
+    SocketAddr,
+    /// See [Type::String]
+    String,
+    /// See [Type::U8]
+    U8,
+    /// See [Type::U16]
+    U16,
+    /// See [Type::U32]
+    U32,
+    /// See [Type::U64]
+    U64,
+    /// See [Type::Usize]
+    Usize,
+}
+
+impl Type {
+    /// Get the kind of this type.
+    pub fn kind(&self) -> TypeKind {
+        match self {
+            Type::Atom(_) => TypeKind::Atom,
+            Type::Bool(_) => TypeKind::Bool,
+            Type::Char(_) => TypeKind::Char,
+            Type::Cidr { .. } => TypeKind::Cidr,
+            Type::Command(_) => TypeKind::Command,
+            Type::Custom(_) => TypeKind::Custom,
+            Type::Duration(_) => TypeKind::Duration,
+            Type::F32(_) => TypeKind::F32,
+            Type::F64(_) => TypeKind::F64,
+            #[cfg(feature = "with-glob")]
+            Type::Glob(_) => TypeKind::Glob,
+            Type::I8(_) => TypeKind::I8,
+            Type::I16(_) => TypeKind::I16,
+            Type::I32(_) => TypeKind::I32,
+            Type::I32List(_) => TypeKind::I32List,
+            Type::I64(_) => TypeKind::I64,
+            Type::IpAddr(_) => TypeKind::IpAddr,
+            Type::KeyChord { .. } => TypeKind::KeyChord,
+            Type::Path(_) => TypeKind::Path,
+            Type::Raw(_) => TypeKind::Raw,
+            Type::Regex(_) => TypeKind::Regex,
+            #[cfg(feature = "with-semver")]
+            Type::SemVer(_) => TypeKind::SemVer,
+            Type::SocketAddr(_) => TypeKind::SocketAddr,
+            Type::String(_) => TypeKind::String,
+            Type::U8(_) => TypeKind::U8,
+            Type::U16(_) => TypeKind::U16,
+            Type::U32(_) => TypeKind::U32,
+            Type::U64(_) => TypeKind::U64,
+            Type::Usize(_) => TypeKind::Usize,
+        }
+    }
+}
+
+/// Renders the value in the form its decider accepts, so `F32(1.5)` is shown as `1.5` and
+/// `Duration` in the largest whole unit, as in `1500ms` or `2h`. `Atom` and `String` are shown
+/// verbatim, `Raw` as base64, and `Custom` values, which are opaque, as `<custom>`.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Atom(value) | Type::Regex(value) | Type::String(value) => write![f, "{}", value],
+            Type::Bool(value) => write![f, "{}", value],
+            Type::Char(value) => write![f, "{}", value],
+            Type::Cidr { addr, prefix } => write![f, "{}/{}", addr, prefix],
+            Type::Command(value) => write![f, "({})", value],
+            Type::Custom(_) => write![f, "<custom>"],
+            Type::Duration(value) => write_duration(f, *value),
+            Type::F32(value) => write![f, "{}", value],
+            Type::F64(value) => write![f, "{}", value],
+            #[cfg(feature = "with-glob")]
+            Type::Glob(value) => write![f, "{}", value],
+            Type::I8(value) => write![f, "{}", value],
+            Type::I16(value) => write![f, "{}", value],
+            Type::I32(value) => write![f, "{}", value],
+            Type::I32List(values) => {
+                let values: Vec<_> = values.iter().map(i32::to_string).collect();
+                write![f, "{}", values.join(",")]
+            }
+            Type::I64(value) => write![f, "{}", value],
+            Type::IpAddr(value) => write![f, "{}", value],
+            Type::KeyChord { modifiers, key } => {
+                for modifier in modifiers {
+                    write![f, "{}+", modifier]?;
+                }
+                write![f, "{}", key]
+            }
+            Type::Path(value) => write![f, "{}", value.display()],
+            Type::Raw(value) => write![f, "{}", base64::encode(value)],
+            #[cfg(feature = "with-semver")]
+            Type::SemVer(value) => write![f, "{}", value],
+            Type::SocketAddr(value) => write![f, "{}", value],
+            Type::U8(value) => write![f, "{}", value],
+            Type::U16(value) => write![f, "{}", value],
+            Type::U32(value) => write![f, "{}", value],
+            Type::U64(value) => write![f, "{}", value],
+            Type::Usize(value) => write![f, "{}", value],
+        }
+    }
+}
+
+fn write_duration(f: &mut fmt::Formatter, duration: Duration) -> fmt::Result {
+    if duration.subsec_nanos() != duration.subsec_millis() * 1_000_000 {
+        return write![f, "{:?}", duration];
+    }
+    let millis = duration.as_millis();
+    let (value, unit) = [(3_600_000, "h"), (60_000, "m"), (1000, "s")]
+        .iter()
+        .find(|(size, _)| millis > 0 && millis / size * size == millis)
+        .map_or((millis, "ms"), |(size, unit)| (millis / size, *unit));
+    write![f, "{}{}", value, unit]
+}
+
+impl PartialEq for Type {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Type::Atom(a), Type::Atom(b)) => a == b,
+            (Type::Bool(a), Type::Bool(b)) => a == b,
+            (Type::Char(a), Type::Char(b)) => a == b,
+            (
+                Type::Cidr { addr, prefix },
+                Type::Cidr {
+                    addr: other_addr,
+                    prefix: other_prefix,
+                },
+            ) => addr == other_addr && prefix == other_prefix,
+            (Type::Command(a), Type::Command(b)) => a == b,
+            (Type::Custom(a), Type::Custom(b)) => Arc::ptr_eq(a, b),
+            (Type::Duration(a), Type::Duration(b)) => a == b,
+            (Type::F32(a), Type::F32(b)) => a == b,
+            (Type::F64(a), Type::F64(b)) => a == b,
+            #[cfg(feature = "with-glob")]
+            (Type::Glob(a), Type::Glob(b)) => a == b,
+            (Type::I8(a), Type::I8(b)) => a == b,
+            (Type::I16(a), Type::I16(b)) => a == b,
+            (Type::I32(a), Type::I32(b)) => a == b,
+            (Type::I32List(a), Type::I32List(b)) => a == b,
+            (Type::I64(a), Type::I64(b)) => a == b,
+            (Type::IpAddr(a), Type::IpAddr(b)) => a == b,
+            (
+                Type::KeyChord { modifiers, key },
+                Type::KeyChord {
+                    modifiers: other_modifiers,
+                    key: other_key,
+                },
+            ) => modifiers == other_modifiers && key == other_key,
+            (Type::Path(a), Type::Path(b)) => a == b,
+            (Type::Raw(a), Type::Raw(b)) => a == b,
+            (Type::Regex(a), Type::Regex(b)) => a == b,
+            #[cfg(feature = "with-semver")]
+            (Type::SemVer(a), Type::SemVer(b)) => a == b,
+            (Type::SocketAddr(a), Type::SocketAddr(b)) => a == b,
+            (Type::String(a), Type::String(b)) => a == b,
+            (Type::U8(a), Type::U8(b)) => a == b,
+            (Type::U16(a), Type::U16(b)) => a == b,
+            (Type::U32(a), Type::U32(b)) => a == b,
+            (Type::U64(a), Type::U64(b)) => a == b,
+            (Type::Usize(a), Type::Usize(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+// Serializes `Type::Raw` as base64 to keep it compact and JSON-friendly
+#[cfg(feature = "with-serde")]
+mod base64_bytes {
+    pub fn serialize<S: serde::Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let text = <String as serde::Deserialize>::deserialize(deserializer)?;
+        base64::decode(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+// Serializes `Type::SemVer` as its textual form, as in `1.2.3`
+#[cfg(all(feature = "with-serde", feature = "with-semver"))]
+mod semver_text {
+    pub fn serialize<S: serde::Serializer>(
+        version: &semver::Version,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(version)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<semver::Version, D::Error> {
+        let text = <String as serde::Deserialize>::deserialize(deserializer)?;
+        semver::Version::parse(&text).map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(any(test, feature = "with-quickcheck"))]
 impl quickcheck::Arbitrary for Type {
     fn arbitrary<G: quickcheck::Gen>(g: &mut G) -> Self {
         use rand::Rng;
-        match g.gen_range(0, 9) {
+        let variants =
+            25 + cfg!(feature = "with-semver") as usize + cfg!(feature = "with-glob") as usize;
+        match g.gen_range(0, variants) {
             0 => Type::Atom(String::arbitrary(g)),
             1 => Type::Bool(bool::arbitrary(g)),
             2 => Type::Command(String::arbitrary(g)),
@@ -36,7 +349,289 @@ impl quickcheck::Arbitrary for Type {
             6 => Type::String(String::arbitrary(g)),
             7 => Type::U8(u8::arbitrary(g)),
             8 => Type::Usize(usize::arbitrary(g)),
+            9 => Type::I64(i64::arbitrary(g)),
+            10 => Type::U64(u64::arbitrary(g)),
+            11 => Type::F64(f64::arbitrary(g)),
+            12 => Type::U16(u16::arbitrary(g)),
+            13 => Type::U32(u32::arbitrary(g)),
+            14 => Type::I16(i16::arbitrary(g)),
+            15 => Type::I8(i8::arbitrary(g)),
+            16 => Type::Char(char::arbitrary(g)),
+            17 => Type::IpAddr(IpAddr::arbitrary(g)),
+            18 => Type::SocketAddr(SocketAddr::arbitrary(g)),
+            19 => Type::Path(PathBuf::arbitrary(g)),
+            20 => {
+                let addr = IpAddr::arbitrary(g);
+                let max = if addr.is_ipv4() { 32 } else { 128 };
+                Type::Cidr {
+                    addr,
+                    prefix: u8::arbitrary(g) % (max + 1),
+                }
+            }
+            21 => Type::Regex(String::arbitrary(g)),
+            22 => Type::I32List(Vec::<i32>::arbitrary(g)),
+            23 => Type::Duration(Duration::from_millis(u64::arbitrary(g))),
+            24 => Type::KeyChord {
+                modifiers: Vec::<String>::arbitrary(g),
+                key: String::arbitrary(g),
+            },
+            #[cfg(feature = "with-semver")]
+            25 => Type::SemVer(semver::Version::new(
+                u64::arbitrary(g),
+                u64::arbitrary(g),
+                u64::arbitrary(g),
+            )),
+            #[cfg(feature = "with-glob")]
+            n if n == variants - 1 => Type::Glob(String::arbitrary(g)),
             _ => unimplemented![],
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "with-serde")]
+    #[test]
+    fn serde_round_trip_of_each_variant() {
+        let values = vec![
+            Type::Atom("a".into()),
+            Type::Bool(true),
+            Type::Char('a'),
+            Type::Cidr {
+                addr: [10, 0, 0, 0].into(),
+                prefix: 8,
+            },
+            Type::Command("add 1 2".into()),
+            Type::Duration(Duration::from_millis(1500)),
+            Type::F32(1.5),
+            Type::F64(-2.25),
+            #[cfg(feature = "with-glob")]
+            Type::Glob("*.png".into()),
+            Type::I8(-8),
+            Type::I16(-16),
+            Type::I32(-32),
+            Type::I32List(vec![1, 2, 3]),
+            Type::I64(-64),
+            Type::IpAddr([127, 0, 0, 1].into()),
+            Type::KeyChord {
+                modifiers: vec!["Ctrl".into()],
+                key: "K".into(),
+            },
+            Type::Path("saves/slot1.sav".into()),
+            Type::Raw(vec![1, 2]),
+            Type::Regex("[a-z]+".into()),
+            #[cfg(feature = "with-semver")]
+            Type::SemVer(semver::Version::new(1, 2, 3)),
+            Type::SocketAddr(([127, 0, 0, 1], 8080).into()),
+            Type::String("a b".into()),
+            Type::U8(8),
+            Type::U16(16),
+            Type::U32(32),
+            Type::U64(u64::MAX),
+            Type::Usize(64),
+        ];
+        for value in values {
+            let json = serde_json::to_string(&value).unwrap();
+            assert_eq!(
+                value,
+                serde_json::from_str::<Type>(&json).unwrap(),
+                "{}",
+                json
+            );
+        }
+
+        assert_eq!(
+            r#"{"Raw":"AQI="}"#,
+            serde_json::to_string(&Type::Raw(vec![1, 2])).unwrap()
+        );
+        assert!(serde_json::to_string(&Type::Custom(Arc::new(0u8))).is_err());
+    }
+
+    #[test]
+    fn display_round_trips_through_deciders() {
+        use crate::predicates::*;
+        use cmdmat::{Decision, SVec};
+
+        for (decider, input) in &[
+            (ANY_F32, "1.5"),
+            (ANY_I32, "-42"),
+            (ANY_BASE64, "aGVsbG8="),
+            (ANY_CIDR, "10.0.0.0/8"),
+            (ANY_DURATION, "1500ms"),
+            (CSV_I32, "1,2,3"),
+            (KEYCHORD, "Ctrl+Shift+K"),
+        ] {
+            let out = &mut SVec::new();
+            assert_eq!(
+                Decision::Accept(1),
+                (decider.unwrap().decider)(&[input], out)
+            );
+            assert_eq!(*input, out[0].to_string());
+        }
+    }
+
+    #[test]
+    fn display_of_each_variant() {
+        let net: IpAddr = [10, 0, 0, 0].into();
+        let chord = Type::KeyChord {
+            modifiers: vec!["Ctrl".into(), "Shift".into()],
+            key: "K".into(),
+        };
+        assert_eq!("abc", Type::Atom("abc".into()).to_string());
+        assert_eq!("a b", Type::String("a b".into()).to_string());
+        assert_eq!("(add 1 2)", Type::Command("add 1 2".into()).to_string());
+        assert_eq!(
+            "10.0.0.0/8",
+            Type::Cidr {
+                addr: net,
+                prefix: 8
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "1500ms",
+            Type::Duration(Duration::from_millis(1500)).to_string()
+        );
+        assert_eq!("2h", Type::Duration(Duration::from_secs(7200)).to_string());
+        assert_eq!("90s", Type::Duration(Duration::from_secs(90)).to_string());
+        assert_eq!("0ms", Type::Duration(Duration::from_secs(0)).to_string());
+        assert_eq!("1.5", Type::F32(1.5).to_string());
+        assert_eq!("-3", Type::I32(-3).to_string());
+        assert_eq!("1,2,3", Type::I32List(vec![1, 2, 3]).to_string());
+        assert_eq!("Ctrl+Shift+K", chord.to_string());
+        assert_eq!("AQI=", Type::Raw(vec![1, 2]).to_string());
+        assert_eq!("<custom>", Type::Custom(Arc::new(0u8)).to_string());
+    }
+
+    #[test]
+    fn equality_of_each_variant() {
+        assert_eq!(Type::Atom("a".into()), Type::Atom("a".into()));
+        assert_ne!(Type::Atom("a".into()), Type::Atom("b".into()));
+        assert_eq!(Type::Bool(true), Type::Bool(true));
+        assert_ne!(Type::Bool(true), Type::Bool(false));
+        assert_eq!(Type::Char('a'), Type::Char('a'));
+        assert_ne!(Type::Char('a'), Type::Char('b'));
+        let net: IpAddr = [10, 0, 0, 0].into();
+        assert_eq!(
+            Type::Cidr {
+                addr: net,
+                prefix: 8
+            },
+            Type::Cidr {
+                addr: net,
+                prefix: 8
+            }
+        );
+        assert_ne!(
+            Type::Cidr {
+                addr: net,
+                prefix: 8
+            },
+            Type::Cidr {
+                addr: net,
+                prefix: 16
+            }
+        );
+        assert_eq!(Type::Command("a".into()), Type::Command("a".into()));
+        assert_ne!(Type::Command("a".into()), Type::Command("b".into()));
+        assert_eq!(
+            Type::Duration(Duration::from_secs(2)),
+            Type::Duration(Duration::from_millis(2000))
+        );
+        assert_ne!(
+            Type::Duration(Duration::from_secs(2)),
+            Type::Duration(Duration::from_secs(3))
+        );
+        assert_eq!(Type::F32(1.5), Type::F32(1.5));
+        assert_ne!(Type::F32(1.5), Type::F32(2.5));
+        assert_eq!(Type::F64(51.4769444444), Type::F64(51.4769444444));
+        assert_ne!(Type::F64(51.4769444444), Type::F64(51.4769444445));
+        assert_eq!(Type::I8(-5), Type::I8(-5));
+        assert_ne!(Type::I8(-5), Type::I8(5));
+        assert_eq!(Type::I16(-300), Type::I16(-300));
+        assert_ne!(Type::I16(-300), Type::I16(300));
+        assert_eq!(Type::I32(5), Type::I32(5));
+        assert_ne!(Type::I32(5), Type::I32(-5));
+        assert_eq!(Type::I32List(vec![1, 2]), Type::I32List(vec![1, 2]));
+        assert_ne!(Type::I32List(vec![1, 2]), Type::I32List(vec![1]));
+        assert_eq!(Type::Path("a/b".into()), Type::Path("a/b/".into()));
+        assert_ne!(Type::Path("a/b".into()), Type::Path("a/c".into()));
+        assert_eq!(Type::Raw(vec![1, 2]), Type::Raw(vec![1, 2]));
+        assert_ne!(Type::Raw(vec![1, 2]), Type::Raw(vec![2, 1]));
+        assert_eq!(Type::Regex("a+".into()), Type::Regex("a+".into()));
+        assert_ne!(Type::Regex("a+".into()), Type::Regex("a*".into()));
+        assert_eq!(Type::String("a".into()), Type::String("a".into()));
+        assert_ne!(Type::String("a".into()), Type::String("b".into()));
+        assert_eq!(Type::I64(1 << 40), Type::I64(1 << 40));
+        assert_ne!(Type::I64(1 << 40), Type::I64(-1 << 40));
+        let ip: IpAddr = [127, 0, 0, 1].into();
+        assert_eq!(Type::IpAddr(ip), Type::IpAddr(ip));
+        assert_ne!(Type::IpAddr(ip), Type::IpAddr([127, 0, 0, 2].into()));
+        let chord = |modifiers: &[&str], key: &str| Type::KeyChord {
+            modifiers: modifiers
+                .iter()
+                .map(|modifier| modifier.to_string())
+                .collect(),
+            key: key.into(),
+        };
+        assert_eq!(chord(&["Ctrl"], "K"), chord(&["Ctrl"], "K"));
+        assert_ne!(chord(&["Ctrl"], "K"), chord(&["Alt"], "K"));
+        assert_ne!(chord(&["Ctrl"], "K"), chord(&["Ctrl"], "L"));
+        assert_eq!(
+            Type::SocketAddr((ip, 80).into()),
+            Type::SocketAddr((ip, 80).into())
+        );
+        assert_ne!(
+            Type::SocketAddr((ip, 80).into()),
+            Type::SocketAddr((ip, 81).into())
+        );
+        assert_eq!(Type::U8(1), Type::U8(1));
+        assert_ne!(Type::U8(1), Type::U8(2));
+        assert_eq!(Type::U16(300), Type::U16(300));
+        assert_ne!(Type::U16(300), Type::U16(301));
+        assert_eq!(Type::U32(70000), Type::U32(70000));
+        assert_ne!(Type::U32(70000), Type::U32(70001));
+        assert_eq!(Type::U64(1 << 40), Type::U64(1 << 40));
+        assert_ne!(Type::U64(1 << 40), Type::U64(1 << 41));
+        assert_eq!(Type::Usize(1), Type::Usize(1));
+        assert_ne!(Type::Usize(1), Type::Usize(2));
+    }
+
+    #[test]
+    fn different_variants_are_unequal() {
+        assert_ne!(Type::Atom("a".into()), Type::String("a".into()));
+        assert_ne!(Type::Atom("a".into()), Type::Command("a".into()));
+        assert_ne!(Type::U8(1), Type::Usize(1));
+        assert_ne!(Type::U16(1), Type::U32(1));
+        assert_ne!(Type::I8(1), Type::I16(1));
+        assert_ne!(Type::I32(1), Type::F32(1.0));
+    }
+
+    #[test]
+    fn float_equality_follows_ieee() {
+        assert_ne!(Type::F32(f32::NAN), Type::F32(f32::NAN));
+        assert_eq!(Type::F32(0.0), Type::F32(-0.0));
+        assert_ne!(Type::F64(f64::NAN), Type::F64(f64::NAN));
+        assert_eq!(Type::F64(0.0), Type::F64(-0.0));
+    }
+
+    #[test]
+    fn custom_equality_is_by_identity() {
+        let value: Arc<dyn Any + Send + Sync> = Arc::new(3u8);
+        assert_eq!(Type::Custom(value.clone()), Type::Custom(value));
+        assert_ne!(Type::Custom(Arc::new(3u8)), Type::Custom(Arc::new(3u8)));
+    }
+
+    #[cfg(feature = "with-semver")]
+    #[test]
+    fn semver_equality() {
+        let version = semver::Version::new(1, 2, 3);
+        assert_eq!(Type::SemVer(version.clone()), Type::SemVer(version));
+        assert_ne!(
+            Type::SemVer(semver::Version::new(1, 2, 3)),
+            Type::SemVer(semver::Version::new(1, 2, 4))
+        );
+    }
+}